@@ -0,0 +1,9 @@
+//! Loads arbitrary bytes as an IFC.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ifc_fuzz::load_and_walk(data);
+});
@@ -0,0 +1,15 @@
+//! Corrupts a small, valid IFC and loads it. Random bytes rarely get past the file header, so
+//! this reaches much more of the reader than the `load` target.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|edits: Vec<(u32, u8)>| {
+    let mut data = ifc_fuzz::seed();
+    for (pos, value) in edits {
+        let pos = pos as usize % data.len();
+        data[pos] = value;
+    }
+    ifc_fuzz::load_and_walk(&data);
+});
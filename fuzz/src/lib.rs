@@ -0,0 +1,6 @@
+//! Shared code for the fuzz targets.
+//!
+//! Run with `cargo fuzz run load` or `cargo fuzz run mutate`, from the `fuzz` directory. The
+//! seed and the walk are in `ifc::testing`, so that the unit tests run them too.
+
+pub use ifc::testing::{load_and_walk, seed};
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use ifc::Ifc;
+use std::sync::Arc;
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -24,7 +25,7 @@ struct Options {
 struct Reference {
     name: String,
     path: String,
-    ifc: Ifc,
+    ifc: Arc<Ifc<'static>>,
 }
 
 fn main() -> Result<()> {
@@ -41,10 +42,10 @@ fn main() -> Result<()> {
     for ref_ in cli_options.reference {
         if let Some((ifc_name, ifc_path)) = ref_.split_once('=') {
             let ref_data = std::fs::read(ifc_path)?;
-            let ref_ifc = Ifc::load(ref_data)?;
+            let ref_ifc = Arc::new(Ifc::load(ref_data)?);
 
             // Read this IFC file and add its symbols to the symbol map.
-            let _ref_index = symbol_map.add_ref_ifc(ifc_name, &ref_ifc)?;
+            let _ref_index = symbol_map.add_ref_ifc(ifc_name, ref_ifc.clone())?;
 
             references.push(Reference {
                 name: ifc_name.to_string(),
@@ -36,7 +36,9 @@ impl<'a> Gen<'a> {
 
         // Generate the enum type tokens.
 
+        let locus_doc = self.gen_locus_doc(&enum_decl.locus);
         let mut output = quote! {
+            #locus_doc
             #[repr(transparent)]
             #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
             #[cfg_attr(feature = "zerocopy", derive(::zerocopy::AsBytes, ::zerocopy::FromBytes))]
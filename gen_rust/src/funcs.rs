@@ -16,7 +16,7 @@ impl<'a> Gen<'a> {
                     return Ok(None);
                 }
 
-                if self.symbol_map.is_symbol_in(func_name) {
+                if self.symbol_map.is_external(self.ifc, member_decl_index)? {
                     debug!("function {} - defined in external crate", func_name);
                     None
                 } else {
@@ -73,10 +73,25 @@ impl<'a> Gen<'a> {
                             args_tys = vec![func_ty.source];
                         }
 
-                        for &arg_ty in args_tys.iter() {
+                        // Use the parameter names, if we have them.
+                        let params = self.ifc.function_parameters(func_decl)?;
+                        let param_names: Vec<&str> = if params.len() == args_tys.len() {
+                            params.iter().map(|p| p.name).collect()
+                        } else {
+                            debug!(
+                                "function {} has {} parameter types but {} parameter decls",
+                                func_name,
+                                args_tys.len(),
+                                params.len()
+                            );
+                            vec![""; args_tys.len()]
+                        };
+
+                        for (&arg_ty, &param_name) in args_tys.iter().zip(param_names.iter()) {
                             let arg_ty_tokens = self.get_type_tokens(arg_ty)?;
+                            let param_ident = param_ident(param_name);
                             args.extend(quote! {
-                                _: #arg_ty_tokens,
+                                #param_ident: #arg_ty_tokens,
                             });
                         }
                     }
@@ -99,3 +114,25 @@ impl<'a> Gen<'a> {
         })
     }
 }
+
+/// Converts a C++ parameter name to a Rust pattern. Unnamed parameters become `_`, and names
+/// that are Rust keywords get a trailing `_`. Names that are not Rust identifiers at all, such
+/// as MSVC names that contain `$`, also become `_`.
+fn param_ident(name: &str) -> Ident {
+    const RUST_KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+        "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+        "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+        "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+        "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+    ];
+    let name = if RUST_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    };
+    match syn::parse_str::<Ident>(&name) {
+        Ok(ident) => ident,
+        Err(_) => Ident::new("_", Span::call_site()),
+    }
+}
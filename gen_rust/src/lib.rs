@@ -42,7 +42,7 @@ impl Default for Options {
 type RefIndex = usize;
 
 struct Gen<'a> {
-    ifc: &'a Ifc,
+    ifc: &'a Ifc<'a>,
     symbol_map: SymbolMap,
     options: &'a Options,
     #[allow(dead_code)]
@@ -124,15 +124,18 @@ struct WellKnown {
 
 pub struct ReferencedIfc {
     pub name: String,
-    pub ifc: Arc<Ifc>,
+    pub ifc: Arc<Ifc<'static>>,
 }
 
 #[derive(Default, Clone)]
 pub struct SymbolMap {
     pub crates: Vec<String>,
     /// Maps symbol names that are in the global namespace scope to the IFC which define them.
-    /// For example, `"_GUID"` to some index.
+    /// For example, `"_GUID"` to some index. This includes forward declarations, so use
+    /// `is_external` to decide whether an entity is defined by a referenced crate.
     pub map: HashMap<String, RefIndex>,
+    /// The referenced IFCs. Unit `i` of the workspace is the IFC of `crates[i]`.
+    pub workspace: Workspace,
 }
 
 #[derive(Default, Clone)]
@@ -148,16 +151,34 @@ impl SymbolMap {
     /// If a symbol is exported by more than one Ifc, then just pick the one with the lowest index.
     /// This is a terrible idea, but it's good enough to start with.
     ///
-    /// For now, we only process the root scope.  So no nested namespaces.  And we ignore preprocessor
-    /// definitions.
-    pub fn add_ref_ifc(&mut self, ifc_name: &str, ifc: &Ifc) -> Result<RefIndex> {
-        let ifc_index = self.crates.len();
+    /// Symbols are the types and functions in every namespace, keyed by their qualified names
+    /// (see `Ifc::build_index`). We ignore preprocessor definitions.
+    pub fn add_ref_ifc(&mut self, ifc_name: &str, ifc: Arc<Ifc<'static>>) -> Result<RefIndex> {
+        let ifc_index = self.workspace.add(ifc_name, ifc)?;
+        debug_assert_eq!(ifc_index, self.crates.len());
 
         self.crates.push(ifc_name.to_string());
 
         let mut num_added: u64 = 0;
 
-        let mut add_symbol = |name: &str| {
+        let unit = self.workspace.unit(ifc_index)?;
+        let ifc = &unit.ifc;
+        for (name, decls) in unit.index.iter() {
+            let mut is_symbol = false;
+            for &decl in decls {
+                is_symbol |= match decl.tag() {
+                    DeclSort::SCOPE => {
+                        let scope = ifc.decl_scope().entry(decl.index())?;
+                        !ifc.is_type_namespace(scope.ty)?
+                    }
+                    DeclSort::ALIAS | DeclSort::ENUMERATION | DeclSort::FUNCTION => true,
+                    _ => false,
+                };
+            }
+            if !is_symbol {
+                continue;
+            }
+
             if let Some(existing_index) = self.map.get_mut(name) {
                 if ifc_index < *existing_index {
                     *existing_index = ifc_index;
@@ -167,60 +188,6 @@ impl SymbolMap {
                 self.map.insert(name.to_string(), ifc_index);
             }
             num_added += 1;
-        };
-
-        let scope = ifc.global_scope();
-        for member_decl in ifc.iter_scope(scope)? {
-            match member_decl.tag() {
-                DeclSort::SCOPE => {
-                    let nested_scope = ifc.decl_scope().entry(member_decl.index())?;
-                    if ifc.is_type_namespace(nested_scope.ty)? {
-                        // For now, we ignore namespaces.
-                    } else {
-                        // It's a nested struct/class.
-                        if nested_scope.name.tag() == NameSort::IDENTIFIER {
-                            let nested_name = ifc.get_string(nested_scope.name.index())?;
-                            add_symbol(nested_name);
-                        } else {
-                            warn!("ignoring scope member named: {:?}", nested_scope.name);
-                        }
-                    }
-                }
-
-                DeclSort::ALIAS => {
-                    let alias = ifc.decl_alias().entry(member_decl.index())?;
-                    let alias_name = ifc.get_string(alias.name)?;
-                    add_symbol(alias_name);
-                }
-
-                DeclSort::ENUMERATION => {
-                    let en = ifc.decl_enum().entry(member_decl.index())?;
-                    let en_name = ifc.get_string(en.name)?;
-                    add_symbol(en_name);
-                }
-
-                DeclSort::INTRINSIC => {}
-                DeclSort::TEMPLATE => {}
-                DeclSort::EXPLICIT_INSTANTIATION => {}
-                DeclSort::EXPLICIT_SPECIALIZATION => {}
-
-                DeclSort::FUNCTION => {
-                    let func_decl = ifc.decl_function().entry(member_decl.index())?;
-                    match func_decl.name.tag() {
-                        NameSort::IDENTIFIER => {
-                            let func_name = ifc.get_string(func_decl.name.index())?;
-                            add_symbol(func_name);
-                        }
-                        _ => {
-                            warn!("ignoring function named: {:?}", func_decl.name);
-                        }
-                    }
-                }
-
-                _ => {
-                    warn!("ignoring unrecognized scope member: {:?}", member_decl);
-                }
-            }
         }
 
         info!(
@@ -230,18 +197,24 @@ impl SymbolMap {
         Ok(ifc_index)
     }
 
-    pub fn is_symbol_in(&self, name: &str) -> bool {
-        self.map.contains_key(name)
+    /// Finds the referenced crate that defines the entity that `decl` declares, using the
+    /// module information in `ifc` when it has some (see `Workspace::resolve_foreign_decl`).
+    pub fn resolve_decl(&self, ifc: &Ifc, decl: DeclIndex) -> Result<Option<&str>> {
+        Ok(self
+            .workspace
+            .resolve_foreign_decl(ifc, decl)?
+            .map(|(unit, _)| self.crates[unit].as_str()))
     }
 
-    pub fn resolve(&self, name: &str) -> Option<&str> {
-        let crate_index = *self.map.get(name)?;
-        Some(self.crates[crate_index].as_str())
+    /// Whether a referenced crate defines the entity that `decl` declares, so that this crate
+    /// should use that definition instead of emitting its own.
+    pub fn is_external(&self, ifc: &Ifc, decl: DeclIndex) -> Result<bool> {
+        Ok(self.resolve_decl(ifc, decl)?.is_some())
     }
 }
 
 impl<'a> Gen<'a> {
-    fn new(ifc: &'a Ifc, symbol_map: SymbolMap, options: &'a Options) -> Self {
+    fn new(ifc: &'a Ifc<'a>, symbol_map: SymbolMap, options: &'a Options) -> Self {
         Self {
             ifc,
             symbol_map: symbol_map,
@@ -274,12 +247,6 @@ pub fn gen_rust(ifc: &Ifc, symbol_map: SymbolMap, options: &Options) -> Result<T
     Ok(outputs.finish())
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum ScopeKind {
-    Namespace,
-    Type,
-}
-
 impl<'a> Gen<'a> {
     fn gen_crate_start(&self) -> Result<TokenStream> {
         let extern_crates = self.gen_extern_crate()?;
@@ -306,7 +273,12 @@ impl<'a> Gen<'a> {
 
     #[inline(never)]
     fn gen_types(&self, outputs: &mut GenOutputs) -> Result<()> {
-        self.gen_types_for_scope(outputs, self.ifc.global_scope(), 50, ScopeKind::Namespace)
+        let mut visitor = TypesVisitor {
+            gen: self,
+            outputs,
+            counter: 0,
+        };
+        self.ifc.visit(&mut visitor)
     }
 
     /// Walk all scopes and find types that appear to be compiler-generated.
@@ -316,110 +288,28 @@ impl<'a> Gen<'a> {
     fn rename_decls(&self, parent_scope: ScopeIndex) -> Result<HashMap<DeclIndex, Ident>> {
         let mut decl_names: HashMap<DeclIndex, Ident> = HashMap::new();
         debug!("rename_decls: start");
-        self.rename_decls_rec(parent_scope, None, &mut decl_names)?;
+        let mut visitor = RenameVisitor {
+            decl_names: &mut decl_names,
+            scopes: vec![RenameScope::default()],
+        };
+        self.ifc.visit_scope(parent_scope, &mut visitor)?;
         debug!("rename_decls: end, num renamed = {}", decl_names.len());
         Ok(decl_names)
     }
 
-    #[inline(never)]
-    fn rename_decls_rec(
-        &self,
-        parent_scope: ScopeIndex,
-        name_basis_opt: Option<&str>,
-        decl_names: &mut HashMap<DeclIndex, Ident>,
-    ) -> Result<()> {
-        let mut new_name: String = String::with_capacity(200);
-        let mut fixed_name: String = String::with_capacity(100);
-
-        let mut anon_name_counter: u32 = 0;
-        // let mut overloaded_func_name_counter: u32 = 0;
-
-        for member_decl in self.ifc.iter_scope(parent_scope)? {
-            new_name.clear();
-            fixed_name.clear();
-
-            match member_decl.tag() {
-                DeclSort::SCOPE => {
-                    let nested_scope = self.ifc.decl_scope().entry(member_decl.index())?;
-                    if self.ifc.is_type_namespace(nested_scope.ty)? {
-                        // ignore namespaces for now
-                    } else {
-                        // It's a nested type.  Is the name compiler-generated?
-                        let scope_name = self.ifc.get_name_string(nested_scope.name)?;
-                        let is_gen = is_name_compiler_generated(scope_name);
-                        let this_name: &str = if is_gen {
-                            fixed_name.clear();
-                            fixed_name.push_str(scope_name);
-                            fixup_anon_names(&mut fixed_name, &mut anon_name_counter);
-                            debug!(
-                                "fixed compiler-generated name: {} -> {}",
-                                scope_name, fixed_name
-                            );
-                            &fixed_name
-                        } else {
-                            trace!("ordinary scope name: {}", scope_name);
-                            scope_name
-                        };
-
-                        new_name.clear();
-
-                        if let Some(name_basis) = name_basis_opt {
-                            // We are in a nested scope. We will need to rename this type, no
-                            // matter what.
-                            new_name.push_str(name_basis);
-                            new_name.push_str("__");
-                            new_name.push_str(this_name);
-                            debug!("renaming type into global scope: {}", new_name);
-                        } else {
-                            // We are not in a nested scope. We only need to rename this type if
-                            // it is compiler-generated.
-                            new_name.push_str(this_name);
-                        }
-
-                        if name_basis_opt.is_some() || is_gen {
-                            decl_names
-                                .insert(member_decl, Ident::new(&new_name, Span::call_site()));
-                        }
-
-                        // Recursively evaluate nested scope.
-                        if nested_scope.initializer != 0 {
-                            self.rename_decls_rec(
-                                nested_scope.initializer,
-                                Some(&new_name),
-                                decl_names,
-                            )?;
-                        }
-                    }
-                }
-
-                DeclSort::FUNCTION => {
-                    // TODO
-                    /*
-                    // Functions can be overloaded.
-                    let func = self.ifc.decl_function().entry(member_decl.index())?;
-                    let func_name = self.ifc.get_name_string(func.name)?;
-                    if let Some(existing) = decl_names.get() {
-                        let new_func_name = format!("{}_{:04}", overloaded_func_name_counter);
-                        info!("uh oh!  overloaded functions!  {} -> {}", func_name, new_func_name);
-
-                        overloaded_func_name_counter += 1;
-                        let new_func_ident = Ident::new(&new_func_name, Span::call_site());
-                        decl_names.insert
-                    }
-                    */
-                }
-
-                DeclSort::ENUMERATION => {
-                    // TODO
-                }
-
-                _ => {
-                    // ignore
+    /// Generates a doc comment that gives the source location of a declaration, such as
+    /// `Defined at foo.h:42`. Generates nothing if the location is not known.
+    fn gen_locus_doc(&self, locus: &SourceLocation) -> TokenStream {
+        match self.ifc.resolve_locus(locus) {
+            Some((path, line, _)) => {
+                let doc = format!("Defined at `{}:{}`", path, line);
+                quote! {
+                    #[doc = ""]
+                    #[doc = #doc]
                 }
             }
+            None => quote!(),
         }
-
-        Ok(())
     }
 
     fn gen_extern_crate(&self) -> Result<TokenStream> {
@@ -434,124 +324,6 @@ impl<'a> Gen<'a> {
         Ok(output)
     }
 
-    /// Recursively walks a scope and generates type definitions for it.
-    #[inline(never)]
-    fn gen_types_for_scope(
-        &self,
-        outputs: &mut GenOutputs,
-        parent_scope: ScopeIndex,
-        max_depth: u32,
-        _scope_kind: ScopeKind,
-    ) -> Result<()> {
-        debug!(
-            "Scope #{}{}",
-            parent_scope,
-            if parent_scope + 1 == self.ifc.file_header().global_scope {
-                " - Global scope"
-            } else {
-                ""
-            }
-        );
-
-        if max_depth == 0 {
-            bail!("Max depth exceeded!");
-        }
-
-        let _max_depth = max_depth - 1;
-
-        let mut counter: u32 = 0;
-
-        for member_decl_index in self.ifc.iter_scope(parent_scope)? {
-            match member_decl_index.tag() {
-                DeclSort::ALIAS => {
-                    if false {
-                        let decl_alias = self.ifc.decl_alias().entry(member_decl_index.index())?;
-                        let mut alias_name = self.ifc.get_string(decl_alias.name)?.to_string();
-                        fixup_anon_names(&mut alias_name, &mut counter);
-
-                        if self.symbol_map.is_symbol_in(&alias_name) {
-                            debug!("alias {} is defined in external crate", alias_name);
-                        } else {
-                            debug!("alias {} - adding", alias_name);
-                            let alias_ident = syn::Ident::new(&alias_name, Span::call_site());
-                            let aliasee_tokens = self.get_type_tokens(decl_alias.aliasee)?;
-
-                            outputs.aliases.extend(quote! {
-                                pub type #alias_ident = #aliasee_tokens;
-                            });
-                        }
-                    }
-                }
-
-                DeclSort::FUNCTION => {
-                    // Functions are processed in a later pass.
-                }
-
-                DeclSort::METHOD => {}
-
-                DeclSort::SCOPE => {
-                    let nested_scope = self.ifc.decl_scope().entry(member_decl_index.index())?;
-
-                    // What kind of scope is it?
-                    if self.ifc.is_type_namespace(nested_scope.ty)? {
-                        // We do not yet process nested namespaces.
-                    } else {
-                        // It's a nested struct/class.
-                        outputs.types.extend(self.gen_struct(member_decl_index)?);
-
-                        if nested_scope.initializer != 0 {
-                            debug!(
-                                "gen_types_for_scope: recursing, to scope #{} (zero-based)",
-                                nested_scope.initializer
-                            );
-                            self.gen_types_for_scope(
-                                outputs,
-                                nested_scope.initializer,
-                                max_depth - 1,
-                                ScopeKind::Type,
-                            )?;
-                        } else {
-                            debug!("gen_types_for_scope: not recursing, because this is a forward decl only");
-                        }
-                    }
-                }
-
-                DeclSort::ENUMERATION => {
-                    let en = self.ifc.decl_enum().entry(member_decl_index.index())?;
-                    let en_name = self.ifc.get_string(en.name)?;
-                    if self.symbol_map.is_symbol_in(en_name) {
-                        debug!("enum {} - defined in external crate", en_name);
-                    } else {
-                        debug!("enum {} - emitting", en_name);
-                        let t = self.gen_enum(&en)?;
-                        outputs.types.extend(t);
-                    }
-                }
-
-                DeclSort::VARIABLE => {
-                    self.gen_variable(member_decl_index.index(), outputs)?;
-                }
-
-                DeclSort::INTRINSIC
-                | DeclSort::TEMPLATE
-                | DeclSort::CONCEPT
-                | DeclSort::EXPLICIT_INSTANTIATION
-                | DeclSort::FIELD
-                | DeclSort::BITFIELD
-                | DeclSort::USING_DECLARATION
-                | DeclSort::PARTIAL_SPECIALIZATION
-                | DeclSort::EXPLICIT_SPECIALIZATION => {}
-
-                _ => {
-                    nyi!();
-                    info!("unknown decl: {:?}", member_decl_index);
-                }
-            }
-        }
-
-        Ok(())
-    }
-
     /// Recursively walks a scope and generates type definitions for it.
     #[inline(never)]
     fn gen_functions(&self, outputs: &mut GenOutputs, parent_scope: ScopeIndex) -> Result<()> {
@@ -688,6 +460,215 @@ pub fn fixup_anon_names(s: &mut String, counter: &mut u32) {
     *s = out;
 }
 
+/// Assigns new names to compiler-generated types and to nested types, which we hoist into
+/// global scope.
+struct RenameVisitor<'m> {
+    decl_names: &'m mut HashMap<DeclIndex, Ident>,
+    /// One entry for each enclosing class scope.
+    scopes: Vec<RenameScope>,
+}
+
+#[derive(Default)]
+struct RenameScope {
+    /// If we are in a nested scope, then this is the (new) name of that scope.
+    name_basis: Option<String>,
+    anon_name_counter: u32,
+}
+
+#[allow(unused_variables)]
+impl<'m> DeclVisitor for RenameVisitor<'m> {
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        // ignore namespaces for now
+        Ok(false)
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        let parent = self.scopes.last_mut().unwrap();
+
+        // It's a nested type.  Is the name compiler-generated?
+        let scope_name = ifc.get_name_string(scope.name)?;
+        let is_gen = is_name_compiler_generated(&scope_name);
+        let this_name: String = if is_gen {
+            let mut fixed_name = scope_name.to_string();
+            fixup_anon_names(&mut fixed_name, &mut parent.anon_name_counter);
+            debug!(
+                "fixed compiler-generated name: {} -> {}",
+                scope_name, fixed_name
+            );
+            fixed_name
+        } else {
+            trace!("ordinary scope name: {}", scope_name);
+            scope_name.to_string()
+        };
+
+        let new_name = if let Some(name_basis) = parent.name_basis.as_deref() {
+            // We are in a nested scope. We will need to rename this type, no
+            // matter what.
+            let new_name = format!("{}__{}", name_basis, this_name);
+            debug!("renaming type into global scope: {}", new_name);
+            new_name
+        } else {
+            // We are not in a nested scope. We only need to rename this type if
+            // it is compiler-generated.
+            this_name
+        };
+
+        if parent.name_basis.is_some() || is_gen {
+            self.decl_names
+                .insert(decl, Ident::new(&new_name, Span::call_site()));
+        }
+
+        // Recursively evaluate nested scope.
+        self.scopes.push(RenameScope {
+            name_basis: Some(new_name),
+            anon_name_counter: 0,
+        });
+        Ok(true)
+    }
+
+    fn leave_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<()> {
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        // TODO
+        Ok(false)
+    }
+}
+
+/// Generates type definitions for the types in the global scope, and for the types nested
+/// within them.
+struct TypesVisitor<'g, 'a> {
+    gen: &'g Gen<'a>,
+    outputs: &'g mut GenOutputs,
+    counter: u32,
+}
+
+#[allow(unused_variables)]
+impl<'g, 'a> DeclVisitor for TypesVisitor<'g, 'a> {
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        // We do not yet process nested namespaces.
+        Ok(false)
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        // It's a nested struct/class.
+        self.outputs.types.extend(self.gen.gen_struct(decl)?);
+
+        if scope.initializer != 0 {
+            debug!(
+                "gen_types: recursing, to scope #{}",
+                scope.initializer
+            );
+        } else {
+            debug!("gen_types: not recursing, because this is a forward decl only");
+        }
+        Ok(true)
+    }
+
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, decl_alias: &DeclAlias) -> Result<()> {
+        if false {
+            let mut alias_name = ifc.get_string(decl_alias.name)?.to_string();
+            fixup_anon_names(&mut alias_name, &mut self.counter);
+
+            if self.gen.symbol_map.is_external(ifc, decl)? {
+                debug!("alias {} is defined in external crate", alias_name);
+            } else {
+                debug!("alias {} - adding", alias_name);
+                let alias_ident = syn::Ident::new(&alias_name, Span::call_site());
+                let aliasee_tokens = self.gen.get_type_tokens(decl_alias.aliasee)?;
+
+                self.outputs.aliases.extend(quote! {
+                    pub type #alias_ident = #aliasee_tokens;
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        let en_name = ifc.get_string(en.name)?;
+        if self.gen.symbol_map.is_external(ifc, decl)? {
+            debug!("enum {} - defined in external crate", en_name);
+        } else {
+            debug!("enum {} - emitting", en_name);
+            let t = self.gen.gen_enum(en)?;
+            self.outputs.types.extend(t);
+        }
+        Ok(false)
+    }
+
+    fn visit_variable(&mut self, ifc: &Ifc, decl: DeclIndex, var: &DeclVar) -> Result<()> {
+        self.gen.gen_variable(decl.index(), self.outputs)
+    }
+
+    // Functions are processed in a later pass.
+
+    fn visit_other(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        match decl.tag() {
+            DeclSort::INTRINSIC
+            | DeclSort::CONCEPT
+            | DeclSort::EXPLICIT_INSTANTIATION
+            | DeclSort::USING_DECLARATION
+            | DeclSort::PARTIAL_SPECIALIZATION
+            | DeclSort::EXPLICIT_SPECIALIZATION => {}
+
+            _ => {
+                nyi!();
+                info!("unknown decl: {:?}", decl);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Records which scopes are reachable from the global scope.
+struct OrphanVisitor {
+    decl_scope_found: Vec<bool>,
+}
+
+impl OrphanVisitor {
+    fn found_scope(&mut self, decl: DeclIndex) -> bool {
+        let found = &mut self.decl_scope_found[decl.index() as usize];
+        if *found {
+            warn!("found scope twice!  {:?}", decl);
+            false
+        } else {
+            *found = true;
+            true
+        }
+    }
+}
+
+#[allow(unused_variables)]
+impl DeclVisitor for OrphanVisitor {
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        Ok(self.found_scope(decl))
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        Ok(self.found_scope(decl))
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn visit_other(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        match decl.tag() {
+            DeclSort::EXPLICIT_INSTANTIATION
+            | DeclSort::EXPLICIT_SPECIALIZATION
+            | DeclSort::PARTIAL_SPECIALIZATION
+            | DeclSort::USING_DECLARATION
+            | DeclSort::INTRINSIC => {}
+
+            _ => warn!("unrecognized member decl: {:?}", decl),
+        }
+        Ok(())
+    }
+}
+
 pub struct SymbolRemaps {
     /// If an entry is present in this table, then it represents a new name that we have computed
     /// for a given type.
@@ -699,54 +680,10 @@ impl<'a> Gen<'a> {
     fn find_orphans(&self, outputs: &mut GenOutputs) -> Result<()> {
         let ifc = self.ifc;
 
-        struct State {
-            decl_scope_found: Vec<bool>,
-        }
-
-        let mut state = State {
+        let mut state = OrphanVisitor {
             decl_scope_found: vec![false; ifc.decl_scope().entries.len()],
         };
-
-        fn search_scope(ifc: &Ifc, state: &mut State, parent_scope: ScopeIndex) -> Result<()> {
-            for member_decl in ifc.iter_scope(parent_scope)? {
-                match member_decl.tag() {
-                    DeclSort::FUNCTION
-                    | DeclSort::FIELD
-                    | DeclSort::ALIAS
-                    | DeclSort::TEMPLATE
-                    | DeclSort::EXPLICIT_INSTANTIATION
-                    | DeclSort::EXPLICIT_SPECIALIZATION
-                    | DeclSort::PARTIAL_SPECIALIZATION
-                    | DeclSort::USING_DECLARATION
-                    | DeclSort::ENUMERATOR
-                    | DeclSort::ENUMERATION
-                    | DeclSort::VARIABLE
-                    | DeclSort::CONSTRUCTOR
-                    | DeclSort::METHOD
-                    | DeclSort::DESTRUCTOR
-                    | DeclSort::INTRINSIC
-                    | DeclSort::BITFIELD => {}
-
-                    DeclSort::SCOPE => {
-                        let nested_scope = ifc.decl_scope().entry(member_decl.index())?;
-                        if state.decl_scope_found[member_decl.index() as usize] {
-                            warn!("found scope twice!  {:?}", member_decl);
-                        } else {
-                            state.decl_scope_found[member_decl.index() as usize] = true;
-                            if nested_scope.initializer != 0 {
-                                search_scope(ifc, state, nested_scope.initializer)?;
-                            }
-                        }
-                    }
-
-                    _ => todo!("unrecognized member decl: {:?}", member_decl),
-                }
-            }
-
-            Ok(())
-        }
-
-        search_scope(ifc, &mut state, ifc.global_scope())?;
+        ifc.visit(&mut state)?;
 
         for (i, value) in state.decl_scope_found.iter().enumerate() {
             if !*value {
@@ -21,6 +21,13 @@ impl<'a> Gen<'a> {
             Ident::new(&nested_scope_name, Span::call_site())
         };
 
+        // If the type is defined in a different crate, then do not emit a definition. This is
+        // the same check that `get_type_tokens` uses to refer to the type in that crate.
+        if self.symbol_map.is_external(self.ifc, member_decl_index)? {
+            debug!("struct {} - defined in external crate", nested_scope_name);
+            return Ok(quote!());
+        }
+
         // If the initializer is NULL (not empty, but NULL), then this is a forward declaration
         // with no definition.
         if nested_scope.initializer == 0 {
@@ -43,12 +50,6 @@ impl<'a> Gen<'a> {
             }
         }
 
-        // If the type is defined in a different crate, then do not emit a definition.
-        if self.symbol_map.is_symbol_in(&nested_scope_name) {
-            debug!("struct {} - defined in external crate", nested_scope_name);
-            return Ok(quote!());
-        }
-
         // Emit the definition for this struct.
         debug!("struct {} - emitting", nested_scope_name);
         let mut struct_contents = TokenStream::new();
@@ -148,10 +149,12 @@ impl<'a> Gen<'a> {
         // let doc = format!("{:#?}", nested_scope);
 
         let doc = format!("Scope: {:?}", member_decl_index);
+        let locus_doc = self.gen_locus_doc(&nested_scope.locus);
 
         debug!("emitting struct {}", nested_scope_ident);
         Ok(quote! {
             #[doc = #doc]
+            #locus_doc
             #[repr(C)]
             pub struct #nested_scope_ident {
                 #struct_contents
@@ -95,8 +95,9 @@ impl<'a> Gen<'a> {
                 let extent_tokens = if type_array.extent.tag() == ExprSort::EMPTY {
                     quote!(_)
                 } else {
-                    quote!(42)
-                    // gen_expr_tokens(ifc, type_array.extent)?
+                    let extent = self.get_literal_expr_as_u64(type_array.extent)?;
+                    let lit = syn::LitInt::new(&extent.to_string(), Span::call_site());
+                    quote!(#lit)
                 };
 
                 quote! {
@@ -141,7 +142,7 @@ impl<'a> Gen<'a> {
                 let mut desig_name = desig_name.to_string();
                 fixup_anon_names(&mut desig_name, &mut anon_name_counter);
 
-                if let Some(extern_crate) = self.symbol_map.resolve(&desig_name) {
+                if let Some(extern_crate) = self.symbol_map.resolve_decl(self.ifc, desig_decl)? {
                     // This designated type reference resolves to a name in a dependent crate.
                     trace!("resolved type to external crate: {}", extern_crate);
                     let extern_ident = syn::Ident::new(extern_crate, Span::call_site());
@@ -171,11 +172,22 @@ impl<'a> Gen<'a> {
                 quote!(*mut #tokens)
             }
 
-            TypeSort::FUNCTION => {
+            TypeSort::FUNCTION | TypeSort::METHOD => {
                 quote!(*const core::ffi::c_void)
             }
 
-            _ => todo!("unrecognized type sort: {:?}", type_index),
+            TypeSort::PLACEHOLDER => {
+                let placeholder = self.ifc.type_placeholder().entry(type_index.index())?;
+                if placeholder.elaboration.is_null() {
+                    bail!("placeholder type was not deduced: {:?}", placeholder);
+                }
+                self.get_type_tokens(placeholder.elaboration)?
+            }
+
+            _ => bail!(
+                "unsupported type: {}",
+                self.ifc.get_type_string(type_index)?
+            ),
         })
     }
 }
@@ -17,8 +17,8 @@ impl<'a> Gen<'a> {
             is_const = true;
         } else {
             if self.ifc.is_const_qualified(var.ty)? {
-                // If it has a literal initializer, it's a constant.
-                if self.ifc.is_literal_expr(var.initializer) {
+                // If it has a constant initializer, it's a constant.
+                if self.ifc.eval_const(var.initializer).is_ok() {
                     is_const = true;
                 } else {
                     is_const = false;
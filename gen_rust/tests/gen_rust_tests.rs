@@ -3,6 +3,7 @@
 use ifc::Ifc;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
 
 mod enums;
 mod headers;
@@ -77,7 +78,7 @@ impl Case {
         self.spawn_and_wait(cl);
     }
 
-    fn read_ifc(&self, ifc_filename: &str) -> Ifc {
+    fn read_ifc(&self, ifc_filename: &str) -> Ifc<'static> {
         let ifc_file_path = self.case_tmp_dir.join(ifc_filename);
         println!("ifc_file_path: {}", ifc_file_path.display());
         let ifc_data = std::fs::read(&ifc_file_path).expect("failed to read IFC file");
@@ -107,7 +108,7 @@ impl Case {
         for (ref_name, ref_filename) in ifc_references.iter() {
             let ref_path = self.case_tmp_dir.join(ref_filename);
             let ref_ifc = self.read_ifc(&ref_path.to_string_lossy());
-            symbol_map.add_ref_ifc(ref_name, &ref_ifc).unwrap();
+            symbol_map.add_ref_ifc(ref_name, Arc::new(ref_ifc)).unwrap();
         }
 
         let ifc_options = Default::default();
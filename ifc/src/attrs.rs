@@ -0,0 +1,242 @@
+//! Attributes - Chapter 14
+
+use super::*;
+
+#[c_enum(storage = "u32")]
+pub enum AttrSort {
+    /// No attribute. The index is zero.
+    NOTHING = 0,
+    /// A single token, such as `nodiscard`. The index is into `attr.basic`.
+    BASIC = 1,
+    /// A scoped attribute, such as `gnu::always_inline`. The index is into `attr.scoped`.
+    SCOPED = 2,
+    /// A labeled attribute, such as `name: value`. The index is into `attr.labeled`.
+    LABELED = 3,
+    /// An attribute with arguments, such as `deprecated("use bar")`. The index is into
+    /// `attr.called`.
+    CALLED = 4,
+    /// A pack expansion, such as `attr...`. The index is into `attr.expanded`.
+    EXPANDED = 5,
+    /// Attributes that share a namespace, such as `using gnu: hot, cold`. The index is into
+    /// `attr.factored`.
+    FACTORED = 6,
+    /// An attribute that is an expression, such as the argument of `alignas`. The index is into
+    /// `attr.elaborated`.
+    ELABORATED = 7,
+    /// A sequence of attributes. The index is into `attr.tuple`.
+    TUPLE = 8,
+}
+
+tagged_index! {
+    pub struct AttrIndex {
+        const TAG_BITS: usize = 4;
+        tag: AttrSort,
+        index: u32,
+    }
+}
+
+/// `attr.scoped`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttrScoped {
+    pub scope: Word,
+    pub member: Word,
+}
+
+/// `attr.labeled`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttrLabeled {
+    pub label: Word,
+    pub attribute: AttrIndex,
+}
+
+/// `attr.called`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttrCalled {
+    pub function: AttrIndex,
+    /// A single argument, or an `AttrSort::TUPLE` of them.
+    pub arguments: AttrIndex,
+}
+
+/// `attr.factored`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttrFactored {
+    pub factor: Word,
+    pub terms: AttrIndex,
+}
+
+/// `attr.tuple`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttrTuple {
+    // Index into `heap.attr`.
+    pub start: Index,
+    pub cardinality: Cardinality,
+}
+
+/// `.msvc.trait.decl-attrs` and `trait.attribute`: associate a declaration with its
+/// attributes.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct AttributeTrait {
+    pub decl: DeclIndex,
+    pub attribute: AttrIndex,
+}
+
+/// `trait.deprecated`: the message of a `[[deprecated]]` declaration.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeprecatedTrait {
+    pub decl: DeclIndex,
+    pub message: TextOffset,
+}
+
+/// A decoded attribute, as returned by `Ifc::attr`.
+#[derive(Clone, Debug)]
+pub enum Attr<'a> {
+    Nothing,
+    Basic(&'a Word),
+    Scoped(&'a AttrScoped),
+    Labeled(&'a AttrLabeled),
+    Called(&'a AttrCalled),
+    /// The attribute that is expanded.
+    Expanded(AttrIndex),
+    Factored(&'a AttrFactored),
+    Elaborated(ExprIndex),
+    Tuple(&'a [AttrIndex]),
+    /// A sort that is not defined by the spec.
+    Other(AttrIndex),
+}
+
+impl<'data> Ifc<'data> {
+    /// Looks up the record for an attribute.
+    pub fn attr(&self, attr: AttrIndex) -> IfcResult<Attr<'_>> {
+        let i = attr.index();
+        Ok(match attr.tag() {
+            AttrSort::NOTHING => Attr::Nothing,
+            AttrSort::BASIC => Attr::Basic(self.attr_basic().entry(i)?),
+            AttrSort::SCOPED => Attr::Scoped(self.attr_scoped().entry(i)?),
+            AttrSort::LABELED => Attr::Labeled(self.attr_labeled().entry(i)?),
+            AttrSort::CALLED => Attr::Called(self.attr_called().entry(i)?),
+            AttrSort::EXPANDED => Attr::Expanded(*self.attr_expanded().entry(i)?),
+            AttrSort::FACTORED => Attr::Factored(self.attr_factored().entry(i)?),
+            AttrSort::ELABORATED => Attr::Elaborated(*self.attr_elaborated().entry(i)?),
+            AttrSort::TUPLE => {
+                let tuple = self.attr_tuple().entry(i)?;
+                let heap = self.heap_attr();
+                let range = tuple.start as usize..tuple.start as usize + tuple.cardinality as usize;
+                match heap.entries.get(range) {
+                    Some(slice) => Attr::Tuple(slice),
+                    None => {
+                        return Err(IfcError::BadEntryIndex {
+                            partition: heap.part_name,
+                            index: tuple.start.saturating_add(tuple.cardinality),
+                            len: heap.entries.len(),
+                        })
+                    }
+                }
+            }
+            _ => Attr::Other(attr),
+        })
+    }
+
+    /// Returns the attributes of a declaration. MSVC records the attributes that were written
+    /// on a declaration in `.msvc.trait.decl-attrs`; `trait.attribute` holds the attributes
+    /// that are not captured by the traits of the declaration, and those of templated
+    /// declarations. Both are returned, in that order. Each attribute may be an
+    /// `AttrSort::TUPLE` of several attributes.
+    pub fn decl_attributes(&self, decl: DeclIndex) -> impl Iterator<Item = AttrIndex> + '_ {
+        let msvc = trait_entries(self.msvc_trait_decl_attrs().entries, decl, |t| t.decl);
+        let attrs = trait_entries(self.trait_attribute().entries, decl, |t| t.decl);
+        msvc.iter().chain(attrs.iter()).map(|t| t.attribute)
+    }
+
+    /// Returns the message of a `[[deprecated]]` declaration, from `trait.deprecated`. The
+    /// message is empty if the attribute did not have one.
+    pub fn deprecation(&self, decl: DeclIndex) -> IfcResult<Option<&str>> {
+        match trait_entries(self.trait_deprecated().entries, decl, |t| t.decl).first() {
+            Some(t) => Ok(Some(self.get_string(t.message)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the attribute as it would be written in C++, without the enclosing `[[` `]]`,
+    /// e.g. `nodiscard`, `gnu::always_inline` or `deprecated("use bar")`.
+    pub fn get_attr_string(&self, attr: AttrIndex) -> Result<String> {
+        let mut s = String::new();
+        self.write_attr_string(&mut s, attr, 0)?;
+        Ok(s)
+    }
+
+    fn write_attr_string(&self, s: &mut String, attr: AttrIndex, depth: u32) -> Result<()> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("attribute {:?} is nested too deeply (is it cyclic?)", attr);
+        }
+
+        match self.attr(attr)? {
+            Attr::Nothing => {}
+            Attr::Basic(word) => s.push_str(&self.get_word_string(word)?),
+            Attr::Scoped(scoped) => {
+                s.push_str(&self.get_word_string(&scoped.scope)?);
+                s.push_str("::");
+                s.push_str(&self.get_word_string(&scoped.member)?);
+            }
+            Attr::Labeled(labeled) => {
+                s.push_str(&self.get_word_string(&labeled.label)?);
+                s.push_str(": ");
+                self.write_attr_string(s, labeled.attribute, depth + 1)?;
+            }
+            Attr::Called(called) => {
+                self.write_attr_string(s, called.function, depth + 1)?;
+                s.push('(');
+                self.write_attr_string(s, called.arguments, depth + 1)?;
+                s.push(')');
+            }
+            Attr::Expanded(operand) => {
+                self.write_attr_string(s, operand, depth + 1)?;
+                s.push_str("...");
+            }
+            Attr::Factored(factored) => {
+                s.push_str("using ");
+                s.push_str(&self.get_word_string(&factored.factor)?);
+                s.push_str(": ");
+                self.write_attr_string(s, factored.terms, depth + 1)?;
+            }
+            Attr::Elaborated(expr) => {
+                s.push_str(&self.get_template_argument_string(expr, depth + 1)?)
+            }
+            Attr::Tuple(attrs) => {
+                for (i, &attr) in attrs.iter().enumerate() {
+                    if i != 0 {
+                        s.push_str(", ");
+                    }
+                    self.write_attr_string(s, attr, depth + 1)?;
+                }
+            }
+            Attr::Other(attr) => bail!("attribute {:?} has an unknown sort", attr),
+        }
+        Ok(())
+    }
+
+    /// Returns the spelling of a word. Identifiers, keywords and literals are stored in the
+    /// string table. Other words are shown by their sort and value.
+    fn get_word_string(&self, word: &Word) -> IfcResult<Cow<'_, str>> {
+        Ok(match word.sort {
+            WordSort::IDENTIFIER | WordSort::KEYWORD | WordSort::LITERAL => {
+                Cow::Borrowed(self.get_string(word.index)?)
+            }
+            _ => Cow::Owned(format!("<{:?} {}>", word.sort, word.value)),
+        })
+    }
+}
+
+/// Finds the entries for a declaration in a trait partition. Trait partitions are sorted by
+/// declaration, so this is a binary search.
+fn trait_entries<T>(entries: &[T], decl: DeclIndex, key: impl Fn(&T) -> DeclIndex) -> &[T] {
+    let start = entries.partition_point(|t| key(t).0 < decl.0);
+    let len = entries[start..].partition_point(|t| key(t).0 == decl.0);
+    &entries[start..start + len]
+}
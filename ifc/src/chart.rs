@@ -4,7 +4,7 @@ use super::*;
 
 tagged_index! {
     pub struct ChartIndex {
-        const TAG_BITS: usize = 6;
+        const TAG_BITS: usize = 2;
         tag: ChartSort,
         index: u32,
     }
@@ -30,7 +30,90 @@ pub struct ChartUnilevel {
 #[repr(C)]
 #[derive(Clone, AsBytes, FromBytes)]
 pub struct ChartMultilevel {
+    // Index into `heap.chart`. Each entry is a unilevel chart.
     pub start: Index,
     pub cardinality: Cardinality,
 }
 
+/// A function parameter, as returned by `Ifc::function_parameters`.
+#[derive(Clone, Debug)]
+pub struct Param<'a> {
+    /// The record in `decl.parameter`.
+    pub decl: &'a DeclParameter,
+    /// The name of the parameter. This is empty if the parameter is unnamed.
+    pub name: &'a str,
+    pub ty: TypeIndex,
+    /// The default argument, if the parameter has one.
+    pub default_argument: Option<ExprIndex>,
+    /// The position of the parameter in its parameter list, as stored in the file.
+    pub position: ParameterPosition,
+}
+
+impl<'data> Ifc<'data> {
+    /// Returns the parameter lists of a chart. A unilevel chart has one list, and a multilevel
+    /// chart (e.g. a member template of a class template) has one list for each level, from
+    /// the outermost level inward. A null chart has none.
+    pub fn chart_levels(&self, chart: ChartIndex) -> Result<Vec<&[DeclParameter]>> {
+        match chart.tag() {
+            ChartSort::NONE => Ok(Vec::new()),
+            ChartSort::UNILEVEL => Ok(vec![self.chart_unilevel_params(chart.index())?]),
+            ChartSort::MULTILEVEL => {
+                let multi = self.chart_multilevel().entry(chart.index())?;
+                let mut levels = Vec::with_capacity(multi.cardinality as usize);
+                for i in multi.start..multi.start.saturating_add(multi.cardinality) {
+                    let level = *self.heap_chart().entry(i)?;
+                    match level.tag() {
+                        ChartSort::UNILEVEL => {
+                            levels.push(self.chart_unilevel_params(level.index())?)
+                        }
+                        ChartSort::NONE => levels.push(&[]),
+                        _ => bail!(
+                            "multilevel chart {:?} contains another multilevel chart {:?}",
+                            chart,
+                            level
+                        ),
+                    }
+                }
+                Ok(levels)
+            }
+            _ => bail!("chart {:?} has an unknown sort", chart),
+        }
+    }
+
+    fn chart_unilevel_params(&self, index: u32) -> IfcResult<&[DeclParameter]> {
+        let uni = self.chart_unilevel().entry(index)?;
+        let params = self.decl_parameter();
+        let range = uni.start as usize..uni.start as usize + uni.cardinality as usize;
+        params.entries.get(range).ok_or(IfcError::BadEntryIndex {
+            partition: params.part_name,
+            index: uni.start.saturating_add(uni.cardinality),
+            len: params.entries.len(),
+        })
+    }
+
+    /// Returns the function parameters of a function or method, in order. Template parameters
+    /// are not included.
+    pub fn function_parameters(&self, func: &DeclFunc) -> Result<Vec<Param<'_>>> {
+        let mut params = Vec::new();
+        for level in self.chart_levels(func.chart)? {
+            for decl in level.iter() {
+                if decl.sort != ParameterSort::OBJECT {
+                    continue;
+                }
+                params.push(Param {
+                    decl,
+                    name: self.get_string(decl.name)?,
+                    ty: decl.ty,
+                    default_argument: if decl.initializer.0 != 0 {
+                        Some(decl.initializer)
+                    } else {
+                        None
+                    },
+                    position: decl.position,
+                });
+            }
+        }
+        Ok(params)
+    }
+}
+
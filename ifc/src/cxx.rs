@@ -0,0 +1,523 @@
+//! Printing types in C++ declarator syntax
+
+use super::*;
+
+/// Controls the spellings used by `CxxTypePrinter`.
+#[derive(Clone, Debug, Default)]
+pub struct CxxPrintOptions {
+    /// Spell sized integers the way MSVC does, e.g. `__int64` rather than `long long`.
+    pub msvc_spellings: bool,
+    /// Show the calling convention of function types, e.g. `int (__stdcall *)(int)`.
+    pub calling_conventions: bool,
+    /// Show the enclosing scopes of classes, enums and aliases, e.g. `ns::POINT`.
+    pub qualified_names: bool,
+}
+
+/// Formats types as C++ source, e.g. `const char *`, `int (*)(int)` or `int *[4]`.
+///
+/// C++ declarators are written inside-out: the declared name sits in the middle of the type,
+/// with pointers to its left and arrays and function parameters to its right. The printer
+/// builds the declarator outward from the name, adding parentheses where a pointer or
+/// reference binds to an array or function.
+pub struct CxxTypePrinter<'a, 'data> {
+    ifc: &'a Ifc<'data>,
+    options: CxxPrintOptions,
+}
+
+impl<'a, 'data> CxxTypePrinter<'a, 'data> {
+    pub fn new(ifc: &'a Ifc<'data>, options: CxxPrintOptions) -> Self {
+        Self { ifc, options }
+    }
+
+    /// Formats a type with no declarator name, e.g. `int (*)(int)`.
+    pub fn type_string(&self, ty: TypeIndex) -> Result<String> {
+        self.declaration_at_depth(ty, "", 0)
+    }
+
+    /// Formats a declaration of `name` with type `ty`, e.g. `int (*callback)(int)` or
+    /// `char name[16]`.
+    pub fn declaration(&self, ty: TypeIndex, name: &str) -> Result<String> {
+        self.declaration_at_depth(ty, name, 0)
+    }
+
+    pub(crate) fn declaration_at_depth(
+        &self,
+        ty: TypeIndex,
+        name: &str,
+        depth: u32,
+    ) -> Result<String> {
+        let mut declarator = name.to_string();
+        let specifiers = self.write_declarator(ty, &mut declarator, depth)?;
+        Ok(if declarator.is_empty() {
+            specifiers
+        } else if specifiers.is_empty() {
+            declarator
+        } else {
+            format!("{} {}", specifiers, declarator)
+        })
+    }
+
+    /// Wraps `declarator` in the parts of `ty` that are written around the name, and returns
+    /// the parts that are written before it (the type specifiers).
+    fn write_declarator(
+        &self,
+        ty: TypeIndex,
+        declarator: &mut String,
+        depth: u32,
+    ) -> Result<String> {
+        // Types are trees, but a malformed file could contain a cycle.
+        if depth > MAX_TYPE_DEPTH {
+            bail!("type {:?} is nested too deeply (is it cyclic?)", ty);
+        }
+
+        let ifc = self.ifc;
+        let i = ty.index();
+        match ty.tag() {
+            TypeSort::POINTER => {
+                let pointee = *ifc.type_pointer().entry(i)?;
+                self.wrap_pointer("*", "", pointee, declarator)?;
+                self.write_declarator(pointee, declarator, depth + 1)
+            }
+            TypeSort::LVALUE_REFERENCE => {
+                let referee = *ifc.type_lvalue_reference().entry(i)?;
+                self.wrap_pointer("&", "", referee, declarator)?;
+                self.write_declarator(referee, declarator, depth + 1)
+            }
+            TypeSort::RVALUE_REFERENCE => {
+                let referee = *ifc.type_rvalue_reference().entry(i)?;
+                self.wrap_pointer("&&", "", referee, declarator)?;
+                self.write_declarator(referee, declarator, depth + 1)
+            }
+            TypeSort::POINTER_TO_MEMBER => {
+                let ptm = ifc.type_pointer_to_member().entry(i)?;
+                let op = format!(
+                    "{}::*",
+                    self.declaration_at_depth(ptm.scope, "", depth + 1)?
+                );
+                self.wrap_pointer(&op, "", ptm.member, declarator)?;
+                self.write_declarator(ptm.member, declarator, depth + 1)
+            }
+            TypeSort::QUALIFIED => {
+                let qualified = ifc.type_qualified().entry(i)?;
+                let cv = qualifiers_string(qualified.qualifiers);
+                self.write_qualified(&cv, qualified.unqualified_type, declarator, depth + 1)
+            }
+            TypeSort::UNALIGNED => {
+                let target = *ifc.type_unaligned().entry(i)?;
+                self.write_qualified("__unaligned", target, declarator, depth + 1)
+            }
+            TypeSort::ARRAY => {
+                let array = ifc.type_array().entry(i)?;
+                // An unknown bound is written `[]`, and so is one that we can't evaluate.
+                let extent = if array.extent.0 == 0 {
+                    None
+                } else {
+                    ifc.eval_const(array.extent).ok().and_then(|v| v.to_u64())
+                };
+                match extent {
+                    Some(extent) => declarator.push_str(&format!("[{}]", extent)),
+                    None => declarator.push_str("[]"),
+                }
+                self.write_declarator(array.element, declarator, depth + 1)
+            }
+            TypeSort::FUNCTION => {
+                let func = ifc.type_function().entry(i)?;
+                self.write_function(
+                    func.source,
+                    func.convention,
+                    &func.eh_spec,
+                    declarator,
+                    depth,
+                )?;
+                self.write_declarator(func.target, declarator, depth + 1)
+            }
+            TypeSort::METHOD => {
+                let method = ifc.type_method().entry(i)?;
+                // A member function type on its own is written as the type of a pointer to it,
+                // e.g. `int (C::*)(int) const`, so that the class is not lost. When it is the
+                // type of a pointer to member or of a named declaration, the class is already
+                // in the declarator.
+                if declarator.is_empty() {
+                    let op = format!(
+                        "{}::*",
+                        self.declaration_at_depth(method.scope, "", depth + 1)?
+                    );
+                    self.wrap_pointer(&op, "", ty, declarator)?;
+                }
+                self.write_function(
+                    method.source,
+                    method.convention,
+                    &method.eh_spec,
+                    declarator,
+                    depth,
+                )?;
+                let traits = method.traits;
+                if traits.contains(FunctionTypeTraits::CONST_TRAIT) {
+                    declarator.push_str(" const");
+                }
+                if traits.contains(FunctionTypeTraits::VOLATILE) {
+                    declarator.push_str(" volatile");
+                }
+                if traits.contains(FunctionTypeTraits::LVALUE) {
+                    declarator.push_str(" &");
+                }
+                if traits.contains(FunctionTypeTraits::RVALUE) {
+                    declarator.push_str(" &&");
+                }
+                self.write_declarator(method.target, declarator, depth + 1)
+            }
+            TypeSort::DEDUCED => {
+                // Constructors and destructors have no return type.
+                let tor = ifc.type_tor().entry(i)?;
+                self.write_function(tor.source, tor.convention, &tor.eh_spec, declarator, depth)?;
+                Ok(String::new())
+            }
+            TypeSort::PLACEHOLDER => {
+                let placeholder = ifc.type_placeholder().entry(i)?;
+                if !placeholder.elaboration.is_null() {
+                    self.write_declarator(placeholder.elaboration, declarator, depth + 1)
+                } else if placeholder.basis == TypeBasis::DECLTYPE_AUTO {
+                    Ok("decltype(auto)".to_string())
+                } else {
+                    Ok("auto".to_string())
+                }
+            }
+            _ => self.specifiers(ty, depth),
+        }
+    }
+
+    /// Formats the types that are written entirely before the declarator. This is separate
+    /// from `write_declarator` to keep its stack frame small, since it is recursive.
+    fn specifiers(&self, ty: TypeIndex, depth: u32) -> Result<String> {
+        let ifc = self.ifc;
+        let i = ty.index();
+        Ok(match ty.tag() {
+            TypeSort::FUNDAMENTAL => self.fundamental_string(ifc.type_fundamental().entry(i)?),
+            TypeSort::DESIGNATED => self.decl_name(*ifc.type_designated().entry(i)?, depth + 1)?,
+            TypeSort::TUPLE => {
+                let mut s = String::new();
+                for (n, element) in ifc.iter_type_tuple(ty)?.enumerate() {
+                    if n != 0 {
+                        s.push_str(", ");
+                    }
+                    s.push_str(&self.declaration_at_depth(element, "", depth + 1)?);
+                }
+                s
+            }
+            TypeSort::EXPANSION => {
+                let expansion = ifc.type_expansion().entry(i)?;
+                let pack = self.declaration_at_depth(expansion.pack, "", depth + 1)?;
+                format!("{}...", pack)
+            }
+            TypeSort::FORALL => {
+                let forall = ifc.type_forall().entry(i)?;
+                let subject = self.declaration_at_depth(forall.subject, "", depth + 1)?;
+                format!("template {}", subject)
+            }
+            TypeSort::TYPENAME => {
+                let path = *ifc.type_typename().entry(i)?;
+                format!("typename {}", self.expr_name(path, depth + 1)?)
+            }
+            TypeSort::SYNTACTIC => self.expr_name(*ifc.type_syntactic().entry(i)?, depth + 1)?,
+            // The operand is a syntax tree, which we do not decode.
+            TypeSort::DECLTYPE => "decltype(...)".to_string(),
+            TypeSort::BASE => {
+                let base = ifc.type_base().entry(i)?;
+                let mut s = match base.access {
+                    Access::PUBLIC => "public ".to_string(),
+                    Access::PROTECTED => "protected ".to_string(),
+                    Access::PRIVATE => "private ".to_string(),
+                    _ => String::new(),
+                };
+                if base.specifiers.0 & BaseTypeSpecifiers::SHARED.0 != 0 {
+                    s.push_str("virtual ");
+                }
+                s.push_str(&self.declaration_at_depth(base.ty, "", depth + 1)?);
+                if base.specifiers.0 & BaseTypeSpecifiers::EXPANDED.0 != 0 {
+                    s.push_str("...");
+                }
+                s
+            }
+            // Vendor extensions and syntax trees have no structure that we can print.
+            TypeSort::VENDOR_EXTENSION => "<vendor extension>".to_string(),
+            TypeSort::SYNTAX_TREE => "<syntax tree>".to_string(),
+            _ => "<unknown type>".to_string(),
+        })
+    }
+
+    /// The name of the class, enumeration or alias that a designated type refers to.
+    fn decl_name(&self, decl: DeclIndex, depth: u32) -> Result<String> {
+        let ifc = self.ifc;
+        Ok(if self.options.qualified_names {
+            ifc.qualified_name_at_depth(decl, depth)?.to_string()
+        } else {
+            match ifc.decl_name_at_depth(decl, depth)? {
+                Some(name) => name.into_owned(),
+                None => "<unnamed>".to_string(),
+            }
+        })
+    }
+
+    /// Formats an expression that names an entity, such as the `T::value_type` in
+    /// `typename T::value_type`. Expressions that are not names are written as `<expression>`.
+    fn expr_name(&self, expr: ExprIndex, depth: u32) -> Result<String> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("expression {:?} is nested too deeply (is it cyclic?)", expr);
+        }
+
+        let ifc = self.ifc;
+        let name = |name: NameIndex| -> Result<String> {
+            Ok(ifc.get_name_string_at_depth(name, depth + 1)?.into_owned())
+        };
+        Ok(match ifc.expr(expr)? {
+            Expr::Type(t) => self.declaration_at_depth(t.denotation, "", depth + 1)?,
+            Expr::NamedDecl(named) => self.decl_name(named.resolution, depth + 1)?,
+            Expr::UnresolvedId(id) => name(id.name)?,
+            Expr::UnqualifiedId(id) => name(id.name)?,
+            Expr::SimpleIdentifier(id) => name(id.name)?,
+            Expr::TemplateId(id) => {
+                let mut s = self.expr_name(id.primary, depth + 1)?;
+                s.push('<');
+                for (n, arg) in ifc.iter_expr_tuple(id.arguments)?.enumerate() {
+                    if n != 0 {
+                        s.push_str(", ");
+                    }
+                    s.push_str(&ifc.get_template_argument_string(arg, depth + 1)?);
+                }
+                s.push('>');
+                s
+            }
+            Expr::QualifiedName(qualified) => {
+                let mut s = String::new();
+                for (n, element) in ifc.iter_expr_tuple(qualified.elements)?.enumerate() {
+                    if n != 0 {
+                        s.push_str("::");
+                    }
+                    s.push_str(&self.expr_name(element, depth + 1)?);
+                }
+                s
+            }
+            Expr::Path(path) => format!(
+                "{}::{}",
+                self.expr_name(path.scope, depth + 1)?,
+                self.expr_name(path.member, depth + 1)?
+            ),
+            Expr::TemplateReference(reference) => format!(
+                "{}::{}",
+                self.declaration_at_depth(reference.scope, "", depth + 1)?,
+                name(reference.member_name)?
+            ),
+            _ => "<expression>".to_string(),
+        })
+    }
+
+    /// Writes the operator of a pointer or reference. The operator binds less tightly than
+    /// the `[]` of an array or the `()` of a function, so it needs parentheses around it if
+    /// it points to one of those.
+    fn wrap_pointer(
+        &self,
+        op: &str,
+        cv: &str,
+        pointee: TypeIndex,
+        declarator: &mut String,
+    ) -> Result<()> {
+        let mut s = String::with_capacity(op.len() + cv.len() + declarator.len() + 4);
+        if let Some(convention) = self.pointee_convention(pointee)? {
+            s.push_str(&convention);
+            s.push(' ');
+        }
+        s.push_str(op);
+        s.push_str(cv);
+        if !cv.is_empty() && !declarator.is_empty() {
+            s.push(' ');
+        }
+        s.push_str(declarator);
+
+        if matches!(
+            pointee.tag(),
+            TypeSort::ARRAY | TypeSort::FUNCTION | TypeSort::METHOD | TypeSort::DEDUCED
+        ) {
+            s.insert(0, '(');
+            s.push(')');
+        }
+        *declarator = s;
+        Ok(())
+    }
+
+    /// Writes a cv-qualified type. Qualifiers on a pointer go after the `*`, so that they
+    /// apply to the pointer rather than to the type it points to. Qualifiers on anything else
+    /// go before the type.
+    fn write_qualified(
+        &self,
+        cv: &str,
+        ty: TypeIndex,
+        declarator: &mut String,
+        depth: u32,
+    ) -> Result<String> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("type {:?} is nested too deeply (is it cyclic?)", ty);
+        }
+
+        let i = ty.index();
+        match ty.tag() {
+            TypeSort::POINTER => {
+                let pointee = *self.ifc.type_pointer().entry(i)?;
+                self.wrap_pointer("*", cv, pointee, declarator)?;
+                self.write_declarator(pointee, declarator, depth + 1)
+            }
+            TypeSort::POINTER_TO_MEMBER => {
+                let ptm = self.ifc.type_pointer_to_member().entry(i)?;
+                let scope = self.declaration_at_depth(ptm.scope, "", depth + 1)?;
+                let op = format!("{}::*", scope);
+                self.wrap_pointer(&op, cv, ptm.member, declarator)?;
+                self.write_declarator(ptm.member, declarator, depth + 1)
+            }
+            _ => {
+                let specifiers = self.write_declarator(ty, declarator, depth)?;
+                Ok(if cv.is_empty() {
+                    specifiers
+                } else if specifiers.is_empty() {
+                    cv.to_string()
+                } else {
+                    format!("{} {}", cv, specifiers)
+                })
+            }
+        }
+    }
+
+    /// Writes the parameter list, calling convention and exception specification of a
+    /// function type.
+    fn write_function(
+        &self,
+        source: TypeIndex,
+        convention: CallingConvention,
+        eh_spec: &NoexceptSpecification,
+        declarator: &mut String,
+        depth: u32,
+    ) -> Result<()> {
+        // A pointer to this function has already written the calling convention inside its
+        // parentheses.
+        if self.options.calling_conventions && !declarator.starts_with('(') {
+            let convention = calling_convention_string(convention);
+            if declarator.is_empty() {
+                declarator.push_str(&convention);
+            } else {
+                declarator.insert_str(0, &format!("{} ", convention));
+            }
+        }
+
+        declarator.push('(');
+        if !source.is_null() {
+            declarator.push_str(&self.declaration_at_depth(source, "", depth + 1)?);
+        }
+        declarator.push(')');
+
+        match eh_spec.sort {
+            NoexceptSort::TRUE => declarator.push_str(" noexcept"),
+            NoexceptSort::FALSE => declarator.push_str(" noexcept(false)"),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The calling convention to write inside the parentheses of a pointer to `pointee`, if
+    /// it is a function.
+    fn pointee_convention(&self, pointee: TypeIndex) -> Result<Option<String>> {
+        if !self.options.calling_conventions {
+            return Ok(None);
+        }
+        let convention = match pointee.tag() {
+            TypeSort::FUNCTION => self.ifc.type_function().entry(pointee.index())?.convention,
+            TypeSort::METHOD => self.ifc.type_method().entry(pointee.index())?.convention,
+            TypeSort::DEDUCED => self.ifc.type_tor().entry(pointee.index())?.convention,
+            _ => return Ok(None),
+        };
+        Ok(Some(calling_convention_string(convention)))
+    }
+
+    fn fundamental_string(&self, fundamental: &FundamentalType) -> String {
+        let sign = match fundamental.sign {
+            TypeSign::SIGNED => "signed ",
+            TypeSign::UNSIGNED => "unsigned ",
+            _ => "",
+        };
+        let msvc = self.options.msvc_spellings;
+        let name = match fundamental.basis {
+            TypeBasis::VOID => "void",
+            TypeBasis::BOOL => "bool",
+            TypeBasis::CHAR => match fundamental.precision {
+                TypePrecision::BIT8 => "char8_t",
+                TypePrecision::BIT16 => "char16_t",
+                TypePrecision::BIT32 => "char32_t",
+                _ => "char",
+            },
+            TypeBasis::WCHAR_T => "wchar_t",
+            TypeBasis::INT => match fundamental.precision {
+                TypePrecision::DEFAULT => "int",
+                TypePrecision::SHORT => "short",
+                TypePrecision::LONG => "long",
+                TypePrecision::BIT8 if msvc => "__int8",
+                TypePrecision::BIT8 => "char",
+                TypePrecision::BIT16 if msvc => "__int16",
+                TypePrecision::BIT16 => "short",
+                TypePrecision::BIT32 if msvc => "__int32",
+                TypePrecision::BIT32 => "int",
+                TypePrecision::BIT64 if msvc => "__int64",
+                TypePrecision::BIT64 => "long long",
+                TypePrecision::BIT128 => "__int128",
+                _ => return format!("{:?}", fundamental),
+            },
+            TypeBasis::FLOAT => "float",
+            TypeBasis::DOUBLE => match fundamental.precision {
+                TypePrecision::LONG => "long double",
+                _ => "double",
+            },
+            TypeBasis::NULLPTR => "decltype(nullptr)",
+            TypeBasis::ELLIPSIS => "...",
+            TypeBasis::SEGMENT_TYPE => "__segment",
+            TypeBasis::CLASS => "class",
+            TypeBasis::STRUCT => "struct",
+            TypeBasis::UNION => "union",
+            TypeBasis::ENUM => "enum",
+            TypeBasis::TYPENAME => "typename",
+            TypeBasis::NAMESPACE => "namespace",
+            TypeBasis::INTERFACE => "__interface",
+            TypeBasis::FUNCTION => "function",
+            TypeBasis::EMPTY => "",
+            TypeBasis::VARIABLE_TEMPLATE => "variable_template",
+            TypeBasis::AUTO => "auto",
+            TypeBasis::DECLTYPE_AUTO => "decltype(auto)",
+            _ => return format!("{:?}", fundamental),
+        };
+        match fundamental.basis {
+            TypeBasis::CHAR | TypeBasis::INT => format!("{}{}", sign, name),
+            _ => name.to_string(),
+        }
+    }
+}
+
+fn qualifiers_string(qualifiers: Qualifiers) -> String {
+    let mut words = Vec::new();
+    if qualifiers.contains(Qualifiers::CONST) {
+        words.push("const");
+    }
+    if qualifiers.contains(Qualifiers::VOLATILE) {
+        words.push("volatile");
+    }
+    if qualifiers.contains(Qualifiers::RESTRICT) {
+        words.push("__restrict");
+    }
+    words.join(" ")
+}
+
+pub(crate) fn calling_convention_string(convention: CallingConvention) -> String {
+    match convention {
+        CallingConvention::Cdecl => "__cdecl".to_string(),
+        CallingConvention::Fast => "__fastcall".to_string(),
+        CallingConvention::Std => "__stdcall".to_string(),
+        CallingConvention::This => "__thiscall".to_string(),
+        CallingConvention::Clr => "__clrcall".to_string(),
+        CallingConvention::Vector => "__vectorcall".to_string(),
+        _ => format!("{:?}", convention),
+    }
+}
@@ -327,3 +327,289 @@ pub struct DeclBitfield {
     pub access: Access,
     pub properties: ReachableProperties,
 }
+
+// 8.1 Parameter level and position
+pub type ParameterLevel = u32;
+pub type ParameterPosition = u32;
+
+/// DeclSort::PARAMETER
+/// `decl.parameter`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclParameter {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub constraint: ExprIndex,
+    pub initializer: ExprIndex,
+    pub level: ParameterLevel,
+    pub position: ParameterPosition,
+    pub sort: ParameterSort,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 2],
+}
+
+#[c_enum(storage = "u8")]
+pub enum ParameterSort {
+    OBJECT = 0,   // Function parameter
+    TYPE = 1,     // Type template parameter
+    NON_TYPE = 2, // Non-type template parameter
+    TEMPLATE = 3, // Template template parameter
+}
+
+/// The declaration being parameterized by a template, or by the template parameter lists of
+/// an enclosing scope.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct ParameterizedEntity {
+    pub decl: DeclIndex,
+    pub head: SentenceIndex,
+    pub body: SentenceIndex,
+    pub attributes: SentenceIndex,
+}
+
+/// DeclSort::TEMPLOID
+/// `decl.temploid`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclTemploid {
+    pub entity: ParameterizedEntity,
+    pub chart: ChartIndex,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 3],
+}
+
+/// DeclSort::TEMPLATE
+/// `decl.template`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclTemplate {
+    pub name: NameIndex,
+    pub locus: SourceLocation,
+    pub home_scope: DeclIndex,
+    pub chart: ChartIndex,
+    pub entity: ParameterizedEntity,
+    pub ty: TypeIndex,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 1],
+}
+
+/// DeclSort::PARTIAL_SPECIALIZATION
+/// `decl.partial-specialization`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclPartialSpecialization {
+    pub name: NameIndex,
+    pub locus: SourceLocation,
+    pub home_scope: DeclIndex,
+    pub chart: ChartIndex,
+    pub entity: ParameterizedEntity,
+    /// Index into `form.spec`.
+    pub form: Index,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 1],
+}
+
+/// DeclSort::EXPLICIT_SPECIALIZATION, which the spec calls `DeclSort::Specialization`.
+/// `decl.specialization`
+///
+/// Explicit instantiations are also stored here, with `SpecializationSort::INSTANTIATION`.
+/// The spec does not define a partition for `DeclSort::EXPLICIT_INSTANTIATION` (0x0D); it
+/// calls that sort `UnusedSort0`.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclSpecialization {
+    /// Index into `form.spec`.
+    pub form: Index,
+    pub decl: DeclIndex,
+    pub sort: SpecializationSort,
+    pub __padding: [u8; 3],
+}
+
+#[c_enum(storage = "u8")]
+pub enum SpecializationSort {
+    IMPLICIT = 0,
+    EXPLICIT = 1,
+    INSTANTIATION = 2,
+}
+
+/// DeclSort::CONCEPT
+/// `decl.concept`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclConcept {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub home_scope: DeclIndex,
+    pub ty: TypeIndex,
+    pub chart: ChartIndex,
+    pub constraint: ExprIndex,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub __padding: [u8; 2],
+    pub head: SentenceIndex,
+    pub body: SentenceIndex,
+}
+
+/// DeclSort::CONSTRUCTOR
+/// `decl.constructor`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclConstructor {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub type_: TypeIndex,
+    pub home_scope: DeclIndex,
+    pub chart: ChartIndex,
+    pub traits: FunctionTraits,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 3],
+}
+
+/// DeclSort::INHERITED_CONSTRUCTOR
+/// `decl.inherited-constructor`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclInheritedConstructor {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub type_: TypeIndex,
+    pub home_scope: DeclIndex,
+    pub chart: ChartIndex,
+    pub traits: FunctionTraits,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub base_ctor: DeclIndex,
+}
+
+/// DeclSort::DESTRUCTOR
+/// `decl.destructor`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclDestructor {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub home_scope: DeclIndex,
+    pub eh_spec: NoexceptSpecification,
+    pub traits: FunctionTraits,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub convention: CallingConvention,
+    pub properties: ReachableProperties,
+    pub __padding: [u8; 2],
+}
+
+/// DeclSort::REFERENCE
+/// `decl.reference`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclReference {
+    pub unit: ModuleReference,
+    pub local_index: DeclIndex,
+}
+
+/// Identifies the module (or header unit) that owns a declaration. If `owner` is null, then
+/// `partition` is the name of the source file of a header unit. Otherwise, `owner` is the name
+/// of the module and `partition` is the name of the module partition, if any.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct ModuleReference {
+    pub owner: TextOffset,
+    pub partition: TextOffset,
+}
+
+/// DeclSort::USING_DECLARATION
+/// `decl.using-declaration`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclUsingDeclaration {
+    pub name: NameIndex,
+    pub locus: SourceLocation,
+    pub home_scope: DeclIndex,
+    pub resolution: DeclIndex,
+    pub parent: ExprIndex,
+    pub name2: TextOffset,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    /// Nonzero if the member is hidden.
+    pub hidden: u8,
+    pub __padding: [u8; 1],
+}
+
+/// DeclSort::EXPANSION
+/// `decl.expansion`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclExpansion {
+    pub operand: DeclIndex,
+    pub locus: SourceLocation,
+}
+
+/// DeclSort::DEDUCTION_GUIDE
+/// `decl.deduction-guide`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclDeductionGuide {
+    pub name: NameIndex,
+    pub locus: SourceLocation,
+    pub parameters: ChartIndex,
+    pub specialization: TypeIndex,
+    pub traits: FunctionTraits,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+}
+
+/// DeclSort::TUPLE
+/// `decl.tuple`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclTuple {
+    /// Index into `heap.decl`.
+    pub start: Index,
+    pub cardinality: Cardinality,
+}
+
+/// DeclSort::INTRINSIC
+/// `decl.intrinsic`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclIntrinsic {
+    pub name: TextOffset,
+    pub locus: SourceLocation,
+    pub type_: TypeIndex,
+    pub home_scope: DeclIndex,
+    pub specifiers: BasicSpecifiers,
+    pub access: Access,
+    pub __padding: [u8; 2],
+}
+
+/// DeclSort::PROPERTY, MSVC's `__declspec(property)`
+/// `decl.property`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclProperty {
+    pub member: DeclIndex,
+    pub getter: TextOffset,
+    pub setter: TextOffset,
+}
+
+pub type SegmentTraits = u32;
+pub type SegmentType = u8;
+
+/// DeclSort::OUTPUT_SEGMENT, a code segment declared with a pragma
+/// `decl.segment`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct DeclSegment {
+    pub name: TextOffset,
+    pub id: TextOffset,
+    pub traits: SegmentTraits,
+    pub ty: SegmentType,
+    pub __padding: [u8; 3],
+}
@@ -0,0 +1,298 @@
+//! Comparing two versions of a unit, to find changes to its ABI
+
+use super::*;
+use std::collections::BTreeMap;
+
+/// The kind of entity that a `Change` is about.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EntityKind {
+    /// The size and alignment of a class, struct or union.
+    Class,
+    /// The type and offset of a field or bitfield.
+    Field,
+    /// The value of an enumerator.
+    Enumerator,
+    /// The signature of a function or method, including its calling convention.
+    Function,
+    /// The type that a typedef or alias names.
+    Typedef,
+    /// The parameters and body of a macro.
+    Macro,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A difference between two versions of a unit, as found by `diff`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde_derive::Serialize))]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub entity: EntityKind,
+    /// The qualified name of the entity, or the name of a macro.
+    pub name: String,
+    /// The entity in the old unit, e.g. `int __cdecl f(int)` for a function or
+    /// `int at offset 4` for a field. This is `None` if the entity was added.
+    pub old: Option<String>,
+    /// The entity in the new unit. This is `None` if the entity was removed.
+    pub new: Option<String>,
+}
+
+impl core::fmt::Display for EntityKind {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt.write_str(match self {
+            Self::Class => "class",
+            Self::Field => "field",
+            Self::Enumerator => "enumerator",
+            Self::Function => "function",
+            Self::Typedef => "typedef",
+            Self::Macro => "macro",
+        })
+    }
+}
+
+impl core::fmt::Display for ChangeKind {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt.write_str(match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        })
+    }
+}
+
+impl core::fmt::Display for Change {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "{} {} {}", self.kind, self.entity, self.name)?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(fmt, ": {} -> {}", old, new),
+            (Some(desc), None) | (None, Some(desc)) => write!(fmt, ": {}", desc),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Compares two versions of a unit, such as a header unit built from two versions of an SDK.
+/// Entities are matched by kind and qualified name, and the changes are sorted by kind and
+/// name.
+///
+/// Overloaded functions share a name, so they are compared as a group: adding an overload
+/// shows up as a change to the function.
+pub fn diff(old: &Ifc, new: &Ifc) -> Result<Vec<Change>> {
+    let old_entities = abi_entities(old)?;
+    let mut new_entities = abi_entities(new)?;
+
+    let mut changes = Vec::new();
+    for ((entity, name), old_desc) in old_entities {
+        let (kind, new_desc) = match new_entities.remove(&(entity, name.clone())) {
+            Some(new_desc) if new_desc == old_desc => continue,
+            Some(new_desc) => (ChangeKind::Changed, Some(new_desc)),
+            None => (ChangeKind::Removed, None),
+        };
+        changes.push(Change {
+            kind,
+            entity,
+            name,
+            old: Some(old_desc),
+            new: new_desc,
+        });
+    }
+    for ((entity, name), new_desc) in new_entities {
+        changes.push(Change {
+            kind: ChangeKind::Added,
+            entity,
+            name,
+            old: None,
+            new: Some(new_desc),
+        });
+    }
+    changes.sort_by(|a, b| (a.entity, &a.name).cmp(&(b.entity, &b.name)));
+    Ok(changes)
+}
+
+/// Describes each entity of a unit that matters to its ABI.
+fn abi_entities(ifc: &Ifc) -> Result<BTreeMap<(EntityKind, String), String>> {
+    let mut collector = AbiCollector {
+        printer: CxxTypePrinter::new(
+            ifc,
+            CxxPrintOptions {
+                calling_conventions: true,
+                qualified_names: true,
+                ..CxxPrintOptions::default()
+            },
+        ),
+        entities: BTreeMap::new(),
+        fields: HashMap::new(),
+        current_enum: None,
+    };
+    ifc.visit(&mut collector)?;
+
+    for m in ifc.macro_object_like().entries.iter() {
+        let body = ifc.form_tokens(m.body)?.join(" ");
+        collector.add(EntityKind::Macro, ifc.get_string(m.name)?.to_string(), body);
+    }
+    for m in ifc.macro_function_like().entries.iter() {
+        let mut params = if m.arity() == 0 {
+            Vec::new()
+        } else {
+            ifc.form_tokens(m.parameters)?
+        };
+        if m.is_variadic() {
+            params.push("...".to_string());
+        }
+        let body = ifc.form_tokens(m.body)?.join(" ");
+        let desc = format!("({}) {}", params.join(", "), body);
+        collector.add(EntityKind::Macro, ifc.get_string(m.name)?.to_string(), desc);
+    }
+
+    // A name can have several descriptions, e.g. for overloaded functions. The order in which
+    // they were declared is not part of the ABI.
+    Ok(collector
+        .entities
+        .into_iter()
+        .map(|(key, mut descs)| {
+            descs.sort();
+            (key, descs.join("; "))
+        })
+        .collect())
+}
+
+struct AbiCollector<'a, 'data> {
+    printer: CxxTypePrinter<'a, 'data>,
+    entities: BTreeMap<(EntityKind, String), Vec<String>>,
+    /// The layouts of the fields of the classes that have been visited.
+    fields: HashMap<DeclIndex, FieldLayout>,
+    /// The qualified name of the enumeration whose enumerators are being visited.
+    current_enum: Option<String>,
+}
+
+impl<'a, 'data> AbiCollector<'a, 'data> {
+    fn add(&mut self, entity: EntityKind, name: String, desc: String) {
+        self.entities.entry((entity, name)).or_default().push(desc);
+    }
+
+    fn describe_field(&self, decl: DeclIndex, ty: TypeIndex, width: Option<u32>) -> Result<String> {
+        let mut desc = self.printer.type_string(ty)?;
+        if let Some(width) = width {
+            desc.push_str(&format!(" : {}", width));
+        }
+        if let Some(layout) = self.fields.get(&decl) {
+            desc.push_str(&format!(" at offset {}", layout.offset));
+            if let Some(bits) = &layout.bits {
+                desc.push_str(&format!(" bit {}", bits.offset));
+            }
+        }
+        Ok(desc)
+    }
+}
+
+impl<'a, 'data> DeclVisitor for AbiCollector<'a, 'data> {
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        // Forward declarations say nothing about the layout.
+        if scope.initializer == 0 {
+            return Ok(false);
+        }
+        let desc = match ifc.class_layout(decl) {
+            Ok(layout) => {
+                for field in layout.fields.iter() {
+                    self.fields.insert(field.decl, field.clone());
+                }
+                format!("size {}, align {}", layout.size, layout.align)
+            }
+            // The error names indexes that differ between builds, so leave it out.
+            Err(_) => "unknown layout".to_string(),
+        };
+        self.add(
+            EntityKind::Class,
+            ifc.qualified_name(decl)?.to_string(),
+            desc,
+        );
+        Ok(true)
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, _en: &DeclEnum) -> Result<bool> {
+        self.current_enum = Some(ifc.qualified_name(decl)?.to_string());
+        Ok(true)
+    }
+
+    fn leave_enum(&mut self, _ifc: &Ifc, _decl: DeclIndex, _en: &DeclEnum) -> Result<()> {
+        self.current_enum = None;
+        Ok(())
+    }
+
+    fn visit_enumerator(
+        &mut self,
+        ifc: &Ifc,
+        _decl: DeclIndex,
+        enumerator: &DeclEnumerator,
+    ) -> Result<()> {
+        let name = ifc.get_string(enumerator.name)?;
+        let name = match &self.current_enum {
+            Some(en) => format!("{}::{}", en, name),
+            None => name.to_string(),
+        };
+        let value = match ifc.eval_const(enumerator.initializer) {
+            Ok(ConstValue::Int(value)) => value.to_string(),
+            Ok(ConstValue::Bool(value)) => value.to_string(),
+            Ok(ConstValue::Float(value)) => value.to_string(),
+            Err(_) => "unknown value".to_string(),
+        };
+        self.add(EntityKind::Enumerator, name, value);
+        Ok(())
+    }
+
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, func: &DeclFunc) -> Result<()> {
+        let name = ifc.qualified_name(decl)?.to_string();
+        let desc = self.printer.declaration(func.type_, &name)?;
+        self.add(EntityKind::Function, name, desc);
+        Ok(())
+    }
+
+    fn visit_method(&mut self, ifc: &Ifc, decl: DeclIndex, method: &DeclMethod) -> Result<()> {
+        self.visit_function(ifc, decl, method)
+    }
+
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, field: &DeclField) -> Result<()> {
+        let desc = self.describe_field(decl, field.ty, None)?;
+        self.add(
+            EntityKind::Field,
+            ifc.qualified_name(decl)?.to_string(),
+            desc,
+        );
+        Ok(())
+    }
+
+    fn visit_bitfield(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        bitfield: &DeclBitfield,
+    ) -> Result<()> {
+        let width = ifc.get_literal_expr_u32(bitfield.width).ok();
+        let desc = self.describe_field(decl, bitfield.ty, width)?;
+        self.add(
+            EntityKind::Field,
+            ifc.qualified_name(decl)?.to_string(),
+            desc,
+        );
+        Ok(())
+    }
+
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, alias: &DeclAlias) -> Result<()> {
+        let desc = self.printer.type_string(alias.aliasee)?;
+        self.add(
+            EntityKind::Typedef,
+            ifc.qualified_name(decl)?.to_string(),
+            desc,
+        );
+        Ok(())
+    }
+}
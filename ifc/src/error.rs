@@ -0,0 +1,115 @@
+//! Errors reported by the IFC reader
+
+use super::*;
+
+pub type IfcResult<T> = core::result::Result<T, IfcError>;
+
+/// Describes a problem found while reading an IFC.
+#[derive(Debug)]
+pub enum IfcError {
+    /// The file could not be read.
+    Io(std::io::Error),
+
+    /// The file does not start with `IFC_FILE_SIGNATURE`.
+    BadSignature,
+
+    /// The file is too small to contain the file header.
+    TruncatedHeader,
+
+    /// The checksum stored in `FileHeader::checksum` does not match the contents of the file.
+    ChecksumMismatch {
+        /// The checksum stored in the file header.
+        expected: Sha256,
+        /// The checksum computed from the file contents.
+        actual: Sha256,
+    },
+
+    /// The string table described by the file header is not within the file.
+    StringTableOutOfBounds { range: Range<usize> },
+
+    /// A partition described by the table of contents is not within the file.
+    PartitionOutOfBounds { name: String, range: Range<usize> },
+
+    /// A partition claims to have records, but its record size is zero.
+    BadEntitySize { name: String },
+
+    /// An index into a partition is out of range.
+    BadEntryIndex {
+        partition: &'static str,
+        index: u32,
+        len: usize,
+    },
+
+    /// A `TextOffset` does not point to a valid string. Either it is outside of the string
+    /// table, the string is not NUL-terminated, or the string is not valid UTF-8.
+    BadString { offset: TextOffset },
+
+    /// A scope index is zero (no scope), or its scope descriptor points outside of
+    /// `scope.member`.
+    BadScope { scope: ScopeIndex },
+
+    /// A composite name, such as a template specialization, cannot be rendered, e.g. because
+    /// it contains itself.
+    BadName { name: NameIndex, message: String },
+}
+
+impl core::fmt::Display for IfcError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Io(e) => write!(fmt, "IFC: failed to read file: {}", e),
+            Self::BadSignature => fmt.write_str("IFC: file does not have IFC signature"),
+            Self::TruncatedHeader => fmt.write_str("IFC: file is too small to contain a header"),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                fmt,
+                "IFC: checksum does not match file contents. header: {:?}, computed: {:?}",
+                expected, actual
+            ),
+            Self::StringTableOutOfBounds { range } => write!(
+                fmt,
+                "IFC: string table range {:?} is outside the file",
+                range
+            ),
+            Self::PartitionOutOfBounds { name, range } => write!(
+                fmt,
+                "IFC: partition '{}' is invalid; its range {:?} is outside the file",
+                name, range
+            ),
+            Self::BadEntitySize { name } => {
+                write!(fmt, "IFC: partition '{}' has records of size zero", name)
+            }
+            Self::BadEntryIndex {
+                partition,
+                index,
+                len,
+            } => write!(
+                fmt,
+                "IFC: bad entry index in partition '{}'. index: {}, len: {}",
+                partition, index, len
+            ),
+            Self::BadString { offset } => {
+                write!(fmt, "IFC: bad string at text offset {}", offset)
+            }
+            Self::BadScope { scope } => {
+                write!(fmt, "IFC: invalid scope member range for scope {}", scope)
+            }
+            Self::BadName { name, message } => {
+                write!(fmt, "IFC: cannot render name {:?}: {}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IfcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IfcError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
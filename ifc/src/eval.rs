@@ -0,0 +1,353 @@
+//! Evaluation of constant expressions
+
+use super::*;
+use core::convert::TryFrom;
+
+/// Constant expressions can refer to other constants (enumerators and `constexpr` variables),
+/// so a malformed file could contain a cycle.
+const MAX_EVAL_DEPTH: u32 = 128;
+
+/// The value of a constant expression, as computed by `Ifc::eval_const`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConstValue {
+    Bool(bool),
+    /// An integer value. This is wide enough to hold every value of every signed and unsigned
+    /// integer type up to 64 bits, so the value is always the mathematical value of the
+    /// expression, after wrapping it to the expression's type if the type is known.
+    Int(i128),
+    Float(f64),
+}
+
+impl ConstValue {
+    /// Returns the value as a `u64`, if it is an integer or bool and fits. Negative values are
+    /// reinterpreted as their two's complement bit pattern.
+    pub fn to_u64(&self) -> Option<u64> {
+        match *self {
+            Self::Bool(b) => Some(b as u64),
+            Self::Int(i) if i >= i64::MIN as i128 && i <= u64::MAX as i128 => Some(i as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an `i64`, if it is an integer or bool and fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Bool(b) => Some(b as i64),
+            Self::Int(i) => i64::try_from(i).ok(),
+            Self::Float(_) => None,
+        }
+    }
+
+    fn to_int(self) -> Option<i128> {
+        match self {
+            Self::Bool(b) => Some(b as i128),
+            Self::Int(i) => Some(i),
+            Self::Float(_) => None,
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        match self {
+            Self::Bool(b) => b as u8 as f64,
+            Self::Int(i) => i as f64,
+            Self::Float(f) => f,
+        }
+    }
+
+    fn is_true(self) -> bool {
+        match self {
+            Self::Bool(b) => b,
+            Self::Int(i) => i != 0,
+            Self::Float(f) => f != 0.0,
+        }
+    }
+}
+
+/// The representation of an arithmetic type, which determines how values are converted to it.
+#[derive(Copy, Clone, Debug)]
+enum Arith {
+    Bool,
+    Int { bits: u32, signed: bool },
+    Float { bits: u32 },
+}
+
+impl<'data> Ifc<'data> {
+    /// Evaluates a constant expression, such as an array extent, a bitfield width, or an
+    /// enumerator initializer.
+    ///
+    /// This handles literals, the arithmetic, bitwise, comparison and logical operators, casts
+    /// to arithmetic types, `sizeof` and `alignof` of complete types, and references to
+    /// enumerators and variables that have initializers. Integer arithmetic wraps to the type of
+    /// each subexpression when that type is known.
+    pub fn eval_const(&self, expr: ExprIndex) -> Result<ConstValue> {
+        self.eval_const_at_depth(expr, 0)
+    }
+
+    pub(crate) fn eval_const_at_depth(&self, expr: ExprIndex, depth: u32) -> Result<ConstValue> {
+        if depth > MAX_EVAL_DEPTH {
+            bail!("expression {:?} is nested too deeply (is it cyclic?)", expr);
+        }
+        let eval = |e| self.eval_const_at_depth(e, depth + 1);
+
+        Ok(match self.expr(expr)? {
+            Expr::Literal(literal) => match literal.value.tag() {
+                LiteralSort::IMMEDIATE => ConstValue::Int(literal.value.index() as i128),
+                LiteralSort::INTEGER => {
+                    ConstValue::Int(*self.const_i64().entry(literal.value.index())? as i128)
+                }
+                LiteralSort::FLOATING_POINT => {
+                    let f = self.const_f64().entry(literal.value.index())?;
+                    ConstValue::Float(f64::from_le_bytes(f.f64_bytes))
+                }
+                _ => bail!("unrecognized literal value: {:?}", literal.value),
+            },
+
+            Expr::NamedDecl(named) => {
+                let resolution = named.resolution;
+                match resolution.tag() {
+                    DeclSort::ENUMERATOR => {
+                        let e = self.decl_enumerator().entry(resolution.index())?;
+                        self.convert_const(eval(e.initializer)?, e.ty)?
+                    }
+                    DeclSort::VARIABLE => {
+                        let var = self.decl_var().entry(resolution.index())?;
+                        if var.initializer.0 == 0 {
+                            bail!("variable {:?} does not have an initializer", resolution);
+                        }
+                        self.convert_const(eval(var.initializer)?, var.ty)?
+                    }
+                    _ => bail!("{:?} does not refer to a constant", resolution),
+                }
+            }
+
+            Expr::Read(read) => self.convert_const(eval(read.address)?, read.ty)?,
+            Expr::Condition(c) => eval(c.expr)?,
+            Expr::Initializer(init) => eval(init.expr)?,
+            Expr::AssignInitializer(init) => eval(init.initializer)?,
+            Expr::ExpressionList(list) => self.eval_single(list.contents, depth)?,
+            Expr::InitializerList(list) => {
+                self.convert_const(self.eval_single(list.elements, depth)?, list.ty)?
+            }
+
+            Expr::Monad(monad) => {
+                let value = match monad.assoc {
+                    MonadicOperator::PAREN | MonadicOperator::READ => eval(monad.argument)?,
+                    op => eval_monadic(op, eval(monad.argument)?)?,
+                };
+                self.convert_const(value, monad.ty)?
+            }
+
+            Expr::Dyad(dyad) => {
+                let left = eval(dyad.arguments[0])?;
+                let value = match dyad.assoc {
+                    // These only evaluate their right operand when they have to.
+                    DyadicOperator::LOGIC_AND if !left.is_true() => ConstValue::Bool(false),
+                    DyadicOperator::LOGIC_OR if left.is_true() => ConstValue::Bool(true),
+                    DyadicOperator::LOGIC_AND | DyadicOperator::LOGIC_OR => {
+                        ConstValue::Bool(eval(dyad.arguments[1])?.is_true())
+                    }
+                    DyadicOperator::COMMA => eval(dyad.arguments[1])?,
+                    op => eval_dyadic(op, left, eval(dyad.arguments[1])?)?,
+                };
+                self.convert_const(value, dyad.ty)?
+            }
+
+            Expr::Triad(triad) if triad.assoc == TriadicOperator::CHOICE => {
+                let chosen = if eval(triad.arguments[0])?.is_true() {
+                    triad.arguments[1]
+                } else {
+                    triad.arguments[2]
+                };
+                self.convert_const(eval(chosen)?, triad.ty)?
+            }
+
+            Expr::Cast(cast) => match cast.operator {
+                DyadicOperator::REINTERPRET_CAST | DyadicOperator::DYNAMIC_CAST => {
+                    bail!("{:?} is not a constant conversion", cast.operator)
+                }
+                _ => {
+                    let value = eval(cast.source)?;
+                    if self.arith_type(cast.target)?.is_none() {
+                        bail!("cannot evaluate a cast to {:?}", cast.target);
+                    }
+                    self.convert_const(value, cast.target)?
+                }
+            },
+
+            Expr::SizeofType(s) => {
+                let layout = self.type_layout_at_depth(s.operand, depth + 1)?;
+                self.convert_const(ConstValue::Int(layout.size as i128), s.ty)?
+            }
+            Expr::Alignof(s) => {
+                let layout = self.type_layout_at_depth(s.operand, depth + 1)?;
+                self.convert_const(ConstValue::Int(layout.align as i128), s.ty)?
+            }
+
+            e => bail!("cannot evaluate {:?} as a constant: {:?}", expr, e),
+        })
+    }
+
+    /// Evaluates an expression list or initializer list that contains exactly one element,
+    /// such as the `{5}` in `constexpr int x{5};`.
+    fn eval_single(&self, elements: ExprIndex, depth: u32) -> Result<ConstValue> {
+        let mut iter = self.iter_expr_tuple(elements)?;
+        match (iter.next(), iter.next()) {
+            (Some(e), None) => self.eval_const_at_depth(e, depth + 1),
+            _ => bail!("expected a single element in {:?}", elements),
+        }
+    }
+
+    /// Describes an arithmetic type. Returns `None` for types that are not arithmetic (or are
+    /// not known, such as a null type).
+    fn arith_type(&self, ty: TypeIndex) -> Result<Option<Arith>> {
+        if ty.is_null() {
+            return Ok(None);
+        }
+        let ty = self.remove_qualifiers(ty)?;
+        Ok(match ty.tag() {
+            TypeSort::FUNDAMENTAL => {
+                let f = self.type_fundamental().entry(ty.index())?;
+                let bits = match f.precision {
+                    TypePrecision::BIT8 => 8,
+                    TypePrecision::BIT16 | TypePrecision::SHORT => 16,
+                    TypePrecision::BIT64 => 64,
+                    TypePrecision::BIT128 => return Ok(None),
+                    _ => 32,
+                };
+                match f.basis {
+                    TypeBasis::BOOL => Some(Arith::Bool),
+                    TypeBasis::CHAR => Some(Arith::Int {
+                        bits: 8,
+                        signed: f.sign != TypeSign::UNSIGNED,
+                    }),
+                    TypeBasis::WCHAR_T => Some(Arith::Int {
+                        bits: 16,
+                        signed: false,
+                    }),
+                    TypeBasis::INT => Some(Arith::Int {
+                        bits,
+                        signed: f.sign != TypeSign::UNSIGNED,
+                    }),
+                    TypeBasis::FLOAT => Some(Arith::Float { bits: 32 }),
+                    // `long double` is the same as `double` in MSVC.
+                    TypeBasis::DOUBLE => Some(Arith::Float { bits: 64 }),
+                    _ => None,
+                }
+            }
+            TypeSort::DESIGNATED => {
+                let decl = *self.type_designated().entry(ty.index())?;
+                if decl.tag() != DeclSort::ENUMERATION {
+                    return Ok(None);
+                }
+                // The base of an enum is always an integer type. Checking this also keeps a
+                // malformed file from sending us around in circles.
+                let en = self.decl_enum().entry(decl.index())?;
+                if en.base.tag() == TypeSort::DESIGNATED {
+                    bail!("the base of enum {:?} is not an integer type", decl);
+                }
+                self.arith_type(en.base)?
+            }
+            _ => None,
+        })
+    }
+
+    /// Converts a value to an arithmetic type. If `ty` is not an arithmetic type (including
+    /// the null type that MSVC often uses for literals), the value is returned unchanged.
+    fn convert_const(&self, value: ConstValue, ty: TypeIndex) -> Result<ConstValue> {
+        Ok(match self.arith_type(ty)? {
+            None => value,
+            Some(Arith::Bool) => ConstValue::Bool(value.is_true()),
+            Some(Arith::Float { bits: 32 }) => ConstValue::Float(value.to_f64() as f32 as f64),
+            Some(Arith::Float { .. }) => ConstValue::Float(value.to_f64()),
+            Some(Arith::Int { bits, signed }) => {
+                let i = match value {
+                    ConstValue::Float(f) => f as i128,
+                    v => v.to_int().unwrap(),
+                };
+                let shift = 128 - bits;
+                ConstValue::Int(if signed {
+                    (i << shift) >> shift
+                } else {
+                    ((i as u128) << shift >> shift) as i128
+                })
+            }
+        })
+    }
+}
+
+fn eval_monadic(op: MonadicOperator, value: ConstValue) -> Result<ConstValue> {
+    Ok(match (op, value) {
+        (MonadicOperator::PLUS, v) => v,
+        (MonadicOperator::NOT, v) => ConstValue::Bool(!v.is_true()),
+        (MonadicOperator::NEGATE, ConstValue::Float(f)) => ConstValue::Float(-f),
+        (MonadicOperator::NEGATE, v) => match v.to_int().unwrap().checked_neg() {
+            Some(i) => ConstValue::Int(i),
+            None => bail!("{:?} {:?} overflows", op, v),
+        },
+        (MonadicOperator::COMPLEMENT, ConstValue::Float(_)) => {
+            bail!("cannot complement a floating-point value")
+        }
+        (MonadicOperator::COMPLEMENT, v) => ConstValue::Int(!v.to_int().unwrap()),
+        (op, _) => bail!("cannot evaluate monadic operator {:?}", op),
+    })
+}
+
+fn eval_dyadic(op: DyadicOperator, left: ConstValue, right: ConstValue) -> Result<ConstValue> {
+    use core::cmp::Ordering;
+
+    let ordering = match (left, right) {
+        (ConstValue::Float(_), _) | (_, ConstValue::Float(_)) => {
+            left.to_f64().partial_cmp(&right.to_f64())
+        }
+        _ => Some(left.to_int().unwrap().cmp(&right.to_int().unwrap())),
+    };
+    let compare = |f: fn(Ordering) -> bool| ConstValue::Bool(matches!(ordering, Some(o) if f(o)));
+    match op {
+        DyadicOperator::EQUAL => return Ok(compare(|o| o == Ordering::Equal)),
+        DyadicOperator::NOT_EQUAL => {
+            return Ok(ConstValue::Bool(ordering != Some(Ordering::Equal)))
+        }
+        DyadicOperator::LESS => return Ok(compare(|o| o == Ordering::Less)),
+        DyadicOperator::LESS_EQUAL => return Ok(compare(|o| o != Ordering::Greater)),
+        DyadicOperator::GREATER => return Ok(compare(|o| o == Ordering::Greater)),
+        DyadicOperator::GREATER_EQUAL => return Ok(compare(|o| o != Ordering::Less)),
+        _ => {}
+    }
+
+    if let (Some(a), Some(b)) = (left.to_int(), right.to_int()) {
+        let result = match op {
+            DyadicOperator::PLUS => a.checked_add(b),
+            DyadicOperator::MINUS => a.checked_sub(b),
+            DyadicOperator::MULT => a.checked_mul(b),
+            DyadicOperator::SLASH | DyadicOperator::MODULO | DyadicOperator::REMAINDER
+                if b == 0 =>
+            {
+                bail!("division by zero")
+            }
+            DyadicOperator::SLASH => a.checked_div(b),
+            DyadicOperator::MODULO | DyadicOperator::REMAINDER => a.checked_rem(b),
+            DyadicOperator::BITAND => Some(a & b),
+            DyadicOperator::BITOR => Some(a | b),
+            DyadicOperator::BITXOR => Some(a ^ b),
+            DyadicOperator::LSHIFT => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)),
+            DyadicOperator::RSHIFT => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)),
+            _ => bail!("cannot evaluate dyadic operator {:?}", op),
+        };
+        match result {
+            Some(i) => Ok(ConstValue::Int(i)),
+            None => bail!("{:?} {:?} {:?} overflows", a, op, b),
+        }
+    } else {
+        let (a, b) = (left.to_f64(), right.to_f64());
+        Ok(ConstValue::Float(match op {
+            DyadicOperator::PLUS => a + b,
+            DyadicOperator::MINUS => a - b,
+            DyadicOperator::MULT => a * b,
+            DyadicOperator::SLASH => a / b,
+            _ => bail!(
+                "cannot evaluate dyadic operator {:?} on floating-point values",
+                op
+            ),
+        }))
+    }
+}
@@ -12,42 +12,84 @@ tagged_index! {
 
 #[c_enum(storage = "u32")]
 pub enum ExprSort {
-    VENDOR_EXTENSION = 0,
-    EMPTY = 1,
-    LITERAL = 2,
-    LAMBDA = 3,
-    TYPE = 4,
-    NAMED_DECL = 5,
-    UNRESOLVED_ID = 6,
-    TEMPLATE_ID = 6,
-    UNQUALIFIED_ID = 8,
-    SIMPLE_IDENTIFIER = 9,
-    POINTER = 10,
-    QUALIFIED_NAME = 11,
-    PATH = 12,
-    READ = 13,
-    MONAD = 14,
-    DYAD = 15,
-    TRIAD = 16,
-    STRING = 17,
-    TEMPORARY = 18,
-    CALL = 19,
-    MEMBER_INITIALIZER = 20,
-    MEMBER_ACCESS = 21,
-    INHERITANCE_PATH = 22,
-    INITIALIZER_LIST = 23,
-    CAST = 24,
-    CONDITION = 25,
-    EXPRESSION_LIST = 26,
-    SIZEOF_TYPE = 27,
-    ALIGNOF = 28,
-    NEW = 29,
-    DELETE = 30,
-    TYPEID = 31,
-    DESTRUCTOR_CALL = 32,
-    SYNTAX_TREE = 33,
+    VENDOR_EXTENSION = 0x00,
+    EMPTY = 0x01,
+    LITERAL = 0x02,
+    LAMBDA = 0x03,
+    TYPE = 0x04,
+    NAMED_DECL = 0x05,
+    UNRESOLVED_ID = 0x06,
+    TEMPLATE_ID = 0x07,
+    UNQUALIFIED_ID = 0x08,
+    SIMPLE_IDENTIFIER = 0x09,
+    POINTER = 0x0A,
+    QUALIFIED_NAME = 0x0B,
+    PATH = 0x0C,
+    READ = 0x0D,
+    MONAD = 0x0E,
+    DYAD = 0x0F,
+    TRIAD = 0x10,
+    STRING = 0x11,
+    TEMPORARY = 0x12,
+    CALL = 0x13,
+    MEMBER_INITIALIZER = 0x14,
+    MEMBER_ACCESS = 0x15,
+    INHERITANCE_PATH = 0x16,
+    INITIALIZER_LIST = 0x17,
+    CAST = 0x18,
+    CONDITION = 0x19,
+    EXPRESSION_LIST = 0x1A,
+    SIZEOF_TYPE = 0x1B,
+    ALIGNOF = 0x1C,
+    NEW = 0x1D,
+    DELETE = 0x1E,
+    TYPEID = 0x1F,
+    DESTRUCTOR_CALL = 0x20,
+    SYNTAX_TREE = 0x21,
+    FUNCTION_STRING = 0x22,
+    COMPOUND_STRING = 0x23,
+    STRING_SEQUENCE = 0x24,
+    INITIALIZER = 0x25,
+    REQUIRES = 0x26,
+    UNARY_FOLD = 0x27,
+    BINARY_FOLD = 0x28,
+    HIERARCHY_CONVERSION = 0x29,
+    PRODUCT_TYPE_VALUE = 0x2A,
+    SUM_TYPE_VALUE = 0x2B,
+    SUBOBJECT_VALUE = 0x2C,
+    ARRAY_VALUE = 0x2D,
+    DYNAMIC_DISPATCH = 0x2E,
+    VIRTUAL_FUNCTION_CONVERSION = 0x2F,
+    PLACEHOLDER = 0x30,
+    EXPANSION = 0x31,
+    GENERIC = 0x32,
+    TUPLE = 0x33,
+    NULLPTR = 0x34,
+    THIS = 0x35,
+    TEMPLATE_REFERENCE = 0x36,
+    PUSH_STATE = 0x37,
+    TYPE_TRAIT_INTRINSIC = 0x38,
+    DESIGNATED_INITIALIZER = 0x39,
+    PACKED_TEMPLATE_ARGUMENTS = 0x3A,
+    TOKENS = 0x3B,
+    ASSIGN_INITIALIZER = 0x3C,
 }
 
+/// Partition `expr.empty`. Also used for the partitions that have only a location and a type.
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprEmpty {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+}
+
+/// Partition `expr.placeholder`
+pub type ExprPlaceholder = ExprEmpty;
+/// Partition `expr.nullptr`
+pub type ExprNullptr = ExprEmpty;
+/// Partition `expr.this`
+pub type ExprThis = ExprEmpty;
+
 /// Partition `expr.literal`
 
 #[repr(C)]
@@ -86,14 +128,771 @@ pub struct ConstF64 {
     pub unspecified: [u8; 4],
 }
 
+/// Partition `expr.lambda`. All of the fields refer to syntax trees.
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprLambda {
+    pub introducer: SyntaxIndex,
+    pub template_parameters: SyntaxIndex,
+    pub declarator: SyntaxIndex,
+    pub constraint: SyntaxIndex,
+    pub body: SyntaxIndex,
+}
+
+/// Partition `expr.type`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprType {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    /// The type designated by this expression
+    pub denotation: TypeIndex,
+}
+
+/// Partition `expr.decl`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprNamedDecl {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub resolution: DeclIndex,
+}
+
+/// Partition `expr.unresolved`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprUnresolvedId {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub name: NameIndex,
+}
+
+/// Partition `expr.template-id`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTemplateId {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub primary: ExprIndex,
+    /// A single template argument, or an `ExprSort::TUPLE` of them.
+    pub arguments: ExprIndex,
+}
+
+/// Partition `expr.unqualified-id`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprUnqualifiedId {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub name: NameIndex,
+    pub resolution: ExprIndex,
+    pub template_keyword: SourceLocation,
+}
+
+/// Partition `expr.simple-identifier`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprSimpleIdentifier {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub name: NameIndex,
+}
+
+/// Partition `expr.qualified-name`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprQualifiedName {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub elements: ExprIndex,
+    pub typename_keyword: SourceLocation,
+}
+
+/// Partition `expr.path`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprPath {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub scope: ExprIndex,
+    pub member: ExprIndex,
+}
+
+/// Partition `expr.read`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprRead {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub address: ExprIndex,
+    pub sort: ReadConversionSort,
+    pub __padding: [u8; 3],
+}
+
+#[c_enum(storage = "u8")]
+pub enum ReadConversionSort {
+    IDENTITY,
+    INDIRECTION,
+    DEREFERENCE,
+    LVALUE_TO_RVALUE,
+    INTEGRAL_CONVERSION,
+}
+
+/// Partition `expr.monad`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprMonad {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub impl_: DeclIndex,
+    pub argument: ExprIndex,
+    pub assoc: MonadicOperator,
+    pub __padding: [u8; 2],
+}
+
 /// Partition `expr.dyad`
 #[repr(C)]
 #[derive(Clone, AsBytes, FromBytes, Debug)]
 pub struct ExprDyad {
-    locus: SourceLocation,
-    ty: TypeIndex,
-    impl_: DeclIndex,
-    arguments: [ExprIndex; 2],
-    assoc: DyadicOperator,
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub impl_: DeclIndex,
+    pub arguments: [ExprIndex; 2],
+    pub assoc: DyadicOperator,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.triad`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTriad {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub impl_: DeclIndex,
+    pub arguments: [ExprIndex; 3],
+    pub assoc: TriadicOperator,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.strings`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprString {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub string_index: StringIndex,
+}
+
+tagged_index! {
+    pub struct StringIndex {
+        const TAG_BITS: usize = 4;
+        tag: StringSort,
+        index: u32,
+    }
+}
+
+#[c_enum(storage = "u32")]
+pub enum StringSort {
+    ORDINARY,
+    UTF8,
+    CHAR16,
+    CHAR32,
+    WIDE,
+}
+
+/// Partition `const.str`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct StringLiteral {
+    /// The first byte of the string, in the string table.
+    pub start: TextOffset,
+    /// The number of bytes in the string, not counting the suffix.
+    pub length: Cardinality,
+    pub suffix: TextOffset,
+}
+
+/// Partition `expr.temporary`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTemporary {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub id: u32,
+}
+
+/// Partition `expr.call`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprCall {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub operation: ExprIndex,
+    /// A single argument, or an `ExprSort::TUPLE` of them.
+    pub arguments: ExprIndex,
+}
+
+/// Partition `expr.member-initializer`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprMemberInitializer {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub member: DeclIndex,
+    pub base: TypeIndex,
+    pub initializer: ExprIndex,
+}
+
+/// Partition `expr.member-access`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprMemberAccess {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub offset: ExprIndex,
+    pub enclosing: TypeIndex,
+    pub name: TextOffset,
+}
+
+/// Partition `expr.inheritance-path`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprInheritancePath {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub path: ExprIndex,
+}
+
+/// Partition `expr.initializer-list`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprInitializerList {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub elements: ExprIndex,
+}
+
+/// Partition `expr.cast`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprCast {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub source: ExprIndex,
+    pub target: TypeIndex,
+    pub operator: DyadicOperator,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.condition`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprCondition {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub expr: ExprIndex,
+}
+
+/// Partition `expr.expression-list`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprExpressionList {
+    pub left: SourceLocation,
+    pub right: SourceLocation,
+    pub contents: ExprIndex,
+    pub delimiter: DelimiterSort,
+    pub __padding: [u8; 3],
+}
+
+#[c_enum(storage = "u8")]
+pub enum DelimiterSort {
+    UNKNOWN = 0,
+    BRACE = 1,
+    PARENTHESIS = 2,
+}
+
+/// Partition `expr.sizeof-type`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprSizeofType {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub operand: TypeIndex,
+}
+
+/// Partition `expr.alignof`
+pub type ExprAlignof = ExprSizeofType;
+/// Partition `expr.typeid`
+pub type ExprTypeid = ExprSizeofType;
+
+/// Partition `expr.destructor-call`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprDestructorCall {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub object: ExprIndex,
+    pub decltype_specifier: SyntaxIndex,
+    /// 1 for a C++ destructor, 2 for a CLI finalizer.
+    pub cleanup: u8,
+    pub __padding: [u8; 3],
+}
+
+/// Partition `expr.function-string`, e.g. `__FUNCTION__`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprFunctionString {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub macro_: TextOffset,
+}
+
+/// Partition `expr.compound-string`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprCompoundString {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub prefix: TextOffset,
+    pub string: ExprIndex,
+}
+
+/// Partition `expr.string-sequence`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprStringSequence {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub strings: ExprIndex,
+}
+
+/// Partition `expr.initializer`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprInitializer {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub expr: ExprIndex,
+    pub sort: InitializerSort,
+    pub __padding: [u8; 3],
+}
+
+#[c_enum(storage = "u8")]
+pub enum InitializerSort {
+    UNKNOWN = 0,
+    DIRECT = 1,
+    COPY = 2,
+}
+
+/// Partition `expr.requires`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprRequires {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub parameters: SyntaxIndex,
+    pub body: SyntaxIndex,
+}
+
+/// Partition `expr.unary-fold`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprUnaryFold {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub expr: ExprIndex,
+    pub operation: DyadicOperator,
+    pub associativity: u8,
+    pub __padding: [u8; 1],
 }
 
+/// Partition `expr.binary-fold`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprBinaryFold {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub left: ExprIndex,
+    pub right: ExprIndex,
+    pub operation: DyadicOperator,
+    pub associativity: u8,
+    pub __padding: [u8; 1],
+}
+
+/// Partition `expr.hierarchy-conversion`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprHierarchyConversion {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub source: ExprIndex,
+    pub target: TypeIndex,
+    pub inheritance: ExprIndex,
+    pub override_: ExprIndex,
+    pub operator: DyadicOperator,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.product-type-value`, a class type object, e.g. from constant evaluation
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprProductTypeValue {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub structure: TypeIndex,
+    pub members: ExprIndex,
+    pub base_subobjects: ExprIndex,
+}
+
+/// Partition `expr.sum-type-value`, a union object, e.g. from constant evaluation
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprSumTypeValue {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub variant: TypeIndex,
+    /// The index of the active member, starting from 0.
+    pub discriminant: u32,
+    pub value: ExprIndex,
+}
+
+/// Partition `expr.array-value`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprArrayValue {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub elements: ExprIndex,
+    pub element_type: TypeIndex,
+}
+
+/// Partition `expr.dynamic-dispatch`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprDynamicDispatch {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub pivot: ExprIndex,
+}
+
+/// Partition `expr.virtual-function-conversion`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprVirtualFunctionConversion {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub function: DeclIndex,
+}
+
+/// Partition `expr.expansion`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprExpansion {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub operand: ExprIndex,
+}
+
+/// Partition `expr.tuple`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTuple {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    /// Index into `heap.expr`
+    pub start: Index,
+    pub cardinality: Cardinality,
+}
+
+/// Partition `expr.template-reference`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTemplateReference {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub member_name: NameIndex,
+    pub member_locus: SourceLocation,
+    pub scope: TypeIndex,
+    pub arguments: ExprIndex,
+}
+
+/// Partition `expr.push-state`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprPushState {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub ctor_call: ExprIndex,
+    pub dtor_call: ExprIndex,
+    pub flags: u16,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.type-trait`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTypeTraitIntrinsic {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub arguments: TypeIndex,
+    pub intrinsic: Operator,
+    pub __padding: [u8; 2],
+}
+
+/// Partition `expr.designated-init`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprDesignatedInitializer {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub member: TextOffset,
+    pub initializer: ExprIndex,
+}
+
+/// Partition `expr.packed-template-arguments`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprPackedTemplateArguments {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub arguments: ExprIndex,
+}
+
+/// Partition `expr.tokens`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprTokens {
+    pub locus: SourceLocation,
+    pub ty: TypeIndex,
+    pub words: SentenceIndex,
+}
+
+/// Partition `expr.assign-initializer`
+#[repr(C)]
+#[derive(Clone, AsBytes, FromBytes, Debug)]
+pub struct ExprAssignInitializer {
+    pub equal: SourceLocation,
+    pub initializer: ExprIndex,
+}
+
+/// A decoded expression, as returned by `Ifc::expr`. Each variant refers to the record for
+/// one `ExprSort`.
+#[derive(Clone, Debug)]
+pub enum Expr<'a> {
+    Empty(&'a ExprEmpty),
+    Literal(&'a ExprLiteral),
+    Lambda(&'a ExprLambda),
+    Type(&'a ExprType),
+    NamedDecl(&'a ExprNamedDecl),
+    UnresolvedId(&'a ExprUnresolvedId),
+    TemplateId(&'a ExprTemplateId),
+    UnqualifiedId(&'a ExprUnqualifiedId),
+    SimpleIdentifier(&'a ExprSimpleIdentifier),
+    Pointer(&'a SourceLocation),
+    QualifiedName(&'a ExprQualifiedName),
+    Path(&'a ExprPath),
+    Read(&'a ExprRead),
+    Monad(&'a ExprMonad),
+    Dyad(&'a ExprDyad),
+    Triad(&'a ExprTriad),
+    String(&'a ExprString),
+    Temporary(&'a ExprTemporary),
+    Call(&'a ExprCall),
+    MemberInitializer(&'a ExprMemberInitializer),
+    MemberAccess(&'a ExprMemberAccess),
+    InheritancePath(&'a ExprInheritancePath),
+    InitializerList(&'a ExprInitializerList),
+    Cast(&'a ExprCast),
+    Condition(&'a ExprCondition),
+    ExpressionList(&'a ExprExpressionList),
+    SizeofType(&'a ExprSizeofType),
+    Alignof(&'a ExprAlignof),
+    Typeid(&'a ExprTypeid),
+    DestructorCall(&'a ExprDestructorCall),
+    SyntaxTree(SyntaxIndex),
+    FunctionString(&'a ExprFunctionString),
+    CompoundString(&'a ExprCompoundString),
+    StringSequence(&'a ExprStringSequence),
+    Initializer(&'a ExprInitializer),
+    Requires(&'a ExprRequires),
+    UnaryFold(&'a ExprUnaryFold),
+    BinaryFold(&'a ExprBinaryFold),
+    HierarchyConversion(&'a ExprHierarchyConversion),
+    ProductTypeValue(&'a ExprProductTypeValue),
+    SumTypeValue(&'a ExprSumTypeValue),
+    ArrayValue(&'a ExprArrayValue),
+    DynamicDispatch(&'a ExprDynamicDispatch),
+    VirtualFunctionConversion(&'a ExprVirtualFunctionConversion),
+    Placeholder(&'a ExprPlaceholder),
+    Expansion(&'a ExprExpansion),
+    Tuple(&'a ExprTuple),
+    Nullptr(&'a ExprNullptr),
+    This(&'a ExprThis),
+    TemplateReference(&'a ExprTemplateReference),
+    PushState(&'a ExprPushState),
+    TypeTraitIntrinsic(&'a ExprTypeTraitIntrinsic),
+    DesignatedInitializer(&'a ExprDesignatedInitializer),
+    PackedTemplateArguments(&'a ExprPackedTemplateArguments),
+    Tokens(&'a ExprTokens),
+    AssignInitializer(&'a ExprAssignInitializer),
+    /// A sort that has no structure in the spec: `VENDOR_EXTENSION` (which includes the null
+    /// `ExprIndex`), `NEW`, `DELETE`, `SUBOBJECT_VALUE` and `GENERIC`.
+    Other(ExprIndex),
+}
+
+impl<'data> Ifc<'data> {
+    /// Looks up the record for an expression.
+    pub fn expr(&self, expr: ExprIndex) -> IfcResult<Expr<'_>> {
+        let i = expr.index();
+        Ok(match expr.tag() {
+            ExprSort::EMPTY => Expr::Empty(self.expr_empty().entry(i)?),
+            ExprSort::LITERAL => Expr::Literal(self.expr_literal().entry(i)?),
+            ExprSort::LAMBDA => Expr::Lambda(self.expr_lambda().entry(i)?),
+            ExprSort::TYPE => Expr::Type(self.expr_type().entry(i)?),
+            ExprSort::NAMED_DECL => Expr::NamedDecl(self.expr_named_decl().entry(i)?),
+            ExprSort::UNRESOLVED_ID => Expr::UnresolvedId(self.expr_unresolved_id().entry(i)?),
+            ExprSort::TEMPLATE_ID => Expr::TemplateId(self.expr_template_id().entry(i)?),
+            ExprSort::UNQUALIFIED_ID => {
+                Expr::UnqualifiedId(self.expr_unqualified_id().entry(i)?)
+            }
+            ExprSort::SIMPLE_IDENTIFIER => {
+                Expr::SimpleIdentifier(self.expr_simple_identifier().entry(i)?)
+            }
+            ExprSort::POINTER => Expr::Pointer(self.expr_pointer().entry(i)?),
+            ExprSort::QUALIFIED_NAME => {
+                Expr::QualifiedName(self.expr_qualified_name().entry(i)?)
+            }
+            ExprSort::PATH => Expr::Path(self.expr_path().entry(i)?),
+            ExprSort::READ => Expr::Read(self.expr_read().entry(i)?),
+            ExprSort::MONAD => Expr::Monad(self.expr_monad().entry(i)?),
+            ExprSort::DYAD => Expr::Dyad(self.expr_dyad().entry(i)?),
+            ExprSort::TRIAD => Expr::Triad(self.expr_triad().entry(i)?),
+            ExprSort::STRING => Expr::String(self.expr_string().entry(i)?),
+            ExprSort::TEMPORARY => Expr::Temporary(self.expr_temporary().entry(i)?),
+            ExprSort::CALL => Expr::Call(self.expr_call().entry(i)?),
+            ExprSort::MEMBER_INITIALIZER => {
+                Expr::MemberInitializer(self.expr_member_initializer().entry(i)?)
+            }
+            ExprSort::MEMBER_ACCESS => Expr::MemberAccess(self.expr_member_access().entry(i)?),
+            ExprSort::INHERITANCE_PATH => {
+                Expr::InheritancePath(self.expr_inheritance_path().entry(i)?)
+            }
+            ExprSort::INITIALIZER_LIST => {
+                Expr::InitializerList(self.expr_initializer_list().entry(i)?)
+            }
+            ExprSort::CAST => Expr::Cast(self.expr_cast().entry(i)?),
+            ExprSort::CONDITION => Expr::Condition(self.expr_condition().entry(i)?),
+            ExprSort::EXPRESSION_LIST => {
+                Expr::ExpressionList(self.expr_expression_list().entry(i)?)
+            }
+            ExprSort::SIZEOF_TYPE => Expr::SizeofType(self.expr_sizeof_type().entry(i)?),
+            ExprSort::ALIGNOF => Expr::Alignof(self.expr_alignof().entry(i)?),
+            ExprSort::TYPEID => Expr::Typeid(self.expr_typeid().entry(i)?),
+            ExprSort::DESTRUCTOR_CALL => {
+                Expr::DestructorCall(self.expr_destructor_call().entry(i)?)
+            }
+            ExprSort::SYNTAX_TREE => Expr::SyntaxTree(*self.expr_syntax_tree().entry(i)?),
+            ExprSort::FUNCTION_STRING => {
+                Expr::FunctionString(self.expr_function_string().entry(i)?)
+            }
+            ExprSort::COMPOUND_STRING => {
+                Expr::CompoundString(self.expr_compound_string().entry(i)?)
+            }
+            ExprSort::STRING_SEQUENCE => {
+                Expr::StringSequence(self.expr_string_sequence().entry(i)?)
+            }
+            ExprSort::INITIALIZER => Expr::Initializer(self.expr_initializer().entry(i)?),
+            ExprSort::REQUIRES => Expr::Requires(self.expr_requires().entry(i)?),
+            ExprSort::UNARY_FOLD => Expr::UnaryFold(self.expr_unary_fold().entry(i)?),
+            ExprSort::BINARY_FOLD => Expr::BinaryFold(self.expr_binary_fold().entry(i)?),
+            ExprSort::HIERARCHY_CONVERSION => {
+                Expr::HierarchyConversion(self.expr_hierarchy_conversion().entry(i)?)
+            }
+            ExprSort::PRODUCT_TYPE_VALUE => {
+                Expr::ProductTypeValue(self.expr_product_type_value().entry(i)?)
+            }
+            ExprSort::SUM_TYPE_VALUE => {
+                Expr::SumTypeValue(self.expr_sum_type_value().entry(i)?)
+            }
+            ExprSort::ARRAY_VALUE => Expr::ArrayValue(self.expr_array_value().entry(i)?),
+            ExprSort::DYNAMIC_DISPATCH => {
+                Expr::DynamicDispatch(self.expr_dynamic_dispatch().entry(i)?)
+            }
+            ExprSort::VIRTUAL_FUNCTION_CONVERSION => Expr::VirtualFunctionConversion(
+                self.expr_virtual_function_conversion().entry(i)?,
+            ),
+            ExprSort::PLACEHOLDER => Expr::Placeholder(self.expr_placeholder().entry(i)?),
+            ExprSort::EXPANSION => Expr::Expansion(self.expr_expansion().entry(i)?),
+            ExprSort::TUPLE => Expr::Tuple(self.expr_tuple().entry(i)?),
+            ExprSort::NULLPTR => Expr::Nullptr(self.expr_nullptr().entry(i)?),
+            ExprSort::THIS => Expr::This(self.expr_this().entry(i)?),
+            ExprSort::TEMPLATE_REFERENCE => {
+                Expr::TemplateReference(self.expr_template_reference().entry(i)?)
+            }
+            ExprSort::PUSH_STATE => Expr::PushState(self.expr_push_state().entry(i)?),
+            ExprSort::TYPE_TRAIT_INTRINSIC => {
+                Expr::TypeTraitIntrinsic(self.expr_type_trait().entry(i)?)
+            }
+            ExprSort::DESIGNATED_INITIALIZER => {
+                Expr::DesignatedInitializer(self.expr_designated_init().entry(i)?)
+            }
+            ExprSort::PACKED_TEMPLATE_ARGUMENTS => {
+                Expr::PackedTemplateArguments(self.expr_packed_template_arguments().entry(i)?)
+            }
+            ExprSort::TOKENS => Expr::Tokens(self.expr_tokens().entry(i)?),
+            ExprSort::ASSIGN_INITIALIZER => {
+                Expr::AssignInitializer(self.expr_assign_initializer().entry(i)?)
+            }
+            _ => Expr::Other(expr),
+        })
+    }
+
+    /// Iterates a single expression, or the elements of an `ExprSort::TUPLE`. Template
+    /// arguments and call arguments use this encoding. A null `ExprIndex` yields nothing.
+    pub fn iter_expr_tuple(&self, expr: ExprIndex) -> IfcResult<IterExprTuple<'_>> {
+        if expr.0 == 0 {
+            return Ok(IterExprTuple {
+                single: None,
+                tuple: &[],
+            });
+        }
+
+        if expr.tag() == ExprSort::TUPLE {
+            let tuple = self.expr_tuple().entry(expr.index())?;
+            let heap = self.heap_expr();
+            let range = tuple.start as usize..tuple.start as usize + tuple.cardinality as usize;
+            match heap.entries.get(range) {
+                Some(slice) => Ok(IterExprTuple {
+                    single: None,
+                    tuple: slice,
+                }),
+                None => Err(IfcError::BadEntryIndex {
+                    partition: heap.part_name,
+                    index: tuple.start.saturating_add(tuple.cardinality),
+                    len: heap.entries.len(),
+                }),
+            }
+        } else {
+            Ok(IterExprTuple {
+                single: Some(expr),
+                tuple: &[],
+            })
+        }
+    }
+}
+
+pub struct IterExprTuple<'a> {
+    single: Option<ExprIndex>,
+    tuple: &'a [ExprIndex],
+}
+
+impl<'a> Iterator for IterExprTuple<'a> {
+    type Item = ExprIndex;
+    fn next(&mut self) -> Option<ExprIndex> {
+        if self.single.is_some() {
+            return self.single.take();
+        }
+        let (&first, rest) = self.tuple.split_first()?;
+        self.tuple = rest;
+        Some(first)
+    }
+}
@@ -0,0 +1,420 @@
+//! Size, alignment and field offsets of types
+//!
+//! These follow the MSVC rules, since MSVC is the only producer of IFC files. Pointers are
+//! sized by `FileHeader::arch`. Some things are not modeled: virtual base classes are an error,
+//! empty base classes always take no space, and pointers to members use the size of the
+//! single-inheritance representation.
+
+use super::*;
+
+/// The size and alignment of a type, in bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TypeLayout {
+    pub size: u64,
+    pub align: u64,
+}
+
+/// The layout of a class, struct or union, as returned by `Ifc::class_layout`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassLayout {
+    pub size: u64,
+    pub align: u64,
+    /// The size without tail padding. This is zero for an empty class, whose `size` is 1.
+    pub data_size: u64,
+    /// Whether the class starts with its own pointer to a virtual function table, rather than
+    /// sharing the one in its primary base class.
+    pub vfptr: bool,
+    /// Whether the class has virtual functions, either its own or inherited.
+    pub polymorphic: bool,
+    /// The non-virtual base classes, in the order that they are laid out. MSVC puts the first
+    /// polymorphic base class first.
+    pub bases: Vec<BaseLayout>,
+    /// The fields and bitfields, in declaration order. Static data members are not included.
+    pub fields: Vec<FieldLayout>,
+}
+
+/// The position of a base class within a derived class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BaseLayout {
+    pub ty: TypeIndex,
+    pub offset: u64,
+}
+
+/// The position of a field or bitfield within a class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldLayout {
+    /// A `DeclSort::FIELD` or `DeclSort::BITFIELD`.
+    pub decl: DeclIndex,
+    /// The offset in bytes. For a bitfield, this is the offset of its storage unit.
+    pub offset: u64,
+    /// The size of the field's type. For a bitfield, this is the size of its storage unit.
+    pub size: u64,
+    /// The bits of the storage unit that hold a bitfield, or `None` for other fields.
+    pub bits: Option<BitRange>,
+}
+
+/// The bits of a storage unit that hold a bitfield. MSVC allocates bitfields starting at the
+/// least significant bit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BitRange {
+    pub offset: u32,
+    pub width: u32,
+}
+
+/// A storage unit that can hold more bitfields of the same size.
+struct BitfieldUnit {
+    offset: u64,
+    size: u64,
+    used: u32,
+}
+
+fn align_to(offset: u64, align: u64) -> Result<u64> {
+    match offset.checked_add(align - 1) {
+        Some(end) => Ok(end / align * align),
+        None => bail!("offset {} overflows when aligned to {}", offset, align),
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// The size of a pointer on the architecture that the file was compiled for.
+    pub fn pointer_size(&self) -> Result<u64> {
+        Ok(match self.file_header().arch {
+            Architecture::X86 | Architecture::ARM32 => 4,
+            Architecture::X64 | Architecture::ARM64 | Architecture::HybridX86ARM64 => 8,
+            arch => bail!("the size of pointers on {:?} is not known", arch),
+        })
+    }
+
+    /// Returns the size of a complete type, in bytes, as `sizeof` would.
+    pub fn size_of(&self, ty: TypeIndex) -> Result<u64> {
+        Ok(self.type_layout(ty)?.size)
+    }
+
+    /// Returns the alignment of a complete type, in bytes, as `alignof` would.
+    pub fn align_of(&self, ty: TypeIndex) -> Result<u64> {
+        Ok(self.type_layout(ty)?.align)
+    }
+
+    /// Returns the size and alignment of a complete type. This fails for incomplete types,
+    /// such as `void` or a class that is only declared, and for dependent types.
+    pub fn type_layout(&self, ty: TypeIndex) -> Result<TypeLayout> {
+        self.type_layout_at_depth(ty, 0)
+    }
+
+    /// Returns the layout of a class, struct or union, which must be a `DeclSort::SCOPE`.
+    pub fn class_layout(&self, decl: DeclIndex) -> Result<ClassLayout> {
+        self.class_layout_at_depth(decl, 0)
+    }
+
+    /// Array extents can contain `sizeof` expressions, and classes contain other classes, so
+    /// this shares its depth with `eval_const` to stop cycles through either.
+    pub(crate) fn type_layout_at_depth(&self, ty: TypeIndex, depth: u32) -> Result<TypeLayout> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("type {:?} is nested too deeply (is it cyclic?)", ty);
+        }
+
+        let i = ty.index();
+        let pointer = || -> Result<TypeLayout> {
+            let size = self.pointer_size()?;
+            Ok(TypeLayout { size, align: size })
+        };
+        Ok(match ty.tag() {
+            TypeSort::FUNDAMENTAL => {
+                let f = self.type_fundamental().entry(i)?;
+                let size = match f.basis {
+                    TypeBasis::BOOL => 1,
+                    TypeBasis::CHAR => match f.precision {
+                        TypePrecision::BIT16 => 2,
+                        TypePrecision::BIT32 => 4,
+                        _ => 1,
+                    },
+                    TypeBasis::WCHAR_T => 2,
+                    TypeBasis::INT => match f.precision {
+                        TypePrecision::BIT8 => 1,
+                        TypePrecision::BIT16 | TypePrecision::SHORT => 2,
+                        // `long` is 32 bits on every Windows architecture.
+                        TypePrecision::DEFAULT | TypePrecision::LONG | TypePrecision::BIT32 => 4,
+                        TypePrecision::BIT64 => 8,
+                        TypePrecision::BIT128 => 16,
+                        _ => bail!("the size of {:?} is not known", ty),
+                    },
+                    TypeBasis::FLOAT => 4,
+                    // `long double` is the same as `double` in MSVC.
+                    TypeBasis::DOUBLE => 8,
+                    TypeBasis::NULLPTR => return pointer(),
+                    _ => bail!("{:?} is not a complete object type", f.basis),
+                };
+                TypeLayout { size, align: size }
+            }
+            TypeSort::POINTER | TypeSort::LVALUE_REFERENCE | TypeSort::RVALUE_REFERENCE => {
+                pointer()?
+            }
+            TypeSort::POINTER_TO_MEMBER => {
+                let ptm = self.type_pointer_to_member().entry(i)?;
+                if ptm.member.tag() == TypeSort::METHOD {
+                    pointer()?
+                } else {
+                    // A pointer to a data member is an offset.
+                    TypeLayout { size: 4, align: 4 }
+                }
+            }
+            TypeSort::ARRAY => {
+                let array = self.type_array().entry(i)?;
+                let element = self.type_layout_at_depth(array.element, depth + 1)?;
+                if array.extent.0 == 0 {
+                    bail!("array {:?} does not have a bound", ty);
+                }
+                let extent = self.eval_const_at_depth(array.extent, depth + 1)?;
+                let size = match extent.to_u64().and_then(|n| n.checked_mul(element.size)) {
+                    Some(size) => size,
+                    None => bail!("the size of array {:?} is too large", ty),
+                };
+                TypeLayout {
+                    size,
+                    align: element.align,
+                }
+            }
+            TypeSort::QUALIFIED => {
+                let qualified = self.type_qualified().entry(i)?;
+                self.type_layout_at_depth(qualified.unqualified_type, depth + 1)?
+            }
+            TypeSort::UNALIGNED => {
+                let target = *self.type_unaligned().entry(i)?;
+                let layout = self.type_layout_at_depth(target, depth + 1)?;
+                TypeLayout {
+                    size: layout.size,
+                    align: 1,
+                }
+            }
+            TypeSort::PLACEHOLDER => {
+                let placeholder = self.type_placeholder().entry(i)?;
+                if placeholder.elaboration.is_null() {
+                    bail!("the size of a placeholder type is not known");
+                }
+                self.type_layout_at_depth(placeholder.elaboration, depth + 1)?
+            }
+            TypeSort::DESIGNATED => {
+                let decl = *self.type_designated().entry(i)?;
+                match decl.tag() {
+                    DeclSort::SCOPE => {
+                        let class = self.class_layout_at_depth(decl, depth + 1)?;
+                        TypeLayout {
+                            size: class.size,
+                            align: class.align,
+                        }
+                    }
+                    DeclSort::ENUMERATION => {
+                        let en = self.decl_enum().entry(decl.index())?;
+                        // Unscoped enums without a fixed type are `int`.
+                        if en.base.is_null() {
+                            TypeLayout { size: 4, align: 4 }
+                        } else {
+                            self.type_layout_at_depth(en.base, depth + 1)?
+                        }
+                    }
+                    DeclSort::ALIAS => {
+                        let alias = self.decl_alias().entry(decl.index())?;
+                        self.type_layout_at_depth(alias.aliasee, depth + 1)?
+                    }
+                    _ => bail!("the size of {:?} is not known", decl),
+                }
+            }
+            _ => bail!("the size of {:?} is not known", ty),
+        })
+    }
+
+    pub(crate) fn class_layout_at_depth(&self, decl: DeclIndex, depth: u32) -> Result<ClassLayout> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("class {:?} is nested too deeply (is it cyclic?)", decl);
+        }
+        if decl.tag() != DeclSort::SCOPE {
+            bail!("{:?} is not a class", decl);
+        }
+        let scope = self.decl_scope().entry(decl.index())?;
+        if self.is_type_namespace(scope.ty)? {
+            bail!("{:?} is a namespace, not a class", decl);
+        }
+        if scope.initializer == 0 {
+            bail!("class {:?} is incomplete", decl);
+        }
+        let is_union = matches!(
+            self.as_fundamental_type(scope.ty),
+            Some(f) if f.basis == TypeBasis::UNION
+        );
+
+        // `#pragma pack` limits the alignment of members, but not `alignas`.
+        let pack = |align: u64| match scope.pack_size {
+            0 => align,
+            pack => align.min(pack as u64),
+        };
+        let alignas = |expr: ExprIndex| -> Result<u64> {
+            if expr.0 == 0 {
+                return Ok(1);
+            }
+            match self.eval_const_at_depth(expr, depth + 1)?.to_u64() {
+                Some(align) if align.is_power_of_two() => Ok(align),
+                _ => bail!("the alignment of {:?} is not a power of two", decl),
+            }
+        };
+
+        let end = |offset: u64, size: u64| match offset.checked_add(size) {
+            Some(end) => Ok(end),
+            None => bail!("class {:?} is too large", decl),
+        };
+
+        let mut layout = ClassLayout {
+            size: 0,
+            align: 1,
+            data_size: 0,
+            vfptr: false,
+            polymorphic: false,
+            bases: Vec::new(),
+            fields: Vec::new(),
+        };
+        let mut offset = 0;
+
+        // Lay out the bases, with the first polymorphic one first.
+        let mut bases = Vec::new();
+        for base in self.iter_type_tuple(scope.base)? {
+            if base.tag() != TypeSort::BASE {
+                bail!("base {:?} of {:?} is not a TypeSort::BASE", base, decl);
+            }
+            let base = self.type_base().entry(base.index())?;
+            if base.specifiers == BaseTypeSpecifiers::SHARED {
+                bail!("the layout of virtual base classes is not supported");
+            }
+            let base_decl = match base.ty.tag() {
+                TypeSort::DESIGNATED => *self.type_designated().entry(base.ty.index())?,
+                _ => bail!("base {:?} of {:?} is not a class", base.ty, decl),
+            };
+            bases.push((base.ty, self.class_layout_at_depth(base_decl, depth + 1)?));
+        }
+        if let Some(primary) = bases.iter().position(|(_, base)| base.polymorphic) {
+            let base = bases.remove(primary);
+            bases.insert(0, base);
+            layout.polymorphic = true;
+        } else if self.has_own_virtual_functions(scope.initializer)? {
+            let size = self.pointer_size()?;
+            layout.vfptr = true;
+            layout.polymorphic = true;
+            layout.align = pack(size);
+            offset = size;
+        }
+        for (ty, base) in bases {
+            let align = pack(base.align);
+            layout.align = layout.align.max(align);
+            // Empty bases share the address of whatever follows them.
+            if base.data_size == 0 {
+                layout.bases.push(BaseLayout {
+                    ty,
+                    offset: align_to(offset, align)?,
+                });
+                continue;
+            }
+            offset = align_to(offset, align)?;
+            layout.bases.push(BaseLayout { ty, offset });
+            offset = end(offset, base.size)?;
+        }
+
+        let mut unit: Option<BitfieldUnit> = None;
+        for member in self.iter_scope(scope.initializer)? {
+            let i = member.index();
+            match member.tag() {
+                DeclSort::FIELD => {
+                    let field = self.decl_field().entry(i)?;
+                    let ty = self.type_layout_at_depth(field.ty, depth + 1)?;
+                    let align = pack(ty.align).max(alignas(field.alignment)?);
+                    unit = None;
+                    let field_offset = if is_union {
+                        0
+                    } else {
+                        align_to(offset, align)?
+                    };
+                    layout.align = layout.align.max(align);
+                    layout.fields.push(FieldLayout {
+                        decl: member,
+                        offset: field_offset,
+                        size: ty.size,
+                        bits: None,
+                    });
+                    offset = offset.max(end(field_offset, ty.size)?);
+                }
+                DeclSort::BITFIELD => {
+                    let bitfield = self.decl_bitfield().entry(i)?;
+                    let ty = self.type_layout_at_depth(bitfield.ty, depth + 1)?;
+                    let bits = ty.size * 8;
+                    let width = match self
+                        .eval_const_at_depth(bitfield.width, depth + 1)?
+                        .to_u64()
+                    {
+                        Some(width) if width <= bits => width as u32,
+                        _ => bail!("bitfield {:?} is wider than its type", member),
+                    };
+
+                    // A zero-width bitfield ends the current storage unit, and takes no space.
+                    if width == 0 {
+                        unit = None;
+                        continue;
+                    }
+
+                    // MSVC only packs adjacent bitfields into a unit if their types have the
+                    // same size.
+                    let fits = matches!(
+                        &unit,
+                        Some(u) if !is_union && u.size == ty.size && u.used as u64 + width as u64 <= bits
+                    );
+                    if !fits {
+                        let align = pack(ty.align);
+                        let unit_offset = if is_union {
+                            0
+                        } else {
+                            align_to(offset, align)?
+                        };
+                        layout.align = layout.align.max(align);
+                        offset = offset.max(end(unit_offset, ty.size)?);
+                        unit = Some(BitfieldUnit {
+                            offset: unit_offset,
+                            size: ty.size,
+                            used: 0,
+                        });
+                    }
+                    if let Some(unit) = unit.as_mut() {
+                        layout.fields.push(FieldLayout {
+                            decl: member,
+                            offset: unit.offset,
+                            size: unit.size,
+                            bits: Some(BitRange {
+                                offset: unit.used,
+                                width,
+                            }),
+                        });
+                        unit.used += width;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        layout.align = layout.align.max(alignas(scope.alignment)?);
+        layout.data_size = offset;
+        // Every object has a distinct address, so even an empty class takes a byte.
+        layout.size = align_to(offset.max(1), layout.align)?;
+        Ok(layout)
+    }
+
+    /// Returns whether a class declares virtual functions. This does not look at base classes.
+    fn has_own_virtual_functions(&self, scope: ScopeIndex) -> Result<bool> {
+        let virtual_traits = FunctionTraits::VIRTUAL | FunctionTraits::PURE_VIRTUAL;
+        for member in self.iter_scope(scope)? {
+            let traits = match member.tag() {
+                DeclSort::METHOD => self.decl_method().entry(member.index())?.traits,
+                DeclSort::DESTRUCTOR => self.decl_destructor().entry(member.index())?.traits,
+                _ => continue,
+            };
+            if traits.intersects(virtual_traits) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
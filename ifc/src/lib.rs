@@ -8,22 +8,43 @@
 use anyhow::{bail, Result};
 use core::mem::size_of;
 use core::ops::Range;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use zerocopy::{AsBytes, FromBytes, LayoutVerified};
 
 #[macro_use]
 mod macros;
 
+mod attrs;
 mod chart;
+mod cxx;
 mod decl;
+mod diff;
 mod error;
+mod eval;
 mod expr;
+mod layout;
+#[cfg(feature = "serde")]
+mod model;
+mod module;
 mod names;
 mod ops;
 mod parts;
 mod pp;
+mod symbols;
+#[cfg(any(test, feature = "testing"))]
+#[doc(hidden)]
+pub mod testing;
+mod type_key;
 mod types;
+mod validate;
+mod visit;
 mod words;
+mod workspace;
+mod write;
+
+#[cfg(test)]
+mod tests;
 
 use bitflags::bitflags;
 use c_macros::c_enum;
@@ -31,19 +52,33 @@ use core::fmt::{Debug, Formatter};
 use error::*;
 use pp::*;
 
+pub use attrs::*;
 pub use chart::*;
+pub use cxx::*;
 pub use decl::*;
+pub use diff::*;
 pub use error::*;
+pub use eval::*;
 pub use expr::*;
+pub use layout::*;
+#[cfg(feature = "serde")]
+pub use model::*;
+pub use module::*;
 pub use names::*;
 pub use ops::*;
 pub use parts::*;
 pub use pp::*;
+pub use symbols::*;
+pub use type_key::*;
 pub use types::*;
+pub use validate::*;
+pub use visit::*;
 pub use words::*;
+pub use workspace::*;
+pub use write::*;
 
 #[repr(C)]
-#[derive(FromBytes, AsBytes, Clone, Default)]
+#[derive(FromBytes, AsBytes, Clone, Default, Eq, PartialEq)]
 pub struct Sha256 {
     pub bytes: [u8; 32],
 }
@@ -70,7 +105,7 @@ pub struct Sequence {
 
 impl Sequence {
     pub fn to_range(&self) -> Range<u32> {
-        self.start..self.start + self.cardinality
+        self.start..self.start.saturating_add(self.cardinality)
     }
 }
 
@@ -78,7 +113,7 @@ impl IntoIterator for Sequence {
     type Item = u32;
     type IntoIter = Range<u32>;
     fn into_iter(self) -> Self::IntoIter {
-        self.start..self.start + self.cardinality
+        self.to_range()
     }
 }
 
@@ -100,6 +135,13 @@ pub type LanguageVersion = u32;
 
 pub const IFC_FILE_SIGNATURE: [u8; 4] = [0x54, 0x51, 0x45, 0x1A];
 
+/// The checksum covers everything after the signature and the checksum field itself.
+const CHECKSUM_START: usize = IFC_FILE_SIGNATURE.len() + size_of::<Sha256>();
+
+/// The maximum nesting depth of types that `Ifc::get_type_string` will follow. Each level is a
+/// recursive call, so this must fit comfortably in a 2 MB thread stack in debug builds.
+const MAX_TYPE_DEPTH: u32 = 128;
+
 // This is never defined in the spec.
 pub type Bool = u8;
 
@@ -160,7 +202,6 @@ pub struct ScopeDescriptor {
 
 pub type StmtIndex = u32; // heap.stmt
 pub type SyntaxIndex = u32; // heap.syn
-pub type ChartIndex = u32; // heap.chart
 
 // Chapter 15
 
@@ -170,21 +211,41 @@ pub type Column = u32;
 #[repr(C)]
 #[derive(AsBytes, FromBytes, Clone, Debug)]
 pub struct SourceLocation {
+    /// Index into `src.line`
     pub line: LineIndex,
     pub column: Column,
 }
 
+pub type LineNumber = u32;
+
+/// for `src.line`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct FileAndLine {
+    /// Usually a `NameSort::SOURCE_FILE` name. Compiler-generated locations use a null name.
+    pub file: NameIndex,
+    pub line: LineNumber,
+}
+
 // Chapter 17 Token Streams
 
 pub type SentenceIndex = u32;
 
-fn read_struct_at<T: AsBytes + FromBytes>(s: &[u8]) -> Result<T> {
+/// Computes the range of bytes covered by `count` records of `size` bytes each, starting at
+/// `offset`. The arithmetic saturates, so that bogus values from the file produce a range that
+/// is out of bounds, rather than overflowing.
+fn byte_range(offset: ByteOffset, count: Cardinality, size: usize) -> Range<usize> {
+    let start = offset as usize;
+    start..start.saturating_add((count as usize).saturating_mul(size))
+}
+
+fn read_struct_at<T: AsBytes + FromBytes>(s: &[u8]) -> Option<T> {
     if core::mem::size_of::<T>() > s.len() {
-        bail!("structure is larger than input slice");
+        return None;
     }
     let mut value: T = T::new_zeroed();
     value.as_bytes_mut().copy_from_slice(&s[..size_of::<T>()]);
-    Ok(value)
+    Some(value)
 }
 
 fn get_slice(s: &[u8], range: Range<usize>) -> Result<&[u8]> {
@@ -200,56 +261,116 @@ struct StringTable<'a> {
 }
 
 impl<'a> StringTable<'a> {
-    fn get_string(&self, text_offset: TextOffset) -> Result<&'a str> {
+    fn get_string(&self, text_offset: TextOffset) -> IfcResult<&'a str> {
         let offset = text_offset as usize;
+        let bad_string = IfcError::BadString {
+            offset: text_offset,
+        };
         if let Some(strings_at_offset) = self.strings.get(offset..) {
             for (i, &b) in strings_at_offset.iter().enumerate() {
                 if b == 0 {
                     let sb = &strings_at_offset[..i];
-                    return match core::str::from_utf8(sb) {
-                        Ok(s) => Ok(s),
-                        Err(_) => bail!("Bad UTF-8 string in ifc"),
-                    };
+                    return core::str::from_utf8(sb).map_err(|_| bad_string);
                 }
             }
             // Never found end of string!
-            bail!("string at end of string table was not NUL-terminated");
+            Err(bad_string)
         } else {
-            bail!("string offset was beyond bounds of string table");
+            Err(bad_string)
         }
     }
 }
 
-pub struct Ifc {
-    data: Vec<u8>,
+/// Options that control how `Ifc::load_with_options` reads a file.
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    /// Check that `FileHeader::checksum` matches the contents of the file. If it does not, then
+    /// loading fails with `IfcError::ChecksumMismatch`.
+    pub verify_checksum: bool,
+
+    /// Don't convert partitions until they are first accessed. Partitions that can be read in
+    /// place never need to be converted, but partitions whose records have a different size
+    /// than the reader expects (e.g. files produced by a different compiler version) do.
+    /// This makes loading faster when a tool only looks at a few partitions.
+    pub lazy: bool,
+}
+
+/// A loaded IFC file.
+///
+/// The file contents are either owned by the `Ifc` (`Ifc::load`) or borrowed from the caller
+/// (`Ifc::from_bytes`). In both cases, partitions whose records are laid out exactly as the
+/// reader expects (same record size, suitably aligned) are read in place, without copying.
+/// Partitions whose records are smaller or larger than expected are copied and converted.
+///
+/// To avoid reading a large file into memory at all, map the file and pass the mapped bytes
+/// to `Ifc::from_bytes`.
+pub struct Ifc<'data> {
+    data: Cow<'data, [u8]>,
     file_header: FileHeader,
     strings_range: Range<usize>,
 
     parts_map: HashMap<String, PartEntry>,
-    parts: Parts,
+    parts: LoadedParts,
+
+    /// The enumeration that contains each enumerator, indexed by enumerator. This is built by
+    /// `home_scope` the first time that it is needed.
+    enumerator_owners: once_cell::sync::OnceCell<Vec<Option<Index>>>,
 }
 
-impl Ifc {
-    pub fn from_file(path: &std::path::Path) -> Result<Self> {
+impl<'data> Ifc<'data> {
+    pub fn from_file(path: &std::path::Path) -> IfcResult<Self> {
         let file_data = std::fs::read(path)?;
         Self::load(file_data)
     }
 
-    pub fn load(data: Vec<u8>) -> Result<Self> {
-        let fs = data.as_slice();
+    pub fn load(data: Vec<u8>) -> IfcResult<Self> {
+        Self::load_with_options(data, &LoadOptions::default())
+    }
+
+    pub fn load_with_options(data: Vec<u8>, options: &LoadOptions) -> IfcResult<Self> {
+        Self::load_cow(Cow::Owned(data), options)
+    }
+
+    /// Loads an IFC from bytes owned by the caller, e.g. a memory-mapped file.
+    pub fn from_bytes(data: &'data [u8]) -> IfcResult<Self> {
+        Self::from_bytes_with_options(data, &LoadOptions::default())
+    }
+
+    pub fn from_bytes_with_options(data: &'data [u8], options: &LoadOptions) -> IfcResult<Self> {
+        Self::load_cow(Cow::Borrowed(data), options)
+    }
+
+    fn load_cow(data: Cow<'data, [u8]>, options: &LoadOptions) -> IfcResult<Self> {
+        let fs = &*data;
 
-        let sig = read_struct_at::<[u8; 4]>(&fs[0..])?;
+        let sig = read_struct_at::<[u8; 4]>(&fs[0..]).ok_or(IfcError::BadSignature)?;
         if sig != IFC_FILE_SIGNATURE {
-            bail!("File does not have IFC signature");
+            return Err(IfcError::BadSignature);
         }
 
-        let file_header = read_struct_at::<FileHeader>(&fs[4..])?;
+        let file_header =
+            read_struct_at::<FileHeader>(&fs[4..]).ok_or(IfcError::TruncatedHeader)?;
         // println!("File header: {:#?}", file_header);
 
-        let strings_range = file_header.string_table_bytes as usize
-            ..file_header.string_table_bytes as usize + file_header.string_table_size as usize;
+        if options.verify_checksum {
+            let actual = Self::compute_checksum(fs);
+            if actual != file_header.checksum {
+                return Err(IfcError::ChecksumMismatch {
+                    expected: file_header.checksum,
+                    actual,
+                });
+            }
+        }
+
+        let strings_range = byte_range(
+            file_header.string_table_bytes,
+            file_header.string_table_size,
+            1,
+        );
         if fs.get(strings_range.clone()).is_none() {
-            bail!("IFC string table range is not valid");
+            return Err(IfcError::StringTableOutOfBounds {
+                range: strings_range,
+            });
         }
 
         let mut ifc = Ifc {
@@ -257,7 +378,8 @@ impl Ifc {
             file_header,
             parts_map: HashMap::new(),
             strings_range,
-            parts: Parts::default(),
+            parts: LoadedParts::default(),
+            enumerator_owners: Default::default(),
         };
 
         let strings = StringTable {
@@ -265,10 +387,24 @@ impl Ifc {
         };
 
         let num_partitions = ifc.file_header.partition_count;
-        for i in 0..num_partitions as usize {
-            let partition_summary = read_struct_at::<PartitionSummary>(
-                &ifc.data[ifc.file_header.toc as usize + i * size_of::<PartitionSummary>()..],
-            )?;
+        let toc_range = byte_range(
+            ifc.file_header.toc,
+            num_partitions,
+            size_of::<PartitionSummary>(),
+        );
+        let toc = match ifc.data.get(toc_range.clone()) {
+            Some(toc) => toc,
+            None => {
+                return Err(IfcError::PartitionOutOfBounds {
+                    name: "(table of contents)".to_string(),
+                    range: toc_range,
+                })
+            }
+        };
+
+        for (i, summary_bytes) in toc.chunks_exact(size_of::<PartitionSummary>()).enumerate() {
+            // This cannot fail, because the chunk has exactly the right size.
+            let partition_summary = read_struct_at::<PartitionSummary>(summary_bytes).unwrap();
 
             let partition_name = strings.get_string(partition_summary.name)?;
             if false {
@@ -278,26 +414,33 @@ impl Ifc {
                 );
             }
 
-            let part_range = partition_summary.offset as usize
-                ..partition_summary.offset as usize
-                    + partition_summary.cardinality as usize
-                        * partition_summary.entity_size as usize;
-            let part_data = if let Some(part_data) = ifc.data.get(part_range.clone()) {
-                part_data
-            } else {
-                bail!(
-                    "partition {} {:?} is invalid; its range is outside the ifc file size",
-                    i,
-                    partition_name
-                );
-            };
+            if partition_summary.entity_size == 0 && partition_summary.cardinality != 0 {
+                return Err(IfcError::BadEntitySize {
+                    name: partition_name.to_string(),
+                });
+            }
 
-            ifc.parts.load_part_data(
-                partition_name,
-                part_data,
-                partition_summary.cardinality as usize,
+            let part_range = byte_range(
+                partition_summary.offset,
+                partition_summary.cardinality,
                 partition_summary.entity_size as usize,
-            )?;
+            );
+            if ifc.data.get(part_range.clone()).is_none() {
+                return Err(IfcError::PartitionOutOfBounds {
+                    name: partition_name.to_string(),
+                    range: part_range,
+                });
+            }
+
+            let location = PartLocation {
+                range: part_range.clone(),
+                num_records: partition_summary.cardinality as usize,
+                record_size: partition_summary.entity_size as usize,
+            };
+            let borrowed = ifc
+                .parts
+                .add_part(partition_name, &ifc.data, location, options.lazy)
+                .unwrap_or(false);
 
             ifc.parts_map.insert(
                 partition_name.to_string(),
@@ -305,6 +448,7 @@ impl Ifc {
                     part_range,
                     count: partition_summary.cardinality as usize,
                     size: partition_summary.entity_size as usize,
+                    borrowed,
                 },
             );
         }
@@ -312,13 +456,37 @@ impl Ifc {
         Ok(ifc)
     }
 
+    /// Computes the SHA-256 checksum of the contents of an IFC file, i.e. of everything that
+    /// follows the `checksum` field of the file header. Tools that produce or patch IFC files
+    /// can use this to stamp `FileHeader::checksum`.
+    pub fn compute_checksum(data: &[u8]) -> Sha256 {
+        use sha2::Digest;
+        let contents = data.get(CHECKSUM_START..).unwrap_or(&[]);
+        Sha256 {
+            bytes: sha2::Sha256::digest(contents).into(),
+        }
+    }
+
+    /// Checks that the checksum in the file header matches the contents of the file.
+    pub fn verify_checksum(&self) -> IfcResult<()> {
+        let actual = Self::compute_checksum(&self.data);
+        if actual != self.file_header.checksum {
+            return Err(IfcError::ChecksumMismatch {
+                expected: self.file_header.checksum.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the global scope of the file. This is zero if the file does not have a global
+    /// scope, which `iter_scope` reports as an error.
     pub fn global_scope(&self) -> ScopeIndex {
-        assert!(self.file_header.global_scope > 0);
         self.file_header.global_scope
     }
 
-    pub fn iter_scope(&self, scope: ScopeIndex) -> Result<IterScope<'_>> {
-        let scope_desc = self.scope_desc().entry(scope - 1)?;
+    pub fn iter_scope(&self, scope: ScopeIndex) -> IfcResult<IterScope<'_>> {
+        let scope_desc = self.get_scope_descriptor(scope)?;
 
         if let Some(slice) = self.scope_member().entries.get(
             scope_desc.start as usize..scope_desc.start as usize + scope_desc.cardinality as usize,
@@ -328,7 +496,7 @@ impl Ifc {
                 ifc: self,
             })
         } else {
-            bail!("invalid scope member range for scope {}", scope);
+            Err(IfcError::BadScope { scope })
         }
     }
 
@@ -340,24 +508,30 @@ impl Ifc {
         &self.parts_map
     }
 
-    pub fn get_string(&self, text_offset: TextOffset) -> Result<&str> {
+    pub fn get_string(&self, text_offset: TextOffset) -> IfcResult<&str> {
         StringTable {
             strings: &self.data[self.strings_range.clone()],
         }
         .get_string(text_offset)
     }
 
-    pub fn get_name_string(&self, name: NameIndex) -> Result<&str> {
-        Ok(match name.tag() {
-            NameSort::LITERAL => self.get_string(name.index())?,
-            NameSort::CONVERSION => "?CONVERSION",
-            NameSort::GUIDE => "?GUIDE",
-            NameSort::IDENTIFIER => self.get_string(name.index())?,
-            NameSort::OPERATOR => "?OPERATOR",
-            NameSort::SOURCE_FILE => "?SOURCE_FILE",
-            NameSort::TEMPLATE => "?TEMPLATE",
-            _ => "???NameSort",
-        })
+    /// Finds the path, line number and column of a source location, using `src.line` and
+    /// `name.source-file`. Returns `None` if the location does not refer to a source file, which
+    /// is common for compiler-generated declarations, or if the location is malformed.
+    pub fn resolve_locus(&self, locus: &SourceLocation) -> Option<(&str, u32, u32)> {
+        let file_and_line = self.src_line().entry(locus.line).ok()?;
+        if file_and_line.file.tag() != NameSort::SOURCE_FILE {
+            return None;
+        }
+        let file = self
+            .name_source_file()
+            .entry(file_and_line.file.index())
+            .ok()?;
+        if file.path == 0 {
+            return None;
+        }
+        let path = self.get_string(file.path).ok()?;
+        Some((path, file_and_line.line, locus.column))
     }
 
     /*
@@ -402,218 +576,27 @@ impl Ifc {
     }
     */
 
-    pub fn get_scope_descriptor(&self, scope_index: ScopeIndex) -> Result<&ScopeDescriptor> {
-        self.scope_desc().entry(scope_index - 1)
+    pub fn get_scope_descriptor(&self, scope_index: ScopeIndex) -> IfcResult<&ScopeDescriptor> {
+        // ScopeIndex values are 1-based. Zero means "no scope".
+        match scope_index.checked_sub(1) {
+            Some(i) => self.scope_desc().entry(i),
+            None => Err(IfcError::BadScope { scope: scope_index }),
+        }
     }
 
-    pub fn type_heap_lookup(&self, index: Index) -> Result<TypeIndex> {
+    pub fn type_heap_lookup(&self, index: Index) -> IfcResult<TypeIndex> {
         Ok(*self.heap_type().entry(index)?)
     }
 
+    /// Returns the type as it would be written in C++, e.g. `const char *` or `int (*)(int)`.
+    /// Use `CxxTypePrinter` to choose the spellings, or to include a declarator name.
     pub fn get_type_string(&self, type_index: TypeIndex) -> Result<String> {
-        use core::fmt::Write;
-        Ok(match type_index.tag() {
-            TypeSort::FUNCTION => {
-                let type_function_part = self.type_function();
-                let type_func = type_function_part.entry(type_index.index())?;
-
-                let target_type_str = self.get_type_string(type_func.target)?;
-                let mut s = String::with_capacity(80);
-                s.push_str(&target_type_str);
-
-                match type_func.convention {
-                    CallingConvention::Cdecl => s.push_str(" __cdecl "),
-                    CallingConvention::Std => s.push_str(" __stdcall "),
-                    CallingConvention::This => s.push_str(" __thiscall "),
-                    CallingConvention::Vector => s.push_str(" __vectorcall "),
-                    CallingConvention::Fast => s.push_str(" __fastcall "),
-                    _ => {
-                        write!(s, "{:?}", type_func.convention).unwrap();
-                    }
-                }
-
-                s.push('(');
-                if !type_func.source.is_null() {
-                    s.push_str(&self.get_type_string(type_func.source)?);
-                }
-                s.push(')');
-
-                let noexcept_str = match type_func.eh_spec.sort {
-                    NoexceptSort::NONE => "",
-                    NoexceptSort::FALSE => "noexcept(false)",
-                    NoexceptSort::TRUE => "noexcept",
-                    NoexceptSort::EXPRESSION => "noexcept(expr)",
-                    NoexceptSort::INFERRED => "noexcept(inferred)",
-                    NoexceptSort::UNENFORCED => "noexcept(unenforced)",
-                    _ => "??",
-                };
-                if !noexcept_str.is_empty() {
-                    s.push_str(" ");
-                    s.push_str(noexcept_str);
-                }
-                s
-            }
-
-            TypeSort::FUNDAMENTAL => {
-                let type_fundamental = self.type_fundamental().entry(type_index.index())?;
-                match type_fundamental.basis {
-                    TypeBasis::VOID => "void",
-                    TypeBasis::BOOL => "bool",
-                    TypeBasis::CHAR => "char",
-                    TypeBasis::WCHAR_T => "wchar_t",
-                    TypeBasis::INT => match (type_fundamental.sign, type_fundamental.precision) {
-                        (TypeSign::UNSIGNED, TypePrecision::DEFAULT) => "unsigned int",
-                        (TypeSign::SIGNED, TypePrecision::DEFAULT) => "signed int",
-                        (_, TypePrecision::DEFAULT) => "int",
-
-                        (TypeSign::UNSIGNED, TypePrecision::LONG) => "unsigned long",
-                        (TypeSign::SIGNED, TypePrecision::LONG) => "signed long",
-                        (_, TypePrecision::LONG) => "long",
-
-                        (TypeSign::UNSIGNED, TypePrecision::SHORT) => "unsigned short",
-                        (TypeSign::SIGNED, TypePrecision::SHORT) => "signed short",
-                        (_, TypePrecision::SHORT) => "short",
-
-                        (TypeSign::UNSIGNED, TypePrecision::BIT8) => "unsigned __int8",
-                        (TypeSign::SIGNED, TypePrecision::BIT8) => "signed __int8",
-                        (_, TypePrecision::BIT8) => "__int8",
-
-                        (TypeSign::UNSIGNED, TypePrecision::BIT16) => "unsigned __int16",
-                        (TypeSign::SIGNED, TypePrecision::BIT16) => "signed __int16",
-                        (_, TypePrecision::BIT16) => "__int16",
-
-                        (TypeSign::UNSIGNED, TypePrecision::BIT32) => "unsigned _int32",
-                        (TypeSign::SIGNED, TypePrecision::BIT32) => "signed __int32",
-                        (_, TypePrecision::BIT32) => "__int32",
-
-                        (TypeSign::UNSIGNED, TypePrecision::BIT64) => "unsigned __int64",
-                        (TypeSign::SIGNED, TypePrecision::BIT64) => "signed __int64",
-                        (_, TypePrecision::BIT64) => "__int64",
-
-                        (TypeSign::UNSIGNED, TypePrecision::BIT128) => "unsigned __int128",
-                        (TypeSign::SIGNED, TypePrecision::BIT128) => "signed __int128",
-                        (_, TypePrecision::BIT128) => "__int128",
-
-                        _ => "??int",
-                    },
-                    TypeBasis::FLOAT => "float",
-                    TypeBasis::DOUBLE => "double",
-                    TypeBasis::NULLPTR => "nullptr",
-                    TypeBasis::ELLIPSIS => "ellipsis",
-                    TypeBasis::SEGMENT_TYPE => "segment_type",
-                    TypeBasis::CLASS => "class",
-                    TypeBasis::STRUCT => "struct",
-                    TypeBasis::UNION => "union",
-                    TypeBasis::ENUM => "enum",
-                    TypeBasis::TYPENAME => "typename",
-                    TypeBasis::NAMESPACE => "namespace",
-                    TypeBasis::INTERFACE => "interface",
-                    TypeBasis::FUNCTION => "function",
-                    TypeBasis::EMPTY => "empty",
-                    TypeBasis::VARIABLE_TEMPLATE => "variable_template",
-                    TypeBasis::AUTO => "auto",
-                    TypeBasis::DECLTYPE_AUTO => "decltype_auto",
-                    _ => "??",
-                }
-                .to_string()
-            }
-
-            TypeSort::TUPLE => {
-                let type_tuple = self.type_tuple().entry(type_index.index())?;
-                let mut s = String::new();
-                for i in 0..type_tuple.cardinality {
-                    if i > 0 {
-                        s.push_str(", ");
-                    }
-                    let element_type_index = self.type_heap_lookup(type_tuple.start + i)?;
-                    s.push_str(&self.get_type_string(element_type_index)?);
-                }
-                s
-            }
-
-            TypeSort::QUALIFIED => {
-                let qualified_type: &QualifiedType =
-                    self.type_qualified().entry(type_index.index())?;
-                let mut s = self.get_type_string(qualified_type.unqualified_type)?;
-                if qualified_type.qualifiers.contains(Qualifiers::CONST) {
-                    s.insert_str(0, "const ");
-                }
-                if qualified_type.qualifiers.contains(Qualifiers::VOLATILE) {
-                    s.insert_str(0, "volatile ");
-                }
-                s
-            }
-
-            TypeSort::UNALIGNED => {
-                let target_ty = *self.type_unaligned().entry(type_index.index())?;
-                let s = self.get_type_string(target_ty)?;
-                format!("__unaligned {}", s)
-            }
-
-            TypeSort::POINTER => {
-                let pointee_type: TypeIndex = *self.type_pointer().entry(type_index.index())?;
-                let mut pointee_type_str = self.get_type_string(pointee_type)?;
-                pointee_type_str.push_str("*");
-                pointee_type_str
-            }
-
-            TypeSort::LVALUE_REFERENCE => {
-                let pointee_type: TypeIndex =
-                    *self.type_lvalue_reference().entry(type_index.index())?;
-                let mut pointee_type_str = self.get_type_string(pointee_type)?;
-                pointee_type_str.push_str("&");
-                pointee_type_str
-            }
-
-            TypeSort::RVALUE_REFERENCE => {
-                let pointee_type: TypeIndex =
-                    *self.type_rvalue_reference().entry(type_index.index())?;
-                let mut pointee_type_str = self.get_type_string(pointee_type)?;
-                pointee_type_str.push_str("&&");
-                pointee_type_str
-            }
-
-            TypeSort::DESIGNATED => {
-                let designated_type: DeclIndex =
-                    *self.type_designated().entry(type_index.index())?;
-                match designated_type.tag() {
-                    DeclSort::SCOPE => {
-                        let scope = self.decl_scope().entry(designated_type.index())?;
-                        let scope_name = self.get_name_string(scope.name)?;
-                        format!("{} {:?} ({:?})", scope_name, designated_type, scope)
-                        // scope_name.to_string()
-                    }
-
-                    DeclSort::ENUMERATION => {
-                        let en = self.decl_enum().entry(designated_type.index())?;
-                        let en_name = self.get_string(en.name)?;
-                        en_name.to_string()
-                    }
-
-                    DeclSort::ALIAS => {
-                        let alias = self.decl_alias().entry(designated_type.index())?;
-                        self.get_string(alias.name)?.to_string()
-                    }
-
-                    _ => {
-                        format!("{:?}", designated_type)
-                    }
-                }
-            }
-
-            TypeSort::ARRAY => {
-                let array = self.type_array().entry(type_index.index())?;
-                let element_type_str = self.get_type_string(array.element)?;
-
-                if let Ok(extent) = self.get_literal_expr_u32(array.extent) {
-                    format!("[{}; {}]", element_type_str, extent)
-                } else {
-                    format!("[{}; _]", element_type_str)
-                }
-            }
+        self.get_type_string_at_depth(type_index, 0)
+    }
 
-            _ => format!("{:?}", type_index),
-        })
+    fn get_type_string_at_depth(&self, type_index: TypeIndex, depth: u32) -> Result<String> {
+        CxxTypePrinter::new(self, CxxPrintOptions::default())
+            .declaration_at_depth(type_index, "", depth)
     }
 
     pub fn is_type_namespace(&self, ty: TypeIndex) -> Result<bool> {
@@ -648,11 +631,15 @@ impl Ifc {
 
     pub fn remove_qualifiers(&self, ty: TypeIndex) -> Result<TypeIndex> {
         let mut cur_ty = ty;
-        while cur_ty.tag() == TypeSort::QUALIFIED {
+        // A chain of qualified types can't be longer than type.qualified, unless it is cyclic.
+        for _ in 0..=self.type_qualified().entries.len() {
+            if cur_ty.tag() != TypeSort::QUALIFIED {
+                return Ok(cur_ty);
+            }
             let qt = self.type_qualified().entry(cur_ty.index())?;
             cur_ty = qt.unqualified_type;
         }
-        Ok(cur_ty)
+        bail!("qualified type {:?} is cyclic", ty);
     }
 
     /// Returns `true` if the type is qualified with `const`.
@@ -669,7 +656,7 @@ impl Ifc {
     /// * `int* const`
     pub fn is_const_qualified(&self, ty: TypeIndex) -> Result<bool> {
         let mut cur_ty = ty;
-        loop {
+        for _ in 0..=self.type_qualified().entries.len() {
             if cur_ty.tag() == TypeSort::QUALIFIED {
                 let qt = self.type_qualified().entry(cur_ty.index())?;
                 if qt.qualifiers.contains(Qualifiers::CONST) {
@@ -680,6 +667,7 @@ impl Ifc {
                 return Ok(false);
             }
         }
+        bail!("qualified type {:?} is cyclic", ty);
     }
 
     pub fn is_literal_expr(&self, expr: ExprIndex) -> bool {
@@ -764,16 +752,15 @@ pub struct Part<'a, T> {
 }
 
 impl<'a, T> Part<'a, T> {
-    pub fn entry(&self, entry_index: u32) -> Result<&'a T> {
+    pub fn entry(&self, entry_index: u32) -> IfcResult<&'a T> {
         if let Some(entry) = self.entries.get(entry_index as usize) {
             Ok(entry)
         } else {
-            bail!(
-                "IFC: bad entry index in partition '{}'. index: {}, len: {}",
-                self.part_name,
-                entry_index,
-                self.entries.len()
-            )
+            Err(IfcError::BadEntryIndex {
+                partition: self.part_name,
+                index: entry_index,
+                len: self.entries.len(),
+            })
         }
     }
 }
@@ -782,11 +769,13 @@ pub struct PartEntry {
     pub part_range: Range<usize>,
     pub count: usize,
     pub size: usize,
+    /// True if the records are read directly from the file data, rather than copied.
+    pub borrowed: bool,
 }
 
 pub struct IterScope<'a> {
     members: &'a [DeclIndex],
-    ifc: &'a Ifc,
+    ifc: &'a Ifc<'a>,
 }
 
 impl<'a> Iterator for IterScope<'a> {
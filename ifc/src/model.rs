@@ -0,0 +1,539 @@
+//! A serializable model of the contents of an IFC, for tools that do not link Rust
+//!
+//! This is only built with the `serde` feature. It is a simplified view of the file:
+//! declarations are listed with their qualified names, types are trees, and anything that the
+//! model does not break down is given by its C++ spelling. For example,
+//! `serde_json::to_string(&ifc.export_model()?)` dumps an IFC as JSON.
+
+use super::*;
+use serde_derive::Serialize;
+
+/// The contents of an IFC, as returned by `Ifc::export_model`.
+#[derive(Clone, Debug, Serialize)]
+pub struct IfcModel {
+    pub src_path: String,
+    pub unit: UnitModel,
+    /// The paths of the source files that the unit was built from, from `name.source-file`.
+    pub source_files: Vec<String>,
+    /// Every declaration that is reachable from the global scope, in the order of
+    /// `Ifc::visit`. Enumerators are listed in their enumerations.
+    pub declarations: Vec<DeclModel>,
+    pub macros: Vec<MacroModel>,
+}
+
+/// See `ModuleInfo`.
+#[derive(Clone, Debug, Serialize)]
+pub struct UnitModel {
+    /// The `UnitSort`, e.g. `HEADER`.
+    pub sort: String,
+    /// The name that other units import this one by (see `ModuleInfo::unit_name`).
+    pub name: Option<String>,
+    pub imports: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LocationModel {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DeclModel {
+    /// The qualified name, e.g. `ns::POINT::x`.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<LocationModel>,
+    #[serde(flatten)]
+    pub kind: DeclModelKind,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeclModelKind {
+    Namespace,
+    Class {
+        /// `class`, `struct` or `union`.
+        key: String,
+        /// Whether the class is defined, rather than only declared.
+        complete: bool,
+        bases: Vec<TypeModel>,
+    },
+    Enum {
+        base: Option<TypeModel>,
+        enumerators: Vec<EnumeratorModel>,
+    },
+    Alias {
+        aliasee: TypeModel,
+    },
+    Function {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+        params: Vec<ParamModel>,
+    },
+    Method {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+        params: Vec<ParamModel>,
+    },
+    Constructor {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+    },
+    Destructor,
+    Field {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+    },
+    Bitfield {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+        width: Option<u64>,
+    },
+    Variable {
+        #[serde(rename = "type")]
+        ty: TypeModel,
+    },
+    Template,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EnumeratorModel {
+    pub name: String,
+    /// The value, if its initializer is a constant that `Ifc::eval_const` can compute.
+    pub value: Option<i128>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ParamModel {
+    /// The name of the parameter. This is empty if the parameter is unnamed.
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: TypeModel,
+}
+
+/// A type, as a tree. Aliases are not expanded, so `DWORD` is `Named`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeModel {
+    Fundamental {
+        name: String,
+    },
+    /// A class, enumeration or alias, by its qualified name.
+    Named {
+        name: String,
+    },
+    Pointer {
+        pointee: Box<TypeModel>,
+    },
+    LvalueReference {
+        referent: Box<TypeModel>,
+    },
+    RvalueReference {
+        referent: Box<TypeModel>,
+    },
+    PointerToMember {
+        class: Box<TypeModel>,
+        member: Box<TypeModel>,
+    },
+    Array {
+        element: Box<TypeModel>,
+        extent: Option<u64>,
+    },
+    /// A type with qualifiers, e.g. `const`, `volatile`, `restrict` or `__unaligned`.
+    Qualified {
+        qualifiers: Vec<String>,
+        #[serde(rename = "type")]
+        ty: Box<TypeModel>,
+    },
+    /// A function or, if `class` is set, a non-static member function.
+    Function {
+        return_type: Box<TypeModel>,
+        params: Vec<TypeModel>,
+        class: Option<Box<TypeModel>>,
+        calling_convention: String,
+        noexcept: bool,
+    },
+    Tuple {
+        elements: Vec<TypeModel>,
+    },
+    /// Any other type, by its C++ spelling.
+    Other {
+        spelling: String,
+    },
+}
+
+/// A `#define`.
+#[derive(Clone, Debug, Serialize)]
+pub struct MacroModel {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<LocationModel>,
+    /// The parameters of a function-like macro, or `None` for an object-like macro.
+    pub params: Option<Vec<String>>,
+    pub variadic: bool,
+    /// The tokens of the replacement list.
+    pub body: Vec<String>,
+}
+
+impl<'data> Ifc<'data> {
+    /// Builds a model of the whole IFC, which can be serialized with serde.
+    pub fn export_model(&self) -> Result<IfcModel> {
+        let info = self.module_info()?;
+        let unit = UnitModel {
+            sort: format!("{:?}", info.sort),
+            name: info.unit_name(),
+            imports: info.imports.iter().map(|i| i.unit_name()).collect(),
+        };
+
+        let mut source_files = Vec::new();
+        for file in self.name_source_file().entries.iter() {
+            source_files.push(self.get_string(file.path)?.to_string());
+        }
+
+        let mut exporter = ModelExporter {
+            declarations: Vec::new(),
+        };
+        self.visit(&mut exporter)?;
+
+        let mut macros = Vec::new();
+        for m in self.macro_object_like().entries.iter() {
+            macros.push(MacroModel {
+                name: self.get_string(m.name)?.to_string(),
+                location: self.location_model(&m.locus),
+                params: None,
+                variadic: false,
+                body: self.form_tokens(m.body)?,
+            });
+        }
+        for m in self.macro_function_like().entries.iter() {
+            macros.push(MacroModel {
+                name: self.get_string(m.name)?.to_string(),
+                location: self.location_model(&m.locus),
+                params: Some(if m.arity() == 0 {
+                    Vec::new()
+                } else {
+                    self.form_tokens(m.parameters)?
+                }),
+                variadic: m.is_variadic(),
+                body: self.form_tokens(m.body)?,
+            });
+        }
+
+        Ok(IfcModel {
+            src_path: self.get_string(self.file_header().src_path)?.to_string(),
+            unit,
+            source_files,
+            declarations: exporter.declarations,
+            macros,
+        })
+    }
+
+    /// Builds the tree for a type.
+    pub fn type_model(&self, ty: TypeIndex) -> Result<TypeModel> {
+        self.type_model_at_depth(ty, 0)
+    }
+
+    fn type_model_at_depth(&self, ty: TypeIndex, depth: u32) -> Result<TypeModel> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("type {:?} is nested too deeply (is it cyclic?)", ty);
+        }
+        let boxed = |ty| -> Result<Box<TypeModel>> {
+            Ok(Box::new(self.type_model_at_depth(ty, depth + 1)?))
+        };
+
+        let i = ty.index();
+        Ok(match ty.tag() {
+            TypeSort::FUNDAMENTAL => TypeModel::Fundamental {
+                name: self.get_type_string_at_depth(ty, depth + 1)?,
+            },
+            TypeSort::DESIGNATED => TypeModel::Named {
+                name: self
+                    .qualified_name_at_depth(*self.type_designated().entry(i)?, depth + 1)?
+                    .to_string(),
+            },
+            TypeSort::POINTER => TypeModel::Pointer {
+                pointee: boxed(*self.type_pointer().entry(i)?)?,
+            },
+            TypeSort::LVALUE_REFERENCE => TypeModel::LvalueReference {
+                referent: boxed(*self.type_lvalue_reference().entry(i)?)?,
+            },
+            TypeSort::RVALUE_REFERENCE => TypeModel::RvalueReference {
+                referent: boxed(*self.type_rvalue_reference().entry(i)?)?,
+            },
+            TypeSort::POINTER_TO_MEMBER => {
+                let ptm = self.type_pointer_to_member().entry(i)?;
+                TypeModel::PointerToMember {
+                    class: boxed(ptm.scope)?,
+                    member: boxed(ptm.member)?,
+                }
+            }
+            TypeSort::ARRAY => {
+                let array = self.type_array().entry(i)?;
+                let extent = if array.extent.tag() == ExprSort::EMPTY || array.extent.0 == 0 {
+                    None
+                } else {
+                    // Like the C++ printer, write an extent that we cannot evaluate as `[]`.
+                    self.eval_const_at_depth(array.extent, depth + 1)
+                        .ok()
+                        .and_then(|v| v.to_u64())
+                };
+                TypeModel::Array {
+                    element: boxed(array.element)?,
+                    extent,
+                }
+            }
+            TypeSort::QUALIFIED => {
+                let qualified = self.type_qualified().entry(i)?;
+                let mut qualifiers = Vec::new();
+                for (q, name) in [
+                    (Qualifiers::CONST, "const"),
+                    (Qualifiers::VOLATILE, "volatile"),
+                    (Qualifiers::RESTRICT, "restrict"),
+                ] {
+                    if qualified.qualifiers.contains(q) {
+                        qualifiers.push(name.to_string());
+                    }
+                }
+                TypeModel::Qualified {
+                    qualifiers,
+                    ty: boxed(qualified.unqualified_type)?,
+                }
+            }
+            TypeSort::UNALIGNED => TypeModel::Qualified {
+                qualifiers: vec!["__unaligned".to_string()],
+                ty: boxed(*self.type_unaligned().entry(i)?)?,
+            },
+            TypeSort::FUNCTION => {
+                let func = self.type_function().entry(i)?;
+                TypeModel::Function {
+                    return_type: boxed(func.target)?,
+                    params: self.type_models(func.source, depth + 1)?,
+                    class: None,
+                    calling_convention: calling_convention_string(func.convention),
+                    noexcept: func.eh_spec.sort == NoexceptSort::TRUE,
+                }
+            }
+            TypeSort::METHOD => {
+                let method = self.type_method().entry(i)?;
+                TypeModel::Function {
+                    return_type: boxed(method.target)?,
+                    params: self.type_models(method.source, depth + 1)?,
+                    class: Some(boxed(method.scope)?),
+                    calling_convention: calling_convention_string(method.convention),
+                    noexcept: method.eh_spec.sort == NoexceptSort::TRUE,
+                }
+            }
+            TypeSort::TUPLE => TypeModel::Tuple {
+                elements: self.type_models(ty, depth + 1)?,
+            },
+            _ => TypeModel::Other {
+                spelling: self.get_type_string_at_depth(ty, depth + 1)?,
+            },
+        })
+    }
+
+    /// The trees for the elements of a tuple type (see `Ifc::iter_type_tuple`).
+    fn type_models(&self, ty: TypeIndex, depth: u32) -> Result<Vec<TypeModel>> {
+        let mut models = Vec::new();
+        for element in self.iter_type_tuple(ty)? {
+            models.push(self.type_model_at_depth(element, depth)?);
+        }
+        Ok(models)
+    }
+
+    fn location_model(&self, locus: &SourceLocation) -> Option<LocationModel> {
+        let (file, line, column) = self.resolve_locus(locus)?;
+        Some(LocationModel {
+            file: file.to_string(),
+            line,
+            column,
+        })
+    }
+
+    fn decl_model(
+        &self,
+        decl: DeclIndex,
+        locus: &SourceLocation,
+        kind: DeclModelKind,
+    ) -> Result<DeclModel> {
+        Ok(DeclModel {
+            name: self.qualified_name(decl)?.to_string(),
+            location: self.location_model(locus),
+            kind,
+        })
+    }
+
+    fn param_models(&self, func: &DeclFunc) -> Result<Vec<ParamModel>> {
+        let mut params = Vec::new();
+        for param in self.function_parameters(func)? {
+            params.push(ParamModel {
+                name: param.name.to_string(),
+                ty: self.type_model(param.ty)?,
+            });
+        }
+        Ok(params)
+    }
+}
+
+/// Collects the declarations for `Ifc::export_model`.
+struct ModelExporter {
+    declarations: Vec<DeclModel>,
+}
+
+impl ModelExporter {
+    fn add(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        locus: &SourceLocation,
+        kind: DeclModelKind,
+    ) -> Result<()> {
+        self.declarations.push(ifc.decl_model(decl, locus, kind)?);
+        Ok(())
+    }
+}
+
+impl DeclVisitor for ModelExporter {
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        self.add(ifc, decl, &scope.locus, DeclModelKind::Namespace)?;
+        Ok(true)
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        let mut bases = Vec::new();
+        for base in ifc.iter_type_tuple(scope.base)? {
+            let base = match base.tag() {
+                TypeSort::BASE => ifc.type_base().entry(base.index())?.ty,
+                _ => base,
+            };
+            bases.push(ifc.type_model(base)?);
+        }
+        let kind = DeclModelKind::Class {
+            key: ifc.get_type_string(scope.ty)?,
+            complete: scope.initializer != 0,
+            bases,
+        };
+        self.add(ifc, decl, &scope.locus, kind)?;
+        Ok(true)
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        let base = if en.base.is_null() {
+            None
+        } else {
+            Some(ifc.type_model(en.base)?)
+        };
+        let kind = DeclModelKind::Enum {
+            base,
+            enumerators: Vec::new(),
+        };
+        self.add(ifc, decl, &en.locus, kind)?;
+        Ok(true)
+    }
+
+    fn visit_enumerator(
+        &mut self,
+        ifc: &Ifc,
+        _decl: DeclIndex,
+        enumerator: &DeclEnumerator,
+    ) -> Result<()> {
+        // Enumerators are visited right after their enumeration.
+        if let Some(DeclModel {
+            kind: DeclModelKind::Enum { enumerators, .. },
+            ..
+        }) = self.declarations.last_mut()
+        {
+            let value = match ifc.eval_const(enumerator.initializer) {
+                Ok(ConstValue::Int(value)) => Some(value),
+                Ok(ConstValue::Bool(value)) => Some(value as i128),
+                _ => None,
+            };
+            enumerators.push(EnumeratorModel {
+                name: ifc.get_string(enumerator.name)?.to_string(),
+                value,
+            });
+        }
+        Ok(())
+    }
+
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, func: &DeclFunc) -> Result<()> {
+        let kind = DeclModelKind::Function {
+            ty: ifc.type_model(func.type_)?,
+            params: ifc.param_models(func)?,
+        };
+        self.add(ifc, decl, &func.locus, kind)
+    }
+
+    fn visit_method(&mut self, ifc: &Ifc, decl: DeclIndex, method: &DeclMethod) -> Result<()> {
+        let kind = DeclModelKind::Method {
+            ty: ifc.type_model(method.type_)?,
+            params: ifc.param_models(method)?,
+        };
+        self.add(ifc, decl, &method.locus, kind)
+    }
+
+    fn visit_constructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        ctor: &DeclConstructor,
+    ) -> Result<()> {
+        let kind = DeclModelKind::Constructor {
+            ty: ifc.type_model(ctor.type_)?,
+        };
+        self.add(ifc, decl, &ctor.locus, kind)
+    }
+
+    fn visit_destructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        dtor: &DeclDestructor,
+    ) -> Result<()> {
+        self.add(ifc, decl, &dtor.locus, DeclModelKind::Destructor)
+    }
+
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, field: &DeclField) -> Result<()> {
+        let kind = DeclModelKind::Field {
+            ty: ifc.type_model(field.ty)?,
+        };
+        self.add(ifc, decl, &field.locus, kind)
+    }
+
+    fn visit_bitfield(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        bitfield: &DeclBitfield,
+    ) -> Result<()> {
+        let kind = DeclModelKind::Bitfield {
+            ty: ifc.type_model(bitfield.ty)?,
+            width: ifc.eval_const(bitfield.width).ok().and_then(|w| w.to_u64()),
+        };
+        self.add(ifc, decl, &bitfield.locus, kind)
+    }
+
+    fn visit_variable(&mut self, ifc: &Ifc, decl: DeclIndex, var: &DeclVar) -> Result<()> {
+        let kind = DeclModelKind::Variable {
+            ty: ifc.type_model(var.ty)?,
+        };
+        self.add(ifc, decl, &var.locus, kind)
+    }
+
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, alias: &DeclAlias) -> Result<()> {
+        let kind = DeclModelKind::Alias {
+            aliasee: ifc.type_model(alias.aliasee)?,
+        };
+        self.add(ifc, decl, &alias.locus, kind)
+    }
+
+    fn visit_template(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        template: &DeclTemplate,
+    ) -> Result<()> {
+        self.add(ifc, decl, &template.locus, DeclModelKind::Template)
+    }
+}
@@ -0,0 +1,314 @@
+//! Translation units and modules - Chapters 4 and 5
+
+use super::*;
+use std::path::{Path, PathBuf};
+
+/// Describes the translation unit that an IFC was built from, as returned by
+/// `Ifc::module_info`.
+#[derive(Clone, Debug)]
+pub struct ModuleInfo<'a> {
+    pub sort: UnitSort,
+    /// The name of the module, for module interfaces and partitions. For an exported
+    /// translation unit, this is the name given by `/module:name`.
+    pub module_name: Option<&'a str>,
+    /// The name of the partition, e.g. `P` for the partition `M:P`.
+    pub partition_name: Option<&'a str>,
+    /// The path of the source file. For a header unit, this is the path of the header.
+    pub src_path: &'a str,
+    /// The units that this unit imports, from `module.imported`, followed by the ones that it
+    /// re-exports, from `module.exported`.
+    pub imports: Vec<ImportedUnit<'a>>,
+}
+
+/// A module, module partition or header unit that is imported by another unit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportedUnit<'a> {
+    /// The name of the module, or `None` for a header unit (which belongs to the global
+    /// module).
+    pub owner: Option<&'a str>,
+    /// The name of the partition, or the path of a header unit.
+    pub partition: Option<&'a str>,
+    /// Whether the unit is re-exported, as in `export import M;`.
+    pub exported: bool,
+}
+
+impl<'a> ModuleInfo<'a> {
+    /// The name that other units use to import this one: `M` for a primary module interface,
+    /// `M:P` for a partition, and the path for a header unit. Source files cannot be imported,
+    /// so they have no name.
+    pub fn unit_name(&self) -> Option<String> {
+        match (self.sort, self.module_name) {
+            (UnitSort::HEADER, _) => Some(self.src_path.to_string()),
+            (UnitSort::SOURCE, _) | (_, None) => None,
+            (_, Some(module)) => Some(match self.partition_name {
+                Some(partition) => format!("{}:{}", module, partition),
+                None => module.to_string(),
+            }),
+        }
+    }
+}
+
+impl<'a> ImportedUnit<'a> {
+    pub fn is_header_unit(&self) -> bool {
+        self.owner.is_none()
+    }
+
+    /// The name of the imported unit, in the same form as `ModuleInfo::unit_name`.
+    pub fn unit_name(&self) -> String {
+        match (self.owner, self.partition) {
+            (Some(owner), Some(partition)) => format!("{}:{}", owner, partition),
+            (Some(owner), None) => owner.to_string(),
+            (None, partition) => partition.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// The kind of translation unit that this IFC was built from, from `FileHeader::unit`.
+    pub fn unit_sort(&self) -> UnitSort {
+        UnitSort::from_u32(self.file_header.unit & 7)
+    }
+
+    /// Describes the translation unit: its kind, its name, and the units that it imports.
+    pub fn module_info(&self) -> IfcResult<ModuleInfo<'_>> {
+        let sort = self.unit_sort();
+        // The rest of the unit index is the name, for the sorts that have one.
+        let name = match sort {
+            UnitSort::PRIMARY | UnitSort::PARTITION | UnitSort::EXPORTED_TU => {
+                self.optional_string(self.file_header.unit >> 3)?
+            }
+            _ => None,
+        };
+        let (module_name, partition_name) = match (sort, name) {
+            (UnitSort::PARTITION, Some(name)) => match name.split_once(':') {
+                Some((module, partition)) => (Some(module), Some(partition)),
+                None => (Some(name), None),
+            },
+            _ => (name, None),
+        };
+
+        let mut imports = Vec::new();
+        for (part, exported) in [
+            (self.module_imported(), false),
+            (self.module_exported(), true),
+        ] {
+            for r in part.entries.iter() {
+                imports.push(ImportedUnit {
+                    owner: self.optional_string(r.owner)?,
+                    partition: self.optional_string(r.partition)?,
+                    exported,
+                });
+            }
+        }
+
+        Ok(ModuleInfo {
+            sort,
+            module_name,
+            partition_name,
+            src_path: self.get_string(self.file_header.src_path)?,
+            imports,
+        })
+    }
+
+    /// Reads a string that may be null (`TextOffset` 0).
+    pub(crate) fn optional_string(&self, text: TextOffset) -> IfcResult<Option<&str>> {
+        Ok(if text == 0 {
+            None
+        } else {
+            Some(self.get_string(text)?)
+        })
+    }
+}
+
+/// A unit in an `IfcSet`.
+pub struct IfcSetEntry {
+    pub path: PathBuf,
+    pub ifc: Ifc<'static>,
+    /// The name that other units use to import this one (see `ModuleInfo::unit_name`). Source
+    /// files use their `src_path`, since nothing can import them.
+    pub name: String,
+    /// The names of the units that this one imports, and whether each one is re-exported.
+    pub imports: Vec<(String, bool)>,
+}
+
+/// A set of IFC files, such as the output directory of a build, and the import graph between
+/// them.
+///
+/// Units are identified by their index in the set. An import refers to a unit by name, so
+/// imports of units that are not in the set are "unresolved", and do not appear in the graph.
+#[derive(Default)]
+pub struct IfcSet {
+    entries: Vec<IfcSetEntry>,
+    by_name: HashMap<String, usize>,
+}
+
+impl IfcSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.ifc` file in a directory (but not its subdirectories).
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file()
+                && matches!(path.extension(), Some(e) if e.eq_ignore_ascii_case("ifc"))
+            {
+                paths.push(path);
+            }
+        }
+        // Directory order is arbitrary, so sort to make the indexes stable.
+        paths.sort();
+
+        let mut set = Self::new();
+        for path in paths {
+            let ifc = match Ifc::from_file(&path) {
+                Ok(ifc) => ifc,
+                Err(e) => bail!("{}: {}", path.display(), e),
+            };
+            set.insert(path, ifc)?;
+        }
+        Ok(set)
+    }
+
+    /// Adds a unit to the set and returns its index. It is an error to add two units with the
+    /// same name.
+    pub fn insert(&mut self, path: PathBuf, ifc: Ifc<'static>) -> Result<usize> {
+        let info = ifc.module_info()?;
+        let name = match info.unit_name() {
+            Some(name) => name,
+            None => info.src_path.to_string(),
+        };
+        if let Some(&existing) = self.by_name.get(&name) {
+            bail!(
+                "{} and {} both define the unit {}",
+                self.entries[existing].path.display(),
+                path.display(),
+                name
+            );
+        }
+        let imports = info
+            .imports
+            .iter()
+            .map(|import| (import.unit_name(), import.exported))
+            .collect();
+
+        let index = self.entries.len();
+        self.by_name.insert(name.clone(), index);
+        self.entries.push(IfcSetEntry {
+            path,
+            ifc,
+            name,
+            imports,
+        });
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[IfcSetEntry] {
+        &self.entries
+    }
+
+    /// Finds a unit by name, e.g. `M`, `M:P` or the path of a header unit.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.by_name.get(name).copied()
+    }
+
+    /// The units in the set that `unit` imports.
+    pub fn dependencies(&self, unit: usize) -> Vec<usize> {
+        self.entries[unit]
+            .imports
+            .iter()
+            .filter_map(|(name, _)| self.find(name))
+            .collect()
+    }
+
+    /// The names of the units that `unit` imports but that are not in the set.
+    pub fn unresolved_imports(&self, unit: usize) -> Vec<&str> {
+        self.entries[unit]
+            .imports
+            .iter()
+            .filter(|(name, _)| self.find(name).is_none())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Orders the units so that every unit comes after the units that it imports. Fails if
+    /// the imports contain a cycle.
+    pub fn topological_order(&self) -> Result<Vec<usize>> {
+        match self.sort() {
+            Ok(order) => Ok(order),
+            Err(cycle) => {
+                let names: Vec<&str> = cycle
+                    .iter()
+                    .map(|&i| self.entries[i].name.as_str())
+                    .collect();
+                bail!("import cycle: {}", names.join(" -> "))
+            }
+        }
+    }
+
+    /// Finds a cycle of imports, if there is one. The first unit of the cycle is repeated at
+    /// the end, e.g. `[a, b, a]`.
+    pub fn find_cycle(&self) -> Option<Vec<usize>> {
+        self.sort().err()
+    }
+
+    /// Depth-first search, which returns either the units in topological order or a cycle.
+    /// This uses an explicit stack, since import chains can be long.
+    fn sort(&self) -> core::result::Result<Vec<usize>, Vec<usize>> {
+        #[derive(Copy, Clone, PartialEq)]
+        enum State {
+            New,
+            Active,
+            Done,
+        }
+
+        let deps: Vec<Vec<usize>> = (0..self.len()).map(|i| self.dependencies(i)).collect();
+        let mut state = vec![State::New; self.len()];
+        let mut next = vec![0; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+
+        for root in 0..self.len() {
+            if state[root] != State::New {
+                continue;
+            }
+            state[root] = State::Active;
+            let mut stack = vec![root];
+            while let Some(&unit) = stack.last() {
+                match deps[unit].get(next[unit]) {
+                    Some(&dep) => {
+                        next[unit] += 1;
+                        match state[dep] {
+                            State::New => {
+                                state[dep] = State::Active;
+                                stack.push(dep);
+                            }
+                            State::Active => {
+                                let start = stack.iter().position(|&u| u == dep).unwrap_or(0);
+                                let mut cycle = stack[start..].to_vec();
+                                cycle.push(dep);
+                                return Err(cycle);
+                            }
+                            State::Done => {}
+                        }
+                    }
+                    None => {
+                        state[unit] = State::Done;
+                        order.push(unit);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+}
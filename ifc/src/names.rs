@@ -4,12 +4,370 @@ use super::*;
 
 /// `name.operator`
 #[repr(C)]
-#[derive(AsBytes, FromBytes, Clone)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
 pub struct NameOperator {
+    /// The text of the operator, such as `+` or `new[]`.
     pub encoded: TextOffset,
-    pub operator: u16,
+    pub operator: Operator,
     pub __padding: u16,
 }
 
-struct Operator {
+/// `name.conversion`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct NameConversion {
+    pub target: TypeIndex,
+    pub encoded: TextOffset,
+}
+
+/// `name.specialization`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct NameSpecialization {
+    pub primary: NameIndex,
+    /// A single template argument, or an `ExprSort::TUPLE` of them.
+    pub arguments: ExprIndex,
+}
+
+/// A decoded name, as returned by `Ifc::name`.
+#[derive(Clone, Debug)]
+pub enum Name<'a> {
+    Identifier(&'a str),
+    Operator(&'a NameOperator),
+    Conversion(&'a NameConversion),
+    /// A literal operator, e.g. `operator""_km`. This is the text encoding of the operator.
+    Literal(TextOffset),
+    /// An assumed template name. This refers to the name that is assumed to be a template.
+    Template(NameIndex),
+    Specialization(&'a NameSpecialization),
+    SourceFile(&'a NameSourceFile),
+    /// A deduction guide. This refers to the primary class template.
+    Guide(DeclIndex),
+}
+
+impl<'data> Ifc<'data> {
+    /// Looks up the record for a name.
+    pub fn name(&self, name: NameIndex) -> IfcResult<Name<'_>> {
+        let i = name.index();
+        Ok(match name.tag() {
+            NameSort::IDENTIFIER => Name::Identifier(self.get_string(i)?),
+            NameSort::OPERATOR => Name::Operator(self.name_operator().entry(i)?),
+            NameSort::CONVERSION => Name::Conversion(self.name_conversion().entry(i)?),
+            NameSort::LITERAL => Name::Literal(*self.name_literal().entry(i)?),
+            NameSort::TEMPLATE => Name::Template(*self.name_template().entry(i)?),
+            NameSort::SPECIALIZATION => {
+                Name::Specialization(self.name_specialization().entry(i)?)
+            }
+            NameSort::SOURCE_FILE => Name::SourceFile(self.name_source_file().entry(i)?),
+            // NameSort is 3 bits, so this is always GUIDE.
+            _ => Name::Guide(*self.name_guide().entry(i)?),
+        })
+    }
+
+    /// Returns the name as it would be written in C++, e.g. `POINT`, `operator+`,
+    /// `operator int` or `vector<int>`. Identifiers are borrowed from the string table; only
+    /// composite names, such as specializations, are built.
+    pub fn get_name_string(&self, name: NameIndex) -> IfcResult<Cow<'_, str>> {
+        self.get_name_string_at_depth(name, 0)
+            .map_err(|e| match e.downcast::<IfcError>() {
+                Ok(e) => e,
+                Err(e) => IfcError::BadName {
+                    name,
+                    message: e.to_string(),
+                },
+            })
+    }
+
+    pub(crate) fn get_name_string_at_depth(
+        &self,
+        name: NameIndex,
+        depth: u32,
+    ) -> Result<Cow<'_, str>> {
+        // Specializations contain types, which contain names, so a malformed file could
+        // contain a cycle.
+        if depth > MAX_TYPE_DEPTH {
+            bail!("name {:?} is nested too deeply (is it cyclic?)", name);
+        }
+
+        Ok(match self.name(name)? {
+            Name::Identifier(s) => Cow::Borrowed(s),
+            Name::Operator(op) => {
+                let encoded = self.get_string(op.encoded)?;
+                if encoded.starts_with("operator") {
+                    Cow::Borrowed(encoded)
+                } else if encoded.starts_with(|c: char| c.is_alphabetic()) {
+                    // `operator new`, `operator delete[]`, `operator co_await`
+                    Cow::Owned(format!("operator {}", encoded))
+                } else {
+                    Cow::Owned(format!("operator{}", encoded))
+                }
+            }
+            Name::Conversion(conv) => {
+                if conv.target.is_null() {
+                    Cow::Borrowed(self.get_string(conv.encoded)?)
+                } else {
+                    let target = self.get_type_string_at_depth(conv.target, depth + 1)?;
+                    Cow::Owned(format!("operator {}", target))
+                }
+            }
+            Name::Literal(encoded) => {
+                let encoded = self.get_string(encoded)?;
+                if encoded.starts_with("operator") {
+                    Cow::Borrowed(encoded)
+                } else if encoded.starts_with("\"\"") {
+                    Cow::Owned(format!("operator{}", encoded))
+                } else {
+                    Cow::Owned(format!("operator\"\"{}", encoded))
+                }
+            }
+            Name::Template(assumed) => self.get_name_string_at_depth(assumed, depth + 1)?,
+            Name::Specialization(spec) => {
+                let mut s = self
+                    .get_name_string_at_depth(spec.primary, depth + 1)?
+                    .into_owned();
+                s.push('<');
+                for (i, arg) in self.iter_expr_tuple(spec.arguments)?.enumerate() {
+                    if i != 0 {
+                        s.push_str(", ");
+                    }
+                    s.push_str(&self.get_template_argument_string(arg, depth + 1)?);
+                }
+                s.push('>');
+                Cow::Owned(s)
+            }
+            Name::SourceFile(file) => Cow::Borrowed(self.get_string(file.path)?),
+            Name::Guide(primary) => match primary.tag() {
+                DeclSort::TEMPLATE => {
+                    let template = self.decl_template().entry(primary.index())?;
+                    self.get_name_string_at_depth(template.name, depth + 1)?
+                }
+                _ => Cow::Owned(format!("{:?}", primary)),
+            },
+        })
+    }
+
+    /// Template arguments are usually types or constants. Anything else is shown as `?`.
+    pub(crate) fn get_template_argument_string(&self, arg: ExprIndex, depth: u32) -> Result<String> {
+        if let Expr::Type(t) = self.expr(arg)? {
+            return self.get_type_string_at_depth(t.denotation, depth);
+        }
+        Ok(match self.eval_const(arg) {
+            Ok(ConstValue::Bool(b)) => b.to_string(),
+            Ok(ConstValue::Int(i)) => i.to_string(),
+            Ok(ConstValue::Float(f)) => f.to_string(),
+            Err(_) => "?".to_string(),
+        })
+    }
+}
+
+/// The fully-qualified name of a declaration, as returned by `Ifc::qualified_name`. The
+/// components are ordered from the outermost scope to the declaration itself. It displays as
+/// the components joined by `::`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QualifiedName<'a>(pub Vec<Cow<'a, str>>);
+
+impl<'a> core::fmt::Display for QualifiedName<'a> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for (i, component) in self.0.iter().enumerate() {
+            if i != 0 {
+                fmt.write_str("::")?;
+            }
+            fmt.write_str(component)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// Returns the name of a declaration, or `None` if this sort of declaration does not have
+    /// a name (such as a friend declaration or a tuple).
+    pub fn decl_name(&self, decl: DeclIndex) -> Result<Option<Cow<'_, str>>> {
+        self.decl_name_at_depth(decl, 0)
+    }
+
+    pub(crate) fn decl_name_at_depth(
+        &self,
+        decl: DeclIndex,
+        depth: u32,
+    ) -> Result<Option<Cow<'_, str>>> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("declaration {:?} is nested too deeply (is it cyclic?)", decl);
+        }
+
+        let i = decl.index();
+        let name = |name: NameIndex| -> Result<Option<Cow<'_, str>>> {
+            Ok(Some(self.get_name_string_at_depth(name, depth + 1)?))
+        };
+        let text = |text: TextOffset| -> Result<Option<Cow<'_, str>>> {
+            Ok(Some(Cow::Borrowed(self.get_string(text)?)))
+        };
+
+        match decl.tag() {
+            DeclSort::ENUMERATOR => text(self.decl_enumerator().entry(i)?.name),
+            DeclSort::VARIABLE => name(self.decl_var().entry(i)?.name),
+            DeclSort::PARAMETER => text(self.decl_parameter().entry(i)?.name),
+            DeclSort::FIELD => text(self.decl_field().entry(i)?.name),
+            DeclSort::BITFIELD => text(self.decl_bitfield().entry(i)?.name),
+            DeclSort::SCOPE => name(self.decl_scope().entry(i)?.name),
+            DeclSort::ENUMERATION => text(self.decl_enum().entry(i)?.name),
+            DeclSort::ALIAS => text(self.decl_alias().entry(i)?.name),
+            DeclSort::TEMPLOID => {
+                let temploid = self.decl_temploid().entry(i)?;
+                self.decl_name_at_depth(temploid.entity.decl, depth + 1)
+            }
+            DeclSort::TEMPLATE => name(self.decl_template().entry(i)?.name),
+            DeclSort::PARTIAL_SPECIALIZATION => {
+                name(self.decl_partial_specialization().entry(i)?.name)
+            }
+            DeclSort::EXPLICIT_SPECIALIZATION => {
+                let spec = self.decl_specialization().entry(i)?;
+                self.decl_name_at_depth(spec.decl, depth + 1)
+            }
+            DeclSort::CONCEPT => text(self.decl_concept().entry(i)?.name),
+            DeclSort::FUNCTION => name(self.decl_function().entry(i)?.name),
+            DeclSort::METHOD => name(self.decl_method().entry(i)?.name),
+            DeclSort::CONSTRUCTOR => text(self.decl_constructor().entry(i)?.name),
+            DeclSort::INHERITED_CONSTRUCTOR => {
+                text(self.decl_inherited_constructor().entry(i)?.name)
+            }
+            DeclSort::DESTRUCTOR => text(self.decl_destructor().entry(i)?.name),
+            DeclSort::USING_DECLARATION => name(self.decl_using_declaration().entry(i)?.name),
+            DeclSort::DEDUCTION_GUIDE => name(self.decl_deduction_guide().entry(i)?.name),
+            DeclSort::INTRINSIC => text(self.decl_intrinsic().entry(i)?.name),
+            DeclSort::OUTPUT_SEGMENT => text(self.decl_segment().entry(i)?.name),
+            _ => Ok(None),
+        }
+    }
+
+    /// Maps each enumerator to the first enumeration whose range of enumerators contains it.
+    fn enumerator_owners(&self) -> &[Option<Index>] {
+        self.enumerator_owners.get_or_init(|| {
+            let len = self.decl_enumerator().entries.len();
+            let mut owners = vec![None; len];
+            for (en, decl) in self.decl_enum().entries.iter().enumerate() {
+                // Ranges in a malformed file can be huge, so only look at the enumerators
+                // that exist.
+                let range = decl.initializer.to_range();
+                let start = (range.start as usize).min(len);
+                let end = (range.end as usize).min(len);
+                for owner in owners[start..end].iter_mut() {
+                    if owner.is_none() {
+                        *owner = Some(en as Index);
+                    }
+                }
+            }
+            owners
+        })
+    }
+
+    /// Returns the scope that encloses a declaration, or `None` for declarations at global
+    /// scope and declarations that do not record their scope (such as parameters).
+    ///
+    /// Enumerators do not record their scope, so the first call builds a map from enumerators
+    /// to the enumerations that contain them.
+    pub fn home_scope(&self, decl: DeclIndex) -> Result<Option<DeclIndex>> {
+        self.home_scope_at_depth(decl, 0)
+    }
+
+    fn home_scope_at_depth(&self, decl: DeclIndex, depth: u32) -> Result<Option<DeclIndex>> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("declaration {:?} is nested too deeply (is it cyclic?)", decl);
+        }
+
+        let i = decl.index();
+        let home_scope = match decl.tag() {
+            DeclSort::ENUMERATOR => {
+                let owners = self.enumerator_owners();
+                let en = owners.get(i as usize).copied().flatten();
+                return Ok(en.map(|en| DeclIndex::new(DeclSort::ENUMERATION, en)));
+            }
+            DeclSort::VARIABLE => self.decl_var().entry(i)?.home_scope,
+            DeclSort::FIELD => self.decl_field().entry(i)?.home_scope,
+            DeclSort::BITFIELD => self.decl_bitfield().entry(i)?.home_scope,
+            DeclSort::SCOPE => self.decl_scope().entry(i)?.home_scope,
+            DeclSort::ENUMERATION => self.decl_enum().entry(i)?.home_scope,
+            DeclSort::ALIAS => self.decl_alias().entry(i)?.home_scope,
+            DeclSort::TEMPLOID => {
+                let temploid = self.decl_temploid().entry(i)?;
+                return self.home_scope_at_depth(temploid.entity.decl, depth + 1);
+            }
+            DeclSort::TEMPLATE => self.decl_template().entry(i)?.home_scope,
+            DeclSort::PARTIAL_SPECIALIZATION => {
+                self.decl_partial_specialization().entry(i)?.home_scope
+            }
+            DeclSort::EXPLICIT_SPECIALIZATION => {
+                let spec = self.decl_specialization().entry(i)?;
+                return self.home_scope_at_depth(spec.decl, depth + 1);
+            }
+            DeclSort::CONCEPT => self.decl_concept().entry(i)?.home_scope,
+            DeclSort::FUNCTION => self.decl_function().entry(i)?.home_scope,
+            DeclSort::METHOD => self.decl_method().entry(i)?.home_scope,
+            DeclSort::CONSTRUCTOR => self.decl_constructor().entry(i)?.home_scope,
+            DeclSort::INHERITED_CONSTRUCTOR => {
+                self.decl_inherited_constructor().entry(i)?.home_scope
+            }
+            DeclSort::DESTRUCTOR => self.decl_destructor().entry(i)?.home_scope,
+            DeclSort::USING_DECLARATION => self.decl_using_declaration().entry(i)?.home_scope,
+            DeclSort::INTRINSIC => self.decl_intrinsic().entry(i)?.home_scope,
+            _ => return Ok(None),
+        };
+        Ok(if home_scope.0 == 0 {
+            None
+        } else {
+            Some(home_scope)
+        })
+    }
+
+    /// Returns the fully-qualified name of a declaration, e.g. `ns::Outer::Inner::method`, by
+    /// walking the chain of home scopes.
+    ///
+    /// Anonymous namespaces are shown as `(anonymous namespace)`, and other declarations that
+    /// have no name as `(unnamed)`. Unnamed classes keep the name that the compiler gave them,
+    /// such as `<unnamed-tag>`.
+    pub fn qualified_name(&self, decl: DeclIndex) -> Result<QualifiedName<'_>> {
+        self.qualified_name_at_depth(decl, 0)
+    }
+
+    pub(crate) fn qualified_name_at_depth(
+        &self,
+        decl: DeclIndex,
+        depth: u32,
+    ) -> Result<QualifiedName<'_>> {
+        let mut components = Vec::new();
+        let mut current = Some(decl);
+        while let Some(decl) = current {
+            if components.len() as u32 > MAX_TYPE_DEPTH {
+                bail!("declaration {:?} is nested too deeply (is it cyclic?)", decl);
+            }
+
+            components.push(self.qualified_name_component_at_depth(decl, depth)?);
+            current = self.home_scope(decl)?;
+        }
+        components.reverse();
+        Ok(QualifiedName(components))
+    }
+
+    /// The name of `decl` as it appears in a qualified name.
+    pub(crate) fn qualified_name_component(&self, decl: DeclIndex) -> Result<Cow<'_, str>> {
+        self.qualified_name_component_at_depth(decl, 0)
+    }
+
+    fn qualified_name_component_at_depth(
+        &self,
+        decl: DeclIndex,
+        depth: u32,
+    ) -> Result<Cow<'_, str>> {
+        Ok(match self.decl_name_at_depth(decl, depth)? {
+            Some(name) if !name.is_empty() => name,
+            _ => {
+                let is_namespace = decl.tag() == DeclSort::SCOPE
+                    && self.is_type_namespace(self.decl_scope().entry(decl.index())?.ty)?;
+                Cow::Borrowed(if is_namespace {
+                    "(anonymous namespace)"
+                } else {
+                    "(unnamed)"
+                })
+            }
+        })
+    }
 }
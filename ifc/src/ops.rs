@@ -2,11 +2,146 @@
 
 use super::*;
 
-// NiladicOperators
-// MonadicOperators
-// DyadicOperators
+/// A semantic operator of any sort. The low 4 bits are the `OperatorSort`, and the remaining
+/// 12 bits are the operator, e.g. a `MonadicOperator` if the sort is `OperatorSort::MONADIC`.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, AsBytes, FromBytes)]
+pub struct Operator(pub u16);
+
+impl Operator {
+    pub const fn sort(self) -> OperatorSort {
+        OperatorSort::from_u32((self.0 & 0xf) as u32)
+    }
+
+    pub const fn index(self) -> u16 {
+        self.0 >> 4
+    }
+
+    pub const fn new(sort: OperatorSort, index: u16) -> Self {
+        Self(sort.0 as u16 | (index << 4))
+    }
+}
+
+impl Debug for Operator {
+    fn fmt(&self, fmt: &mut Formatter) -> core::fmt::Result {
+        match self.sort() {
+            OperatorSort::NILADIC => write!(fmt, "{:?}", NiladicOperator(self.index())),
+            OperatorSort::MONADIC => write!(fmt, "{:?}", MonadicOperator(self.index())),
+            OperatorSort::DYADIC => write!(fmt, "{:?}", DyadicOperator(self.index())),
+            OperatorSort::TRIADIC => write!(fmt, "{:?}", TriadicOperator(self.index())),
+            OperatorSort::STORAGE => write!(fmt, "{:?}", StorageOperator(self.index())),
+            OperatorSort::VARIADIC => write!(fmt, "{:?}", VariadicOperator(self.index())),
+            sort => write!(fmt, "{:?}({})", sort, self.index()),
+        }
+    }
+}
 
 #[c_enum(storage = "u32")]
+pub enum OperatorSort {
+    NILADIC = 0x00,
+    MONADIC = 0x01,
+    DYADIC = 0x02,
+    TRIADIC = 0x03,
+    STORAGE = 0x0E,
+    VARIADIC = 0x0F,
+}
+
+#[c_enum(storage = "u16")]
+pub enum NiladicOperator {
+    UNKNOWN = 0x00,
+    PHANTOM = 0x01,
+    CONSTANT = 0x02,
+    NIL = 0x03,
+    MSVC = 0x400,
+    MSVC_CONSTANT_OBJECT = 0x401,
+    MSVC_LAMBDA = 0x402,
+}
+
+#[c_enum(storage = "u16")]
+pub enum MonadicOperator {
+    UNKNOWN = 0x00,
+    PLUS = 0x01,
+    NEGATE = 0x02,
+    DEREF = 0x03,
+    ADDRESS = 0x04,
+    COMPLEMENT = 0x05,
+    NOT = 0x06,
+    PRE_INCREMENT = 0x07,
+    PRE_DECREMENT = 0x08,
+    POST_INCREMENT = 0x09,
+    POST_DECREMENT = 0x0A,
+    TRUNCATE = 0x0B,
+    CEIL = 0x0C,
+    FLOOR = 0x0D,
+    PAREN = 0x0E,
+    BRACE = 0x0F,
+    ALIGNAS = 0x10,
+    ALIGNOF = 0x11,
+    SIZEOF = 0x12,
+    CARDINALITY = 0x13,
+    TYPEID = 0x14,
+    NOEXCEPT = 0x15,
+    REQUIRES = 0x16,
+    CO_RETURN = 0x17,
+    AWAIT = 0x18,
+    YIELD = 0x19,
+    THROW = 0x1A,
+    NEW = 0x1B,
+    DELETE = 0x1C,
+    DELETE_ARRAY = 0x1D,
+    EXPAND = 0x1E,
+    READ = 0x1F,
+    MATERIALIZE = 0x20,
+    PSEUDO_DTOR_CALL = 0x21,
+    LOOKUP_GLOBALLY = 0x22,
+    MSVC = 0x400,
+    MSVC_ASSUME = 0x401,
+    MSVC_ALIGNOF = 0x402,
+    MSVC_UUIDOF = 0x403,
+    MSVC_IS_CLASS = 0x404,
+    MSVC_IS_UNION = 0x405,
+    MSVC_IS_ENUM = 0x406,
+    MSVC_IS_POLYMORPHIC = 0x407,
+    MSVC_IS_EMPTY = 0x408,
+    MSVC_IS_TRIVIALLY_COPY_CONSTRUCTIBLE = 0x409,
+    MSVC_IS_TRIVIALLY_COPY_ASSIGNABLE = 0x40A,
+    MSVC_IS_TRIVIALLY_DESTRUCTIBLE = 0x40B,
+    MSVC_HAS_VIRTUAL_DESTRUCTOR = 0x40C,
+    MSVC_IS_NOTHROW_COPY_CONSTRUCTIBLE = 0x40D,
+    MSVC_IS_NOTHROW_COPY_ASSIGNABLE = 0x40E,
+    MSVC_IS_POD = 0x40F,
+    MSVC_IS_ABSTRACT = 0x410,
+    MSVC_IS_TRIVIAL = 0x411,
+    MSVC_IS_TRIVIALLY_COPYABLE = 0x412,
+    MSVC_IS_STANDARD_LAYOUT = 0x413,
+    MSVC_IS_LITERAL_TYPE = 0x414,
+    MSVC_IS_TRIVIALLY_MOVE_CONSTRUCTIBLE = 0x415,
+    MSVC_HAS_TRIVIAL_MOVE_ASSIGN = 0x416,
+    MSVC_IS_TRIVIALLY_MOVE_ASSIGNABLE = 0x417,
+    MSVC_IS_NOTHROW_MOVE_ASSIGNABLE = 0x418,
+    MSVC_UNDERLYING_TYPE = 0x419,
+    MSVC_IS_DESTRUCTIBLE = 0x41A,
+    MSVC_IS_NOTHROW_DESTRUCTIBLE = 0x41B,
+    MSVC_HAS_UNIQUE_OBJECT_REPRESENTATIONS = 0x41C,
+    MSVC_IS_AGGREGATE = 0x41D,
+    MSVC_BUILTIN_ADDRESS_OF = 0x41E,
+    MSVC_IS_REF_CLASS = 0x41F,
+    MSVC_IS_VALUE_CLASS = 0x420,
+    MSVC_IS_SIMPLE_VALUE_CLASS = 0x421,
+    MSVC_IS_INTERFACE_CLASS = 0x422,
+    MSVC_IS_DELEGATE = 0x423,
+    MSVC_IS_FINAL = 0x424,
+    MSVC_IS_SEALED = 0x425,
+    MSVC_HAS_FINALIZER = 0x426,
+    MSVC_HAS_COPY = 0x427,
+    MSVC_HAS_ASSIGN = 0x428,
+    MSVC_HAS_USER_DESTRUCTOR = 0x429,
+    MSVC_CONFUSION = 0xFE0,
+    MSVC_CONFUSED_EXPAND = 0xFE1,
+    MSVC_CONFUSED_DEPENDENT_SIZEOF = 0xFE2,
+}
+
+#[c_enum(storage = "u16")]
 pub enum DyadicOperator {
     UNKNOWN = 0,
     PLUS = 1,
@@ -54,7 +189,7 @@ pub enum DyadicOperator {
     DESTRUCT = 0x2b,
     DESTRUCT_AT = 0x2c,
     CLEANUP = 0x2d,
-    QUALIFICATION = 0x2d,
+    QUALIFICATION = 0x2e,
     PROMOTE = 0x2f,
     DEMOTE = 0x30,
     COERCE = 0x31,
@@ -72,7 +207,7 @@ pub enum DyadicOperator {
     CLOSURE = 0x3d,
     ZERO_INITIALIZE = 0x3e,
     CLEAR_STORAGE = 0x3f,
-    SELECT = 0x400,
+    SELECT = 0x40,
     MSVC = 0x400,
     MSVC_TRY_CAST = 0x401,
     MSVC_CURRY = 0x402,
@@ -97,3 +232,36 @@ pub enum DyadicOperator {
     MSVC_INTRINSIC = 0x415,
     MSVC_SATURATED_ARITHMETIC = 0x416,
 }
+
+#[c_enum(storage = "u16")]
+pub enum TriadicOperator {
+    UNKNOWN = 0x00,
+    CHOICE = 0x01,
+    CONSTRUCT_AT = 0x02,
+    INITIALIZE = 0x03,
+    MSVC = 0x400,
+    MSVC_CONFUSION = 0xFE0,
+    MSVC_CONFUSED_PUSH_STATE = 0xFE1,
+}
+
+#[c_enum(storage = "u16")]
+pub enum StorageOperator {
+    UNKNOWN = 0x00,
+    ALLOCATE_SINGLE = 0x01,
+    ALLOCATE_ARRAY = 0x02,
+    DEALLOCATE_SINGLE = 0x03,
+    DEALLOCATE_ARRAY = 0x04,
+    MSVC = 0x7DE,
+}
+
+#[c_enum(storage = "u16")]
+pub enum VariadicOperator {
+    UNKNOWN = 0x00,
+    COLLECTION = 0x01,
+    SEQUENCE = 0x02,
+    MSVC = 0x400,
+    MSVC_HAS_TRIVIAL_CONSTRUCTOR = 0x401,
+    MSVC_IS_CONSTRUCTIBLE = 0x402,
+    MSVC_IS_NOTHROW_CONSTRUCTIBLE = 0x403,
+    MSVC_IS_TRIVIALLY_CONSTRUCTIBLE = 0x404,
+}
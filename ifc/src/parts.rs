@@ -2,6 +2,7 @@ use super::*;
 use anyhow::Result;
 use core::mem::size_of;
 use log::{debug, trace};
+use once_cell::sync::OnceCell;
 
 // Partition
 
@@ -26,12 +27,12 @@ macro_rules! part_info {
         ;
     )*) => {
 
-        impl Ifc {
+        impl<'data> Ifc<'data> {
             $(
                 pub fn $part_ident<'a>(&'a self) -> Part<'a, $part_record> {
                     Part::<$part_record> {
                         part_name: $part_name,
-                        entries: &self.parts.$part_ident,
+                        entries: self.part_entries($part_name, &self.parts.$part_ident),
                     }
                 }
             )*
@@ -45,17 +46,41 @@ macro_rules! part_info {
         }
 
         impl Parts {
-            pub fn load_part_data(&mut self, name: &str, part_data: &[u8], num_records: usize, record_size: usize) -> Result<()> {
+            /// Calls `f` for each non-empty partition, passing the partition name, the bytes of
+            /// its records, the number of records, and the size of each record.
+            pub fn for_each_part(&self, mut f: impl FnMut(&'static str, &[u8], usize, usize)) {
+                $(
+                    if !self.$part_ident.is_empty() {
+                        f(
+                            $part_name,
+                            self.$part_ident.as_bytes(),
+                            self.$part_ident.len(),
+                            size_of::<$part_record>(),
+                        );
+                    }
+                )*
+            }
+        }
+
+        /// The partitions of a loaded IFC.
+        #[derive(Default)]
+        pub(crate) struct LoadedParts {
+            $(
+                $part_ident: LoadedPart<$part_record>,
+            )*
+        }
+
+        impl LoadedParts {
+            /// Records the location of a partition within the file data. Returns `None` if we
+            /// don't recognize the partition, or `Some(borrowed)`.
+            pub(crate) fn add_part(&mut self, name: &str, data: &[u8], location: PartLocation, lazy: bool) -> Option<bool> {
                 match name {
                     $(
-                        $part_name => {
-                            self.$part_ident = convert_record_data::<$part_record>($part_name, part_data, num_records, record_size);
-                            Ok(())
-                        }
+                        $part_name => Some(self.$part_ident.add($part_name, data, location, lazy)),
                     )*
                     _ => {
                         // We don't recognize this partition by name. That's ok.
-                        Ok(())
+                        None
                     }
                 }
             }
@@ -63,6 +88,87 @@ macro_rules! part_info {
     }
 }
 
+/// Where a partition is found within the file data.
+#[derive(Clone)]
+pub(crate) struct PartLocation {
+    pub(crate) range: Range<usize>,
+    pub(crate) num_records: usize,
+    pub(crate) record_size: usize,
+}
+
+/// A partition of a loaded IFC. If the records in the file can be used directly, then they are
+/// read in place. Otherwise, they are converted to `T` and copied into `copied`. When loading
+/// lazily, this happens the first time the partition is accessed.
+pub(crate) struct LoadedPart<T> {
+    location: Option<PartLocation>,
+    borrowed: bool,
+    copied: OnceCell<Vec<T>>,
+}
+
+impl<T> Default for LoadedPart<T> {
+    fn default() -> Self {
+        Self {
+            location: None,
+            borrowed: false,
+            copied: OnceCell::new(),
+        }
+    }
+}
+
+impl<T: FromBytes + AsBytes> LoadedPart<T> {
+    fn add(&mut self, part_name: &str, data: &[u8], location: PartLocation, lazy: bool) -> bool {
+        let part_data = &data[location.range.clone()];
+        self.borrowed = can_borrow_record_data::<T>(part_data, location.record_size);
+        if !self.borrowed && !lazy {
+            self.copied = OnceCell::from(convert_record_data(
+                part_name,
+                part_data,
+                location.num_records,
+                location.record_size,
+            ));
+        }
+        self.location = Some(location);
+        self.borrowed
+    }
+}
+
+impl<'data> Ifc<'data> {
+    fn part_entries<'a, T: FromBytes + AsBytes>(
+        &'a self,
+        part_name: &str,
+        part: &'a LoadedPart<T>,
+    ) -> &'a [T] {
+        let location = match &part.location {
+            Some(location) => location,
+            None => return &[],
+        };
+        let part_data = &self.data[location.range.clone()];
+        if part.borrowed {
+            // The size and alignment were checked by can_borrow_record_data, when the file
+            // was loaded. The file data does not move after that.
+            LayoutVerified::<_, [T]>::new_slice(part_data)
+                .expect("borrowed partition should be valid")
+                .into_slice()
+        } else {
+            part.copied.get_or_init(|| {
+                convert_record_data(
+                    part_name,
+                    part_data,
+                    location.num_records,
+                    location.record_size,
+                )
+            })
+        }
+    }
+}
+
+/// Checks whether the records in `part_data` can be used directly as `&[T]`. This requires
+/// that the records in the file have exactly the size that we expect and that the partition
+/// is suitably aligned for `T`.
+fn can_borrow_record_data<T: FromBytes>(part_data: &[u8], record_size: usize) -> bool {
+    record_size == size_of::<T>() && LayoutVerified::<_, [T]>::new_slice(part_data).is_some()
+}
+
 fn convert_record_data<T>(
     part_name: &str,
     part_data: &[u8],
@@ -74,6 +180,9 @@ where
 {
     let expected_record_size = size_of::<T>();
     assert_eq!(part_data.len(), num_records * record_size);
+    if num_records == 0 {
+        return Vec::new();
+    }
 
     // There are three cases to consider:
     // * The records in the file are smaller than we expected.
@@ -137,6 +246,32 @@ part_info! {
     decl_enumerator, "decl.enumerator", DeclEnumerator;
     decl_var, "decl.variable", DeclVar;
     decl_bitfield, "decl.bitfield", DeclBitfield;
+    decl_parameter, "decl.parameter", DeclParameter;
+    decl_temploid, "decl.temploid", DeclTemploid;
+    decl_template, "decl.template", DeclTemplate;
+    decl_partial_specialization, "decl.partial-specialization", DeclPartialSpecialization;
+    decl_specialization, "decl.specialization", DeclSpecialization;
+    decl_concept, "decl.concept", DeclConcept;
+    decl_constructor, "decl.constructor", DeclConstructor;
+    decl_inherited_constructor, "decl.inherited-constructor", DeclInheritedConstructor;
+    decl_destructor, "decl.destructor", DeclDestructor;
+    decl_reference, "decl.reference", DeclReference;
+    decl_using_declaration, "decl.using-declaration", DeclUsingDeclaration;
+    decl_friend, "decl.friend", ExprIndex;
+    decl_expansion, "decl.expansion", DeclExpansion;
+    decl_deduction_guide, "decl.deduction-guide", DeclDeductionGuide;
+    decl_tuple, "decl.tuple", DeclTuple;
+    decl_intrinsic, "decl.intrinsic", DeclIntrinsic;
+    decl_property, "decl.property", DeclProperty;
+    decl_segment, "decl.segment", DeclSegment;
+    decl_syntax_tree, "decl.syntax-tree", SyntaxIndex;
+
+    // The record layouts of "decl.using-directive" and "decl.barren" are still "TBD" in the
+    // spec, and we have no way to check a guess against MSVC's output, so those partitions are
+    // not decoded. Indexes into them are still checked against the partition sizes by
+    // `validate`.
+
+    heap_decl, "heap.decl", DeclIndex;
 
     heap_type, "heap.type", TypeIndex;
 
@@ -156,14 +291,94 @@ part_info! {
     type_lvalue_reference, "type.lvalue-reference", TypeIndex;
     type_rvalue_reference, "type.rvalue-reference", TypeIndex;
     type_unaligned, "type.unaligned", TypeIndex;
+    type_tor, "type.tor", TorType;
+    type_syntactic, "type.syntactic", ExprIndex;
+    type_expansion, "type.expansion", ExpansionType;
+    type_pointer_to_member, "type.pointer-to-member", PointerToMemberType;
+    type_method, "type.nonstatic-member-function", MethodType;
+    type_typename, "type.typename", ExprIndex;
+    type_decltype, "type.decltype", SyntaxIndex;
+    type_placeholder, "type.placeholder", PlaceholderType;
+    type_forall, "type.forall", ForallType;
+    type_syntax_tree, "type.syntax-tree", SyntaxIndex;
 
     chart_unilevel, "chart.unilevel", ChartUnilevel;
+    chart_multilevel, "chart.multilevel", ChartMultilevel;
+    heap_chart, "heap.chart", ChartIndex;
 
+    name_operator, "name.operator", NameOperator;
+    name_conversion, "name.conversion", NameConversion;
+    name_literal, "name.literal", TextOffset;
+    name_template, "name.template", NameIndex;
+    name_specialization, "name.specialization", NameSpecialization;
     name_source_file, "name.source-file", NameSourceFile;
+    name_guide, "name.guide", DeclIndex;
     command_line, "command_line", TextOffset;
 
+    src_line, "src.line", FileAndLine;
+
+    module_imported, "module.imported", ModuleReference;
+    module_exported, "module.exported", ModuleReference;
+
+    heap_expr, "heap.expr", ExprIndex;
+    expr_empty, "expr.empty", ExprEmpty;
     expr_literal, "expr.literal", ExprLiteral;
+    expr_lambda, "expr.lambda", ExprLambda;
+    expr_type, "expr.type", ExprType;
+    expr_named_decl, "expr.decl", ExprNamedDecl;
+    expr_unresolved_id, "expr.unresolved", ExprUnresolvedId;
+    expr_template_id, "expr.template-id", ExprTemplateId;
+    expr_unqualified_id, "expr.unqualified-id", ExprUnqualifiedId;
+    expr_simple_identifier, "expr.simple-identifier", ExprSimpleIdentifier;
+    expr_pointer, "expr.pointer", SourceLocation;
+    expr_qualified_name, "expr.qualified-name", ExprQualifiedName;
+    expr_path, "expr.path", ExprPath;
+    expr_read, "expr.read", ExprRead;
+    expr_monad, "expr.monad", ExprMonad;
     expr_dyad, "expr.dyad", ExprDyad;
+    expr_triad, "expr.triad", ExprTriad;
+    expr_string, "expr.strings", ExprString;
+    expr_temporary, "expr.temporary", ExprTemporary;
+    expr_call, "expr.call", ExprCall;
+    expr_member_initializer, "expr.member-initializer", ExprMemberInitializer;
+    expr_member_access, "expr.member-access", ExprMemberAccess;
+    expr_inheritance_path, "expr.inheritance-path", ExprInheritancePath;
+    expr_initializer_list, "expr.initializer-list", ExprInitializerList;
+    expr_cast, "expr.cast", ExprCast;
+    expr_condition, "expr.condition", ExprCondition;
+    expr_expression_list, "expr.expression-list", ExprExpressionList;
+    expr_sizeof_type, "expr.sizeof-type", ExprSizeofType;
+    expr_alignof, "expr.alignof", ExprAlignof;
+    expr_typeid, "expr.typeid", ExprTypeid;
+    expr_destructor_call, "expr.destructor-call", ExprDestructorCall;
+    expr_syntax_tree, "expr.syntax-tree", SyntaxIndex;
+    expr_function_string, "expr.function-string", ExprFunctionString;
+    expr_compound_string, "expr.compound-string", ExprCompoundString;
+    expr_string_sequence, "expr.string-sequence", ExprStringSequence;
+    expr_initializer, "expr.initializer", ExprInitializer;
+    expr_requires, "expr.requires", ExprRequires;
+    expr_unary_fold, "expr.unary-fold", ExprUnaryFold;
+    expr_binary_fold, "expr.binary-fold", ExprBinaryFold;
+    expr_hierarchy_conversion, "expr.hierarchy-conversion", ExprHierarchyConversion;
+    expr_product_type_value, "expr.product-type-value", ExprProductTypeValue;
+    expr_sum_type_value, "expr.sum-type-value", ExprSumTypeValue;
+    expr_array_value, "expr.array-value", ExprArrayValue;
+    expr_dynamic_dispatch, "expr.dynamic-dispatch", ExprDynamicDispatch;
+    expr_virtual_function_conversion, "expr.virtual-function-conversion", ExprVirtualFunctionConversion;
+    expr_placeholder, "expr.placeholder", ExprPlaceholder;
+    expr_expansion, "expr.expansion", ExprExpansion;
+    expr_tuple, "expr.tuple", ExprTuple;
+    expr_nullptr, "expr.nullptr", ExprNullptr;
+    expr_this, "expr.this", ExprThis;
+    expr_template_reference, "expr.template-reference", ExprTemplateReference;
+    expr_push_state, "expr.push-state", ExprPushState;
+    expr_type_trait, "expr.type-trait", ExprTypeTraitIntrinsic;
+    expr_designated_init, "expr.designated-init", ExprDesignatedInitializer;
+    expr_packed_template_arguments, "expr.packed-template-arguments", ExprPackedTemplateArguments;
+    expr_tokens, "expr.tokens", ExprTokens;
+    expr_assign_initializer, "expr.assign-initializer", ExprAssignInitializer;
+
+    const_str, "const.str", StringLiteral;
 
     const_i64, "const.i64", u64;
     const_f64, "const.f64", ConstF64;
@@ -186,6 +401,17 @@ part_info! {
     pp_junk, "pp.junk", FormJunk;
     pp_pragma, "pp.pragma", FormPragma;
 
-    // Attributes using AttrSort::Basic
     attr_basic, "attr.basic", Word;
+    attr_scoped, "attr.scoped", AttrScoped;
+    attr_labeled, "attr.labeled", AttrLabeled;
+    attr_called, "attr.called", AttrCalled;
+    attr_expanded, "attr.expanded", AttrIndex;
+    attr_factored, "attr.factored", AttrFactored;
+    attr_elaborated, "attr.elaborated", ExprIndex;
+    attr_tuple, "attr.tuple", AttrTuple;
+    heap_attr, "heap.attr", AttrIndex;
+
+    trait_attribute, "trait.attribute", AttributeTrait;
+    msvc_trait_decl_attrs, ".msvc.trait.decl-attrs", AttributeTrait;
+    trait_deprecated, "trait.deprecated", DeprecatedTrait;
 }
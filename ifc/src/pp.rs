@@ -74,7 +74,7 @@ impl ArityAndVariadic {
     }
 
     pub fn is_variadic(&self) -> bool {
-        (self.0 & 0x8000_000) != 0
+        (self.0 & 0x8000_0000) != 0
     }
 }
 
@@ -210,3 +210,75 @@ pub struct FormPragma {
     pub locus: SourceLocation,
     pub operand: FormIndex,
 }
+
+impl<'data> Ifc<'data> {
+    /// Flattens a preprocessing form, such as the body of a macro, into the spellings of its
+    /// tokens.
+    pub fn form_tokens(&self, form: FormIndex) -> Result<Vec<String>> {
+        let mut tokens = Vec::new();
+        self.write_form_tokens(form, &mut tokens, 0)?;
+        Ok(tokens)
+    }
+
+    fn write_form_tokens(
+        &self,
+        form: FormIndex,
+        tokens: &mut Vec<String>,
+        depth: u32,
+    ) -> Result<()> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("form {:?} is nested too deeply (is it cyclic?)", form);
+        }
+
+        let i = form.index();
+        let spelling = match form.tag() {
+            FormSort::IDENTIFIER => self.pp_ident().entry(i)?.spelling,
+            FormSort::NUMBER => self.pp_num().entry(i)?.spelling,
+            FormSort::CHARACTER => self.pp_char().entry(i)?.spelling,
+            FormSort::STRING => self.pp_string().entry(i)?.spelling,
+            FormSort::OPERATOR => self.pp_op().entry(i)?.spelling,
+            FormSort::KEYWORD => self.pp_keyword().entry(i)?.spelling,
+            FormSort::PARAMETER => self.pp_param().entry(i)?.spelling,
+            FormSort::JUNK => self.pp_junk().entry(i)?.spelling,
+            FormSort::STRINGIZE => {
+                tokens.push("#".to_string());
+                let operand = self.pp_stringize().entry(i)?.operand;
+                return self.write_form_tokens(operand, tokens, depth + 1);
+            }
+            FormSort::CATENATE => {
+                let catenate = self.pp_catenate().entry(i)?;
+                self.write_form_tokens(catenate.first, tokens, depth + 1)?;
+                tokens.push("##".to_string());
+                return self.write_form_tokens(catenate.second, tokens, depth + 1);
+            }
+            FormSort::PARENTHESIZED => {
+                tokens.push("(".to_string());
+                let operand = self.pp_paren().entry(i)?.operand;
+                self.write_form_tokens(operand, tokens, depth + 1)?;
+                tokens.push(")".to_string());
+                return Ok(());
+            }
+            FormSort::HEADER => {
+                let spelling = self.pp_header().entry(i)?.spelling;
+                return self.write_form_tokens(spelling, tokens, depth + 1);
+            }
+            FormSort::PRAGMA => {
+                tokens.push("_Pragma".to_string());
+                let operand = self.pp_pragma().entry(i)?.operand;
+                return self.write_form_tokens(operand, tokens, depth + 1);
+            }
+            FormSort::TUPLE => {
+                let tuple = self.pp_tuple().entry(i)?;
+                for j in tuple.start..tuple.start.saturating_add(tuple.cardinality) {
+                    let element = *self.heap_form().entry(j)?;
+                    self.write_form_tokens(element, tokens, depth + 1)?;
+                }
+                return Ok(());
+            }
+            // Whitespace has no spelling, and other sorts are not defined by the spec.
+            _ => return Ok(()),
+        };
+        tokens.push(self.get_string(spelling)?.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! An index of declarations by name
+
+use super::*;
+use std::collections::HashSet;
+
+/// Maps names to the declarations that they refer to, as built by `Ifc::build_index`.
+///
+/// Qualified names are written the way `Ifc::qualified_name` displays them, e.g.
+/// `cookie::wookie::POINT::x`. A name can refer to more than one declaration, such as an
+/// overloaded function, so lookups return a slice.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolIndex {
+    qualified: HashMap<String, Vec<DeclIndex>>,
+    unqualified: HashMap<String, Vec<DeclIndex>>,
+}
+
+impl SymbolIndex {
+    /// Finds the declarations that have a qualified name, such as `ns::Class::member`.
+    /// Names at global scope have no `::` prefix.
+    pub fn lookup(&self, qualified_name: &str) -> &[DeclIndex] {
+        self.qualified
+            .get(qualified_name)
+            .map(|decls| decls.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Finds the declarations that have an unqualified name, in any scope.
+    pub fn lookup_unqualified(&self, name: &str) -> &[DeclIndex] {
+        self.unqualified
+            .get(name)
+            .map(|decls| decls.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Iterates the qualified names in the index, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[DeclIndex])> {
+        self.qualified
+            .iter()
+            .map(|(name, decls)| (name.as_str(), decls.as_slice()))
+    }
+
+    /// The number of distinct qualified names.
+    pub fn len(&self) -> usize {
+        self.qualified.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.qualified.is_empty()
+    }
+
+    fn insert(&mut self, qualified_name: String, name: &str, decl: DeclIndex) {
+        self.qualified.entry(qualified_name).or_default().push(decl);
+        self.unqualified
+            .entry(name.to_string())
+            .or_default()
+            .push(decl);
+    }
+
+    /// Adds another qualified name for a declaration that is already in the index.
+    fn insert_alias(&mut self, qualified_name: String, decl: DeclIndex) {
+        self.qualified.entry(qualified_name).or_default().push(decl);
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// Walks every scope that is reachable from the global scope (namespaces, classes and
+    /// enumerations) and indexes each named declaration by its qualified name and by its
+    /// unqualified name.
+    ///
+    /// The enumerators of unscoped enumerations are also indexed in the enclosing scope, since
+    /// C++ allows naming them either way.
+    pub fn build_index(&self) -> Result<SymbolIndex> {
+        let mut index = SymbolIndex::default();
+        let mut visited = HashSet::new();
+        self.index_scope(&mut index, &mut visited, self.global_scope(), "", 0)?;
+        Ok(index)
+    }
+
+    fn index_scope(
+        &self,
+        index: &mut SymbolIndex,
+        visited: &mut HashSet<ScopeIndex>,
+        scope: ScopeIndex,
+        prefix: &str,
+        depth: u32,
+    ) -> Result<()> {
+        // Incomplete classes have no scope.
+        if scope == 0 || !visited.insert(scope) {
+            return Ok(());
+        }
+        if depth > MAX_TYPE_DEPTH {
+            bail!("scope {} is nested too deeply", scope);
+        }
+
+        let qualify = |name: &str| {
+            if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}::{}", prefix, name)
+            }
+        };
+
+        for member in self.iter_scope(scope)? {
+            if self.decl_name(member)?.is_none() {
+                continue;
+            }
+            let name = self.qualified_name_component(member)?;
+            let qualified = qualify(&name);
+
+            match member.tag() {
+                DeclSort::SCOPE => {
+                    let nested = self.decl_scope().entry(member.index())?;
+                    self.index_scope(index, visited, nested.initializer, &qualified, depth + 1)?;
+                }
+                DeclSort::ENUMERATION => {
+                    let en = self.decl_enum().entry(member.index())?;
+                    let is_unscoped = en.ty.tag() == TypeSort::FUNDAMENTAL
+                        && self.type_fundamental().entry(en.ty.index())?.basis == TypeBasis::ENUM;
+                    for i in en.initializer.to_range() {
+                        let enumerator = DeclIndex::new(DeclSort::ENUMERATOR, i);
+                        let enumerator_name =
+                            self.get_string(self.decl_enumerator().entry(i)?.name)?;
+                        index.insert(
+                            format!("{}::{}", qualified, enumerator_name),
+                            enumerator_name,
+                            enumerator,
+                        );
+                        if is_unscoped {
+                            index.insert_alias(qualify(enumerator_name), enumerator);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            index.insert(qualified, &name, member);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! Fixtures shared by the unit tests and the fuzz targets
+//!
+//! This is not part of the supported API. It is only built for the unit tests, and for the `fuzz`
+//! crate through the `testing` feature.
+
+use super::*;
+use std::collections::HashSet;
+
+/// Builds an IFC that is equivalent to:
+///
+/// ```text
+/// struct POINT {
+///     int x;
+///     int y;
+///     unsigned int flags : 3;
+/// };
+/// ```
+pub fn point_builder() -> IfcBuilder {
+    let mut b = IfcBuilder::new();
+    b.set_src_path("point.h");
+
+    let int_ty = b.add_fundamental_type(TypeBasis::INT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let uint_ty =
+        b.add_fundamental_type(TypeBasis::INT, TypePrecision::DEFAULT, TypeSign::UNSIGNED);
+    let struct_ty =
+        b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+
+    let point_decl = DeclIndex::new(DeclSort::SCOPE, 0);
+
+    let mut fields = Vec::new();
+    for name in ["x", "y"] {
+        let name = b.add_string(name);
+        let i = IfcBuilder::push_entry(
+            &mut b.parts.decl_field,
+            DeclField {
+                name,
+                ty: int_ty,
+                home_scope: point_decl,
+                ..DeclField::new_zeroed()
+            },
+        );
+        fields.push(DeclIndex::new(DeclSort::FIELD, i));
+    }
+
+    let width = b.add_integer_literal(uint_ty, 3);
+    let flags_name = b.add_string("flags");
+    let i = IfcBuilder::push_entry(
+        &mut b.parts.decl_bitfield,
+        DeclBitfield {
+            name: flags_name,
+            ty: uint_ty,
+            home_scope: point_decl,
+            width,
+            ..DeclBitfield::new_zeroed()
+        },
+    );
+    fields.push(DeclIndex::new(DeclSort::BITFIELD, i));
+
+    let point_scope = b.add_scope(&fields);
+    let point_name = b.add_identifier("POINT");
+    IfcBuilder::push_entry(
+        &mut b.parts.decl_scope,
+        DeclScope {
+            name: point_name,
+            ty: struct_ty,
+            initializer: point_scope,
+            ..DeclScope::new_zeroed()
+        },
+    );
+
+    b.file_header.global_scope = b.add_scope(&[point_decl]);
+    b
+}
+
+/// Builds a small IFC to start fuzzing from. It is `point_builder`, plus some types that the
+/// reader has to follow:
+///
+/// ```text
+/// struct NESTED {
+///     const int* p;
+///     int (*f)(int, int);
+/// };
+/// ```
+pub fn seed() -> Vec<u8> {
+    let mut b = point_builder();
+
+    let int_ty = b.add_fundamental_type(TypeBasis::INT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let struct_ty =
+        b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+
+    let const_int = IfcBuilder::push_entry(
+        &mut b.parts.type_qualified,
+        QualifiedType {
+            unqualified_type: int_ty,
+            qualifiers: Qualifiers::CONST,
+            padding: [0; 3],
+        },
+    );
+    let const_int_ptr = IfcBuilder::push_entry(
+        &mut b.parts.type_pointer,
+        TypeIndex::new(TypeSort::QUALIFIED, const_int),
+    );
+
+    let args_start = IfcBuilder::push_entry(&mut b.parts.heap_type, int_ty);
+    IfcBuilder::push_entry(&mut b.parts.heap_type, int_ty);
+    let args = IfcBuilder::push_entry(
+        &mut b.parts.type_tuple,
+        TupleType {
+            start: args_start,
+            cardinality: 2,
+        },
+    );
+    let func = IfcBuilder::push_entry(
+        &mut b.parts.type_function,
+        FunctionType {
+            target: int_ty,
+            source: TypeIndex::new(TypeSort::TUPLE, args),
+            ..FunctionType::new_zeroed()
+        },
+    );
+    let func_ptr = IfcBuilder::push_entry(
+        &mut b.parts.type_pointer,
+        TypeIndex::new(TypeSort::FUNCTION, func),
+    );
+
+    let nested_decl = DeclIndex::new(DeclSort::SCOPE, b.parts.decl_scope.len() as Index);
+    let mut fields = Vec::new();
+    for (name, ty) in [("p", const_int_ptr), ("f", func_ptr)] {
+        let name = b.add_string(name);
+        let i = IfcBuilder::push_entry(
+            &mut b.parts.decl_field,
+            DeclField {
+                name,
+                ty: TypeIndex::new(TypeSort::POINTER, ty),
+                home_scope: nested_decl,
+                ..DeclField::new_zeroed()
+            },
+        );
+        fields.push(DeclIndex::new(DeclSort::FIELD, i));
+    }
+
+    let nested_scope = b.add_scope(&fields);
+    let nested_name = b.add_identifier("NESTED");
+    IfcBuilder::push_entry(
+        &mut b.parts.decl_scope,
+        DeclScope {
+            name: nested_name,
+            ty: struct_ty,
+            initializer: nested_scope,
+            ..DeclScope::new_zeroed()
+        },
+    );
+
+    let point_decl = DeclIndex::new(DeclSort::SCOPE, 0);
+    b.file_header.global_scope = b.add_scope(&[point_decl, nested_decl]);
+    b.to_bytes()
+}
+
+/// Loads `data` (eagerly and lazily) and walks it with `walk`.
+pub fn load_and_walk(data: &[u8]) {
+    for lazy in [false, true] {
+        let options = LoadOptions {
+            lazy,
+            ..LoadOptions::default()
+        };
+        if let Ok(ifc) = Ifc::from_bytes_with_options(data, &options) {
+            walk(&ifc);
+        }
+    }
+}
+
+struct NopVisitor;
+
+impl DeclVisitor for NopVisitor {}
+
+/// Walks everything that is reachable from the global scope, the way a code generator would.
+/// Errors are fine, panics are not.
+pub fn walk(ifc: &Ifc) {
+    let _ = ifc.validate();
+    let _ = ifc.build_index();
+    let _ = ifc.module_info();
+    let _ = ifc.visit(&mut NopVisitor);
+    let _ = diff(ifc, ifc);
+    let _ = ifc.get_string(ifc.file_header().src_path);
+
+    let mut visited = HashSet::new();
+    let mut scopes = vec![ifc.global_scope()];
+    while let Some(scope) = scopes.pop() {
+        if !visited.insert(scope) {
+            continue;
+        }
+        let members = match ifc.iter_scope(scope) {
+            Ok(members) => members,
+            Err(_) => continue,
+        };
+        for member in members {
+            for attr in ifc.decl_attributes(member) {
+                let _ = ifc.get_attr_string(attr);
+            }
+            let _ = ifc.deprecation(member);
+            let _ = Workspace::new().resolve_foreign_decl(ifc, member);
+            match member.tag() {
+                DeclSort::SCOPE => {
+                    if let Ok(nested) = ifc.decl_scope().entry(member.index()) {
+                        let _ = ifc.get_name_string(nested.name);
+                        let _ = ifc.get_type_string(nested.ty);
+                        let _ = ifc.resolve_locus(&nested.locus);
+                        let _ = ifc.class_layout(member);
+                        scopes.push(nested.initializer);
+                    }
+                }
+                DeclSort::FIELD => {
+                    if let Ok(field) = ifc.decl_field().entry(member.index()) {
+                        let _ = ifc.get_string(field.name);
+                        let _ = ifc.qualified_name(member);
+                        walk_type(ifc, field.ty);
+                    }
+                }
+                DeclSort::FUNCTION => {
+                    if let Ok(func) = ifc.decl_function().entry(member.index()) {
+                        let _ = ifc.function_parameters(func);
+                    }
+                }
+                DeclSort::BITFIELD => {
+                    if let Ok(bitfield) = ifc.decl_bitfield().entry(member.index()) {
+                        walk_type(ifc, bitfield.ty);
+                        let _ = ifc.get_literal_expr_u32(bitfield.width);
+                        let _ = ifc.eval_const(bitfield.width);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn walk_type(ifc: &Ifc, ty: TypeIndex) {
+    let _ = ifc.get_type_string(ty);
+    let _ = ifc.type_layout(ty);
+    let _ = ifc.type_key(ty, &mut TypeKeyInterner::new());
+    let _ = ifc.remove_qualifiers(ty);
+    let _ = ifc.is_const_qualified(ty);
+    let _ = ifc.is_void(ty);
+    if let Ok(tuple) = ifc.iter_type_tuple(ty) {
+        for element in tuple {
+            let _ = ifc.get_type_string(element);
+        }
+    }
+}
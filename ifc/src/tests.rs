@@ -0,0 +1,2421 @@
+use super::testing::*;
+use super::*;
+use std::sync::Arc;
+
+fn load_point() -> Ifc<'static> {
+    Ifc::load(point_builder().to_bytes()).expect("expected builder output to load")
+}
+
+#[test]
+fn round_trip_header() {
+    let ifc = load_point();
+    assert_eq!(
+        ifc.get_string(ifc.file_header().src_path).unwrap(),
+        "point.h"
+    );
+    assert_eq!(ifc.global_scope(), 2);
+    assert!(ifc.parts().contains_key("decl.field"));
+    assert!(ifc.parts().contains_key("scope.desc"));
+    assert!(!ifc.parts().contains_key("decl.alias"));
+}
+
+#[test]
+fn round_trip_struct() {
+    let ifc = load_point();
+
+    let members: Vec<DeclIndex> = ifc.iter_scope(ifc.global_scope()).unwrap().collect();
+    assert_eq!(members, [DeclIndex::new(DeclSort::SCOPE, 0)]);
+
+    let point = ifc.decl_scope().entry(0).unwrap();
+    assert_eq!(ifc.get_name_string(point.name).unwrap(), "POINT");
+
+    let mut fields = Vec::new();
+    for member in ifc.iter_scope(point.initializer).unwrap() {
+        match member.tag() {
+            DeclSort::FIELD => {
+                let field = ifc.decl_field().entry(member.index()).unwrap();
+                fields.push(format!(
+                    "{} {}",
+                    ifc.get_type_string(field.ty).unwrap(),
+                    ifc.get_string(field.name).unwrap()
+                ));
+            }
+            DeclSort::BITFIELD => {
+                let bitfield = ifc.decl_bitfield().entry(member.index()).unwrap();
+                fields.push(format!(
+                    "{} {} : {}",
+                    ifc.get_type_string(bitfield.ty).unwrap(),
+                    ifc.get_string(bitfield.name).unwrap(),
+                    ifc.get_literal_expr_u32(bitfield.width).unwrap()
+                ));
+            }
+            _ => panic!("unexpected member: {:?}", member),
+        }
+    }
+    assert_eq!(fields, ["int x", "int y", "unsigned int flags : 3"]);
+}
+
+#[test]
+fn large_integer_literal() {
+    let mut b = IfcBuilder::new();
+    let ty = b.add_fundamental_type(TypeBasis::INT, TypePrecision::BIT64, TypeSign::UNSIGNED);
+    let small = b.add_integer_literal(ty, 42);
+    let large = b.add_integer_literal(ty, 0x1_0000_0000);
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+
+    assert_eq!(ifc.get_literal_expr_u32(small).unwrap(), 42);
+    let literal = ifc.expr_literal().entry(large.index()).unwrap();
+    assert_eq!(literal.value.tag(), LiteralSort::INTEGER);
+    assert_eq!(
+        *ifc.const_i64().entry(literal.value.index()).unwrap(),
+        0x1_0000_0000
+    );
+}
+
+#[test]
+fn special_members_and_templates() {
+    let mut b = point_builder();
+    let point_decl = DeclIndex::new(DeclSort::SCOPE, 0);
+
+    let name = b.add_string("POINT");
+    let ctor = IfcBuilder::push_entry(
+        &mut b.parts.decl_constructor,
+        DeclConstructor {
+            name,
+            home_scope: point_decl,
+            traits: FunctionTraits::CONSTEXPR,
+            access: Access::PUBLIC,
+            ..DeclConstructor::new_zeroed()
+        },
+    );
+    let name = b.add_string("~POINT");
+    let dtor = IfcBuilder::push_entry(
+        &mut b.parts.decl_destructor,
+        DeclDestructor {
+            name,
+            home_scope: point_decl,
+            access: Access::PUBLIC,
+            ..DeclDestructor::new_zeroed()
+        },
+    );
+    let name = b.add_identifier("make_point");
+    let template = IfcBuilder::push_entry(
+        &mut b.parts.decl_template,
+        DeclTemplate {
+            name,
+            entity: ParameterizedEntity {
+                decl: point_decl,
+                ..ParameterizedEntity::new_zeroed()
+            },
+            ..DeclTemplate::new_zeroed()
+        },
+    );
+    let start = b.parts.heap_decl.len() as Index;
+    b.parts.heap_decl.extend_from_slice(&[
+        DeclIndex::new(DeclSort::CONSTRUCTOR, ctor),
+        DeclIndex::new(DeclSort::DESTRUCTOR, dtor),
+    ]);
+    IfcBuilder::push_entry(
+        &mut b.parts.decl_tuple,
+        DeclTuple {
+            start,
+            cardinality: 2,
+        },
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+
+    let ctor = ifc.decl_constructor().entry(ctor).unwrap();
+    assert_eq!(ifc.get_string(ctor.name).unwrap(), "POINT");
+    assert_eq!(ctor.traits, FunctionTraits::CONSTEXPR);
+    assert_eq!(ctor.access, Access::PUBLIC);
+    let dtor = ifc.decl_destructor().entry(dtor).unwrap();
+    assert_eq!(ifc.get_string(dtor.name).unwrap(), "~POINT");
+    let template = ifc.decl_template().entry(template).unwrap();
+    assert_eq!(ifc.get_name_string(template.name).unwrap(), "make_point");
+    assert_eq!(template.entity.decl, point_decl);
+
+    let tuple = ifc.decl_tuple().entry(0).unwrap();
+    let members: Vec<DeclSort> = (tuple.start..tuple.start + tuple.cardinality)
+        .map(|i| ifc.heap_decl().entry(i).unwrap().tag())
+        .collect();
+    assert_eq!(members, [DeclSort::CONSTRUCTOR, DeclSort::DESTRUCTOR]);
+
+    // A tuple that runs off the end of `heap.decl` is reported.
+    let mut b = point_builder();
+    b.parts.heap_decl.push(point_decl);
+    b.parts.decl_tuple.push(DeclTuple {
+        start: 0,
+        cardinality: 2,
+    });
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let diags: Vec<String> = ifc.validate().iter().map(|d| d.to_string()).collect();
+    assert_eq!(diags.len(), 1);
+    assert!(diags[0].starts_with("decl.tuple[0].start"), "{:?}", diags);
+}
+
+#[test]
+fn member_types() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_designated,
+            DeclIndex::new(DeclSort::SCOPE, 0),
+        ),
+    );
+
+    let method_ty = TypeIndex::new(
+        TypeSort::METHOD,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_method,
+            MethodType {
+                target: int_ty,
+                source: int_ty,
+                scope: point_ty,
+                convention: CallingConvention::This,
+                ..MethodType::new_zeroed()
+            },
+        ),
+    );
+    let const_method_ty = TypeIndex::new(
+        TypeSort::METHOD,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_method,
+            MethodType {
+                target: int_ty,
+                source: int_ty,
+                scope: point_ty,
+                convention: CallingConvention::This,
+                traits: FunctionTypeTraits::CONST_TRAIT,
+                ..MethodType::new_zeroed()
+            },
+        ),
+    );
+    let ptm_ty = TypeIndex::new(
+        TypeSort::POINTER_TO_MEMBER,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_pointer_to_member,
+            PointerToMemberType {
+                scope: point_ty,
+                member: int_ty,
+            },
+        ),
+    );
+    let method_ptr_ty = TypeIndex::new(
+        TypeSort::POINTER_TO_MEMBER,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_pointer_to_member,
+            PointerToMemberType {
+                scope: point_ty,
+                member: const_method_ty,
+            },
+        ),
+    );
+    let auto_ty = TypeIndex::new(
+        TypeSort::PLACEHOLDER,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_placeholder,
+            PlaceholderType {
+                basis: TypeBasis::AUTO,
+                ..PlaceholderType::new_zeroed()
+            },
+        ),
+    );
+    let deduced_ty = TypeIndex::new(
+        TypeSort::PLACEHOLDER,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_placeholder,
+            PlaceholderType {
+                basis: TypeBasis::AUTO,
+                elaboration: int_ty,
+                ..PlaceholderType::new_zeroed()
+            },
+        ),
+    );
+    let pack_ty = TypeIndex::new(
+        TypeSort::EXPANSION,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_expansion,
+            ExpansionType {
+                pack: int_ty,
+                ..ExpansionType::new_zeroed()
+            },
+        ),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let type_string = |ty| ifc.get_type_string(ty).unwrap();
+    assert_eq!(type_string(point_ty), "POINT");
+    assert_eq!(type_string(method_ty), "int (POINT::*)(int)");
+    assert_eq!(type_string(const_method_ty), "int (POINT::*)(int) const");
+    assert_eq!(type_string(method_ptr_ty), "int (POINT::*)(int) const");
+    let printer = CxxTypePrinter::new(&ifc, CxxPrintOptions::default());
+    assert_eq!(
+        printer.declaration(const_method_ty, "POINT::get").unwrap(),
+        "int POINT::get(int) const"
+    );
+    assert_eq!(type_string(ptm_ty), "int POINT::*");
+    assert_eq!(type_string(auto_ty), "auto");
+    assert_eq!(type_string(deduced_ty), "int");
+    assert_eq!(type_string(pack_ty), "int...");
+
+    let mut b = point_builder();
+    b.parts.type_pointer_to_member.push(PointerToMemberType {
+        scope: TypeIndex::new(TypeSort::DESIGNATED, 5),
+        member: TypeIndex::new(TypeSort::FUNDAMENTAL, 0),
+    });
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let diags: Vec<String> = ifc.validate().iter().map(|d| d.to_string()).collect();
+    assert_eq!(diags.len(), 1);
+    assert!(
+        diags[0].starts_with("type.pointer-to-member[0].scope"),
+        "{:?}",
+        diags
+    );
+}
+
+#[test]
+fn cxx_declarators() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let char_ty = b.add_fundamental_type(TypeBasis::CHAR, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let u64_ty = b.add_fundamental_type(TypeBasis::INT, TypePrecision::BIT64, TypeSign::UNSIGNED);
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_designated,
+            DeclIndex::new(DeclSort::SCOPE, 0),
+        ),
+    );
+    let pointer = |b: &mut IfcBuilder, ty| {
+        TypeIndex::new(
+            TypeSort::POINTER,
+            IfcBuilder::push_entry(&mut b.parts.type_pointer, ty),
+        )
+    };
+    let constant = |b: &mut IfcBuilder, ty| {
+        TypeIndex::new(
+            TypeSort::QUALIFIED,
+            IfcBuilder::push_entry(
+                &mut b.parts.type_qualified,
+                QualifiedType {
+                    unqualified_type: ty,
+                    qualifiers: Qualifiers::CONST,
+                    padding: [0; 3],
+                },
+            ),
+        )
+    };
+    let array = |b: &mut IfcBuilder, element, extent| {
+        TypeIndex::new(
+            TypeSort::ARRAY,
+            IfcBuilder::push_entry(&mut b.parts.type_array, TypeArray { element, extent }),
+        )
+    };
+    let function = |b: &mut IfcBuilder, target, source, convention| {
+        TypeIndex::new(
+            TypeSort::FUNCTION,
+            IfcBuilder::push_entry(
+                &mut b.parts.type_function,
+                FunctionType {
+                    target,
+                    source,
+                    convention,
+                    ..FunctionType::new_zeroed()
+                },
+            ),
+        )
+    };
+
+    let four = b.add_integer_literal(int_ty, 4);
+    let int_ptr = pointer(&mut b, int_ty);
+    let int_ptr_array = array(&mut b, int_ptr, four);
+    let int_array = array(&mut b, int_ty, four);
+    let int_array_ptr = pointer(&mut b, int_array);
+    let unbounded = array(&mut b, char_ty, ExprIndex(0));
+    let const_char = constant(&mut b, char_ty);
+    let const_char_ptr = pointer(&mut b, const_char);
+    let char_ptr = pointer(&mut b, char_ty);
+    let char_const_ptr = constant(&mut b, char_ptr);
+    let const_point = constant(&mut b, point_ty);
+    let const_point_ref = TypeIndex::new(
+        TypeSort::LVALUE_REFERENCE,
+        IfcBuilder::push_entry(&mut b.parts.type_lvalue_reference, const_point),
+    );
+    let array_ref = TypeIndex::new(
+        TypeSort::LVALUE_REFERENCE,
+        IfcBuilder::push_entry(&mut b.parts.type_lvalue_reference, int_array),
+    );
+    let inner = function(&mut b, int_ty, int_ty, CallingConvention::Std);
+    let inner_ptr = pointer(&mut b, inner);
+    let outer = function(&mut b, inner_ptr, char_ty, CallingConvention::Cdecl);
+    let outer_ptr = pointer(&mut b, outer);
+
+    // typename T::type
+    let identifier = |b: &mut IfcBuilder, s| {
+        let name = b.add_identifier(s);
+        ExprIndex::new(
+            ExprSort::SIMPLE_IDENTIFIER,
+            IfcBuilder::push_entry(
+                &mut b.parts.expr_simple_identifier,
+                ExprSimpleIdentifier {
+                    name,
+                    ..ExprSimpleIdentifier::new_zeroed()
+                },
+            ),
+        )
+    };
+    let scope = identifier(&mut b, "T");
+    let member = identifier(&mut b, "type");
+    let path = IfcBuilder::push_entry(
+        &mut b.parts.expr_path,
+        ExprPath {
+            scope,
+            member,
+            ..ExprPath::new_zeroed()
+        },
+    );
+    let typename = TypeIndex::new(
+        TypeSort::TYPENAME,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_typename,
+            ExprIndex::new(ExprSort::PATH, path),
+        ),
+    );
+    let base = TypeIndex::new(
+        TypeSort::BASE,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_base,
+            TypeBase {
+                ty: point_ty,
+                access: Access::PUBLIC,
+                specifiers: BaseTypeSpecifiers::SHARED,
+                __padding: [0; 2],
+            },
+        ),
+    );
+    let decltype = TypeIndex::new(
+        TypeSort::DECLTYPE,
+        IfcBuilder::push_entry(&mut b.parts.type_decltype, 0),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let printer = CxxTypePrinter::new(&ifc, CxxPrintOptions::default());
+    let declaration = |ty, name| printer.declaration(ty, name).unwrap();
+
+    assert_eq!(declaration(int_ptr, "p"), "int *p");
+    assert_eq!(declaration(int_ptr_array, "a"), "int *a[4]");
+    assert_eq!(declaration(int_array_ptr, "p"), "int (*p)[4]");
+    assert_eq!(declaration(unbounded, "s"), "char s[]");
+    assert_eq!(declaration(const_char_ptr, "s"), "const char *s");
+    assert_eq!(declaration(char_const_ptr, "s"), "char *const s");
+    assert_eq!(declaration(const_point_ref, "pt"), "const POINT &pt");
+    assert_eq!(declaration(array_ref, "r"), "int (&r)[4]");
+    assert_eq!(declaration(inner_ptr, "f"), "int (*f)(int)");
+    assert_eq!(declaration(outer_ptr, "f"), "int (*(*f)(char))(int)");
+    assert_eq!(declaration(u64_ty, "n"), "unsigned long long n");
+    assert_eq!(declaration(typename, "t"), "typename T::type t");
+    assert_eq!(declaration(decltype, "d"), "decltype(...) d");
+    assert_eq!(printer.type_string(base).unwrap(), "public virtual POINT");
+
+    assert_eq!(printer.type_string(int_array_ptr).unwrap(), "int (*)[4]");
+    assert_eq!(printer.type_string(char_const_ptr).unwrap(), "char *const");
+    assert_eq!(ifc.get_type_string(inner_ptr).unwrap(), "int (*)(int)");
+
+    let msvc = CxxTypePrinter::new(
+        &ifc,
+        CxxPrintOptions {
+            msvc_spellings: true,
+            calling_conventions: true,
+            ..CxxPrintOptions::default()
+        },
+    );
+    assert_eq!(msvc.declaration(u64_ty, "n").unwrap(), "unsigned __int64 n");
+    assert_eq!(
+        msvc.type_string(inner_ptr).unwrap(),
+        "int (__stdcall *)(int)"
+    );
+    assert_eq!(
+        msvc.declaration(inner, "f").unwrap(),
+        "int __stdcall f(int)"
+    );
+    assert_eq!(
+        msvc.declaration(outer_ptr, "f").unwrap(),
+        "int (__stdcall *(__cdecl *f)(char))(int)"
+    );
+}
+
+#[test]
+fn layout() {
+    let mut b = point_builder();
+    b.file_header.arch = Architecture::X64;
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let uint_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 1);
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_designated,
+            DeclIndex::new(DeclSort::SCOPE, 0),
+        ),
+    );
+    let char_ty = b.add_fundamental_type(TypeBasis::CHAR, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let short_ty = b.add_fundamental_type(TypeBasis::INT, TypePrecision::SHORT, TypeSign::PLAIN);
+    let double_ty =
+        b.add_fundamental_type(TypeBasis::DOUBLE, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let struct_ty =
+        b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let union_ty =
+        b.add_fundamental_type(TypeBasis::UNION, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let int_ptr = TypeIndex::new(
+        TypeSort::POINTER,
+        IfcBuilder::push_entry(&mut b.parts.type_pointer, int_ty),
+    );
+    let three = b.add_integer_literal(int_ty, 3);
+    let char_array = TypeIndex::new(
+        TypeSort::ARRAY,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_array,
+            TypeArray {
+                element: char_ty,
+                extent: three,
+            },
+        ),
+    );
+
+    // Adds a struct or union with the given members, and returns its declaration.
+    let add_class = |b: &mut IfcBuilder, ty, base, members: &[(TypeIndex, u64)], pack_size| {
+        let decl = DeclIndex::new(DeclSort::SCOPE, b.parts.decl_scope.len() as u32);
+        let mut decls = Vec::new();
+        for &(ty, width) in members {
+            decls.push(if width == 0 {
+                let i = IfcBuilder::push_entry(
+                    &mut b.parts.decl_field,
+                    DeclField {
+                        ty,
+                        home_scope: decl,
+                        ..DeclField::new_zeroed()
+                    },
+                );
+                DeclIndex::new(DeclSort::FIELD, i)
+            } else {
+                let width = b.add_integer_literal(int_ty, width);
+                let i = IfcBuilder::push_entry(
+                    &mut b.parts.decl_bitfield,
+                    DeclBitfield {
+                        ty,
+                        home_scope: decl,
+                        width,
+                        ..DeclBitfield::new_zeroed()
+                    },
+                );
+                DeclIndex::new(DeclSort::BITFIELD, i)
+            });
+        }
+        let initializer = b.add_scope(&decls);
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_scope,
+            DeclScope {
+                ty,
+                base,
+                initializer,
+                pack_size,
+                ..DeclScope::new_zeroed()
+            },
+        );
+        decl
+    };
+
+    // struct { char c; double d; int *p; char a[3]; unsigned x : 3, y : 30; short z : 2; }
+    let members = [
+        (char_ty, 0),
+        (double_ty, 0),
+        (int_ptr, 0),
+        (char_array, 0),
+        (uint_ty, 3),
+        (uint_ty, 30),
+        (short_ty, 2),
+    ];
+    let mixed = add_class(&mut b, struct_ty, TypeIndex(0), &members, 0);
+    let packed = add_class(&mut b, struct_ty, TypeIndex(0), &members, 4);
+    let union = add_class(
+        &mut b,
+        union_ty,
+        TypeIndex(0),
+        &[(char_array, 0), (double_ty, 0)],
+        0,
+    );
+
+    // struct DERIVED : POINT { virtual void f(); };
+    let point_base = TypeIndex::new(
+        TypeSort::BASE,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_base,
+            TypeBase {
+                ty: point_ty,
+                ..TypeBase::new_zeroed()
+            },
+        ),
+    );
+    let derived = add_class(&mut b, struct_ty, point_base, &[], 0);
+    let method = IfcBuilder::push_entry(
+        &mut b.parts.decl_method,
+        DeclMethod {
+            home_scope: derived,
+            traits: FunctionTraits::VIRTUAL,
+            ..DeclMethod::new_zeroed()
+        },
+    );
+    let derived_members = b.add_scope(&[DeclIndex::new(DeclSort::METHOD, method)]);
+    b.parts.decl_scope[derived.index() as usize].initializer = derived_members;
+
+    // sizeof(POINT)
+    let sizeof_point = ExprIndex::new(
+        ExprSort::SIZEOF_TYPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_sizeof_type,
+            ExprSizeofType {
+                operand: point_ty,
+                ..ExprSizeofType::new_zeroed()
+            },
+        ),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+
+    let point = ifc
+        .class_layout(DeclIndex::new(DeclSort::SCOPE, 0))
+        .unwrap();
+    assert_eq!((point.size, point.align), (12, 4));
+    let offsets: Vec<_> = point.fields.iter().map(|f| (f.offset, f.bits)).collect();
+    assert_eq!(
+        offsets,
+        [
+            (0, None),
+            (4, None),
+            (
+                8,
+                Some(BitRange {
+                    offset: 0,
+                    width: 3
+                })
+            )
+        ]
+    );
+    assert_eq!(ifc.eval_const(sizeof_point).unwrap(), ConstValue::Int(12));
+
+    // Adjacent bitfields share a unit until they don't fit, or their types change size.
+    let bits = |layout: &ClassLayout| -> Vec<(u64, Option<BitRange>)> {
+        layout.fields.iter().map(|f| (f.offset, f.bits)).collect()
+    };
+    let bit = |offset, width| Some(BitRange { offset, width });
+    let mixed = ifc.class_layout(mixed).unwrap();
+    assert_eq!((mixed.size, mixed.align, mixed.data_size), (40, 8, 38));
+    assert_eq!(
+        bits(&mixed),
+        [
+            (0, None),
+            (8, None),
+            (16, None),
+            (24, None),
+            (28, bit(0, 3)),
+            (32, bit(0, 30)),
+            (36, bit(0, 2)),
+        ]
+    );
+    let packed = ifc.class_layout(packed).unwrap();
+    assert_eq!((packed.size, packed.align), (36, 4));
+    let offsets: Vec<u64> = packed.fields.iter().map(|f| f.offset).collect();
+    assert_eq!(offsets, [0, 4, 12, 20, 24, 28, 32]);
+
+    let union = ifc.class_layout(union).unwrap();
+    assert_eq!((union.size, union.align), (8, 8));
+    assert!(union.fields.iter().all(|f| f.offset == 0));
+
+    // The vfptr comes before the base class.
+    let derived = ifc.class_layout(derived).unwrap();
+    assert!(derived.vfptr && derived.polymorphic);
+    assert_eq!(
+        derived.bases,
+        [BaseLayout {
+            ty: point_ty,
+            offset: 8
+        }]
+    );
+    assert_eq!((derived.size, derived.align), (24, 8));
+
+    assert_eq!(ifc.size_of(char_array).unwrap(), 3);
+    assert_eq!(ifc.align_of(double_ty).unwrap(), 8);
+    assert_eq!(ifc.size_of(int_ptr).unwrap(), 8);
+    assert!(ifc
+        .size_of(TypeIndex::new(TypeSort::FUNDAMENTAL, 2))
+        .is_err());
+
+    // Pointers depend on the architecture.
+    let mut b = point_builder();
+    b.file_header.arch = Architecture::X86;
+    let ptr = TypeIndex::new(
+        TypeSort::POINTER,
+        IfcBuilder::push_entry(&mut b.parts.type_pointer, uint_ty),
+    );
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert_eq!(
+        ifc.type_layout(ptr).unwrap(),
+        TypeLayout { size: 4, align: 4 }
+    );
+}
+
+/// Builds an empty unit of the given sort, which imports and exports the given units. Each
+/// import is `(owner, partition)`, where an empty owner means a header unit.
+fn module_unit(
+    sort: UnitSort,
+    name: &str,
+    src_path: &str,
+    imports: &[(&str, &str)],
+    exports: &[(&str, &str)],
+) -> Vec<u8> {
+    let mut b = IfcBuilder::new();
+    b.set_src_path(src_path);
+    b.set_unit(sort, name);
+    for &(owner, partition) in imports {
+        let r = b.add_module_reference(owner, partition);
+        b.parts.module_imported.push(r);
+    }
+    for &(owner, partition) in exports {
+        let r = b.add_module_reference(owner, partition);
+        b.parts.module_exported.push(r);
+    }
+    b.file_header.global_scope = b.add_scope(&[]);
+    b.to_bytes()
+}
+
+#[test]
+fn modules() {
+    let header = module_unit(UnitSort::HEADER, "", "vector.h", &[], &[]);
+    let partition = module_unit(
+        UnitSort::PARTITION,
+        "m:p",
+        "p.ixx",
+        &[("", "vector.h")],
+        &[],
+    );
+    let primary = module_unit(UnitSort::PRIMARY, "m", "m.ixx", &[("n", "")], &[("m", "p")]);
+    let source = module_unit(UnitSort::SOURCE, "", "main.cpp", &[("m", "")], &[]);
+
+    let ifc = Ifc::load(partition.clone()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let info = ifc.module_info().unwrap();
+    assert_eq!(info.sort, UnitSort::PARTITION);
+    assert_eq!(info.module_name, Some("m"));
+    assert_eq!(info.partition_name, Some("p"));
+    assert_eq!(info.unit_name().as_deref(), Some("m:p"));
+    assert_eq!(
+        info.imports,
+        [ImportedUnit {
+            owner: None,
+            partition: Some("vector.h"),
+            exported: false,
+        }]
+    );
+    assert!(info.imports[0].is_header_unit());
+
+    let ifc = Ifc::load(primary.clone()).unwrap();
+    let info = ifc.module_info().unwrap();
+    let imports: Vec<(String, bool)> = info
+        .imports
+        .iter()
+        .map(|i| (i.unit_name(), i.exported))
+        .collect();
+    assert_eq!(
+        imports,
+        [("n".to_string(), false), ("m:p".to_string(), true)]
+    );
+
+    let ifc = Ifc::load(source.clone()).unwrap();
+    assert_eq!(ifc.unit_sort(), UnitSort::SOURCE);
+    assert_eq!(ifc.module_info().unwrap().unit_name(), None);
+
+    // The graph of units, loaded from a directory.
+    let dir = std::env::temp_dir().join(format!("ifc-modules-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, data) in [
+        ("main.ifc", &source),
+        ("m.ifc", &primary),
+        ("m-p.ifc", &partition),
+        ("vector.h.ifc", &header),
+    ] {
+        std::fs::write(dir.join(file), data).unwrap();
+    }
+    std::fs::write(dir.join("notes.txt"), "not an ifc").unwrap();
+    let set = IfcSet::load_dir(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let mut set = set.unwrap();
+
+    assert_eq!(set.len(), 4);
+    let unit = |name| set.find(name).unwrap();
+    assert_eq!(
+        set.entries()[unit("main.cpp")].path.file_name().unwrap(),
+        "main.ifc"
+    );
+    assert_eq!(set.dependencies(unit("m")), [unit("m:p")]);
+    assert_eq!(set.unresolved_imports(unit("m")), ["n"]);
+    assert_eq!(set.dependencies(unit("m:p")), [unit("vector.h")]);
+    let order: Vec<&str> = set
+        .topological_order()
+        .unwrap()
+        .into_iter()
+        .map(|i| set.entries()[i].name.as_str())
+        .collect();
+    assert_eq!(order, ["vector.h", "m:p", "m", "main.cpp"]);
+    assert!(set.find_cycle().is_none());
+
+    // Units must have distinct names.
+    let again = Ifc::load(primary).unwrap();
+    assert!(set.insert("m2.ifc".into(), again).is_err());
+
+    // `n` imports `m`, which imports `n`.
+    let n = module_unit(UnitSort::PRIMARY, "n", "n.ixx", &[("m", "")], &[]);
+    set.insert("n.ifc".into(), Ifc::load(n).unwrap()).unwrap();
+    let cycle: Vec<&str> = set
+        .find_cycle()
+        .unwrap()
+        .into_iter()
+        .map(|i| set.entries()[i].name.as_str())
+        .collect();
+    assert_eq!(cycle, ["m", "n", "m"]);
+    let error = set.topological_order().unwrap_err().to_string();
+    assert_eq!(error, "import cycle: m -> n -> m");
+}
+
+#[test]
+fn workspace() {
+    let mut b = point_builder();
+    b.set_unit(UnitSort::HEADER, "");
+    let point = Arc::new(Ifc::load(b.to_bytes()).unwrap());
+    let point_decl = DeclIndex::new(DeclSort::SCOPE, 0);
+
+    // `shape.h` imports `point.h`, and forward-declares `POINT`, as in `struct POINT;`.
+    let mut b = IfcBuilder::new();
+    b.set_src_path("shape.h");
+    b.set_unit(UnitSort::HEADER, "");
+    let r = b.add_module_reference("", "point.h");
+    b.parts.module_imported.push(r);
+    let struct_ty =
+        b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let name = b.add_identifier("POINT");
+    let forward = DeclIndex::new(
+        DeclSort::SCOPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_scope,
+            DeclScope {
+                name,
+                ty: struct_ty,
+                ..DeclScope::new_zeroed()
+            },
+        ),
+    );
+    let designated = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(&mut b.parts.type_designated, forward),
+    );
+    let const_point = TypeIndex::new(
+        TypeSort::QUALIFIED,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_qualified,
+            QualifiedType {
+                unqualified_type: designated,
+                qualifiers: Qualifiers::CONST,
+                ..QualifiedType::new_zeroed()
+            },
+        ),
+    );
+    let unit = b.add_module_reference("", "point.h");
+    let reference = DeclIndex::new(
+        DeclSort::REFERENCE,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_reference,
+            DeclReference {
+                unit,
+                local_index: point_decl,
+            },
+        ),
+    );
+    b.file_header.global_scope = b.add_scope(&[forward]);
+    let shape = Arc::new(Ifc::load(b.to_bytes()).unwrap());
+    assert!(shape.validate().is_empty());
+
+    // Without `point.h`, nothing defines `POINT`.
+    let mut ws = Workspace::new();
+    let shape_id = ws.add("shape", shape.clone()).unwrap();
+    assert_eq!(ws.resolve_decl(shape_id, forward).unwrap(), None);
+    assert_eq!(ws.resolve_decl(shape_id, reference).unwrap(), None);
+
+    let point_id = ws.add("point", point.clone()).unwrap();
+    assert!(ws.add("point", point.clone()).is_err());
+    assert_eq!(ws.len(), 2);
+    assert_eq!(ws.find("point"), Some(point_id));
+    assert_eq!(ws.find_unit_name("point.h"), Some(point_id));
+    assert_eq!(ws.units()[shape_id].imports, ["point.h"]);
+
+    let expected = Some((point_id, point_decl));
+    assert_eq!(ws.resolve_decl(shape_id, forward).unwrap(), expected);
+    assert_eq!(ws.resolve_decl(shape_id, reference).unwrap(), expected);
+    assert_eq!(ws.resolve_type(shape_id, const_point).unwrap(), expected);
+    assert_eq!(ws.resolve_decl(point_id, point_decl).unwrap(), expected);
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    assert_eq!(ws.resolve_type(point_id, int_ty).unwrap(), None);
+
+    // Members are found by their qualified names.
+    let x = point.build_index().unwrap().lookup("POINT::x")[0];
+    assert_eq!(ws.resolve_decl(point_id, x).unwrap(), Some((point_id, x)));
+
+    // An IFC outside the workspace finds the definition even when it has its own.
+    let mut ws = Workspace::new();
+    let point_id = ws.add("point", point.clone()).unwrap();
+    assert_eq!(
+        ws.resolve_foreign_decl(&point, point_decl).unwrap(),
+        Some((point_id, point_decl))
+    );
+    assert_eq!(
+        ws.resolve_foreign_type(&shape, designated).unwrap(),
+        Some((point_id, point_decl))
+    );
+}
+
+/// Two units that each have `namespace { struct S; }` and `static void f();`, where the second
+/// unit also defines `S`. Neither entity is the same entity as the one in the other unit.
+#[test]
+fn workspace_unit_local() {
+    fn unit(path: &str, define: bool) -> (Ifc<'static>, DeclIndex, DeclIndex) {
+        let mut b = IfcBuilder::new();
+        b.set_src_path(path);
+        b.set_unit(UnitSort::HEADER, "");
+        let namespace_ty = b.add_fundamental_type(
+            TypeBasis::NAMESPACE,
+            TypePrecision::DEFAULT,
+            TypeSign::PLAIN,
+        );
+        let struct_ty =
+            b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+        let void_ty =
+            b.add_fundamental_type(TypeBasis::VOID, TypePrecision::DEFAULT, TypeSign::PLAIN);
+        let anon_name = b.add_identifier("");
+        let anon = DeclIndex::new(
+            DeclSort::SCOPE,
+            IfcBuilder::push_entry(
+                &mut b.parts.decl_scope,
+                DeclScope {
+                    name: anon_name,
+                    ty: namespace_ty,
+                    ..DeclScope::new_zeroed()
+                },
+            ),
+        );
+        let initializer = if define { b.add_scope(&[]) } else { 0 };
+        let s_name = b.add_identifier("S");
+        let s = DeclIndex::new(
+            DeclSort::SCOPE,
+            IfcBuilder::push_entry(
+                &mut b.parts.decl_scope,
+                DeclScope {
+                    name: s_name,
+                    ty: struct_ty,
+                    home_scope: anon,
+                    initializer,
+                    ..DeclScope::new_zeroed()
+                },
+            ),
+        );
+        let anon_scope = b.add_scope(&[s]);
+        b.parts.decl_scope[anon.index() as usize].initializer = anon_scope;
+
+        let func_ty = TypeIndex::new(
+            TypeSort::FUNCTION,
+            IfcBuilder::push_entry(
+                &mut b.parts.type_function,
+                FunctionType {
+                    target: void_ty,
+                    ..FunctionType::new_zeroed()
+                },
+            ),
+        );
+        let f_name = b.add_identifier("f");
+        let f = DeclIndex::new(
+            DeclSort::FUNCTION,
+            IfcBuilder::push_entry(
+                &mut b.parts.decl_function,
+                DeclFunc {
+                    name: f_name,
+                    type_: func_ty,
+                    specifiers: BasicSpecifiers::INTERNAL,
+                    ..DeclFunc::new_zeroed()
+                },
+            ),
+        );
+        b.file_header.global_scope = b.add_scope(&[anon, f]);
+        let ifc = Ifc::load(b.to_bytes()).unwrap();
+        assert!(ifc.validate().is_empty());
+        (ifc, s, f)
+    }
+
+    let (a, a_s, a_f) = unit("a.h", false);
+    let (b, b_s, b_f) = unit("b.h", true);
+    assert_eq!(
+        a.qualified_name(a_s).unwrap().to_string(),
+        "(anonymous namespace)::S"
+    );
+
+    let mut ws = Workspace::new();
+    let a_id = ws.add("a", Arc::new(a)).unwrap();
+    let b_id = ws.add("b", Arc::new(b)).unwrap();
+    assert_eq!(ws.resolve_decl(a_id, a_s).unwrap(), None);
+    assert_eq!(ws.resolve_decl(b_id, b_s).unwrap(), Some((b_id, b_s)));
+    assert_eq!(ws.resolve_decl(a_id, a_f).unwrap(), Some((a_id, a_f)));
+    let b = &ws.units()[b_id].ifc;
+    assert_eq!(ws.resolve_foreign_decl(b, b_s).unwrap(), None);
+    assert_eq!(ws.resolve_foreign_decl(b, b_f).unwrap(), None);
+}
+
+#[test]
+fn type_keys() {
+    /// Adds `const T *`.
+    fn pointer_to_const(b: &mut IfcBuilder, ty: TypeIndex) -> TypeIndex {
+        let qualified = TypeIndex::new(
+            TypeSort::QUALIFIED,
+            IfcBuilder::push_entry(
+                &mut b.parts.type_qualified,
+                QualifiedType {
+                    unqualified_type: ty,
+                    qualifiers: Qualifiers::CONST,
+                    ..QualifiedType::new_zeroed()
+                },
+            ),
+        );
+        TypeIndex::new(
+            TypeSort::POINTER,
+            IfcBuilder::push_entry(&mut b.parts.type_pointer, qualified),
+        )
+    }
+
+    let mut b = point_builder();
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_designated,
+            DeclIndex::new(DeclSort::SCOPE, 0),
+        ),
+    );
+    let a_ptr = pointer_to_const(&mut b, point_ty);
+    let a = Ifc::load(b.to_bytes()).unwrap();
+
+    // The same types, with different indexes, and spelled `signed int` and `const PT *` given
+    // `typedef POINT PT;`.
+    let mut b = IfcBuilder::new();
+    b.set_src_path("other.h");
+    b.add_fundamental_type(TypeBasis::BOOL, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let signed_ty =
+        b.add_fundamental_type(TypeBasis::INT, TypePrecision::DEFAULT, TypeSign::SIGNED);
+    let struct_ty =
+        b.add_fundamental_type(TypeBasis::STRUCT, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let name = b.add_identifier("POINT");
+    let point_decl = DeclIndex::new(
+        DeclSort::SCOPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_scope,
+            DeclScope {
+                name,
+                ty: struct_ty,
+                ..DeclScope::new_zeroed()
+            },
+        ),
+    );
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(&mut b.parts.type_designated, point_decl),
+    );
+    let name = b.add_string("PT");
+    let alias = DeclIndex::new(
+        DeclSort::ALIAS,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_alias,
+            DeclAlias {
+                name,
+                aliasee: point_ty,
+                ..DeclAlias::new_zeroed()
+            },
+        ),
+    );
+    let alias_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(&mut b.parts.type_designated, alias),
+    );
+    let b_ptr = pointer_to_const(&mut b, alias_ty);
+    let extent = b.add_integer_literal(signed_ty, 4);
+    let array = TypeIndex::new(
+        TypeSort::ARRAY,
+        IfcBuilder::push_entry(
+            &mut b.parts.type_array,
+            TypeArray {
+                element: signed_ty,
+                extent,
+            },
+        ),
+    );
+    b.file_header.global_scope = b.add_scope(&[point_decl, alias]);
+    let other = Ifc::load(b.to_bytes()).unwrap();
+
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let mut keys = TypeKeyInterner::new();
+    let key = a.type_key(a_ptr, &mut keys).unwrap();
+    let point = keys
+        .find(&TypeKey::Designated("POINT".to_string()))
+        .unwrap();
+    let const_point = keys
+        .find(&TypeKey::Qualified {
+            ty: point,
+            qualifiers: Qualifiers::CONST,
+        })
+        .unwrap();
+    assert_eq!(*keys.get(key), TypeKey::Pointer(const_point));
+    assert_eq!(other.type_key(b_ptr, &mut keys).unwrap(), key);
+    assert_eq!(
+        other.type_key(signed_ty, &mut keys).unwrap(),
+        a.type_key(int_ty, &mut keys).unwrap()
+    );
+    assert_ne!(
+        a.type_key(int_ty, &mut keys).unwrap(),
+        a.type_key(TypeIndex::new(TypeSort::FUNDAMENTAL, 1), &mut keys)
+            .unwrap()
+    );
+    let array = other.type_key(array, &mut keys).unwrap();
+    let element = a.type_key(int_ty, &mut keys).unwrap();
+    assert_eq!(
+        *keys.get(array),
+        TypeKey::Array {
+            element,
+            extent: Some(4),
+        }
+    );
+
+    // Ids can be used to find the same type in several units.
+    let mut types = HashMap::new();
+    types.insert(key, "a");
+    assert_eq!(
+        types.get(&other.type_key(b_ptr, &mut keys).unwrap()),
+        Some(&"a")
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn export_model() {
+    let mut b = point_builder();
+    // `#define ANSWER 42`
+    let name = b.add_string("ANSWER");
+    let spelling = b.add_string("42");
+    let body = FormIndex::new(
+        FormSort::NUMBER,
+        IfcBuilder::push_entry(
+            &mut b.parts.pp_num,
+            FormNumber {
+                spelling,
+                ..FormNumber::new_zeroed()
+            },
+        ),
+    );
+    IfcBuilder::push_entry(
+        &mut b.parts.macro_object_like,
+        MacroObjectLike {
+            name,
+            body,
+            ..MacroObjectLike::new_zeroed()
+        },
+    );
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+
+    let model = ifc.export_model().unwrap();
+    assert_eq!(model.src_path, "point.h");
+    let names: Vec<&str> = model.declarations.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["POINT", "POINT::x", "POINT::y", "POINT::flags"]);
+    match &model.declarations[0].kind {
+        DeclModelKind::Class { key, complete, .. } => {
+            assert_eq!(key, "struct");
+            assert!(complete);
+        }
+        kind => panic!("POINT is not a class: {:?}", kind),
+    }
+    match &model.declarations[1].kind {
+        DeclModelKind::Field {
+            ty: TypeModel::Fundamental { name },
+        } => assert_eq!(name, "int"),
+        kind => panic!("x is not an int field: {:?}", kind),
+    }
+    match &model.declarations[3].kind {
+        DeclModelKind::Bitfield { width, .. } => assert_eq!(*width, Some(3)),
+        kind => panic!("flags is not a bitfield: {:?}", kind),
+    }
+    assert_eq!(model.macros.len(), 1);
+    assert_eq!(model.macros[0].name, "ANSWER");
+    assert_eq!(model.macros[0].params, None);
+    assert_eq!(model.macros[0].body, ["42"]);
+}
+
+#[test]
+fn abi_diff() {
+    let point_decl = DeclIndex::new(DeclSort::SCOPE, 0);
+
+    // The old version has `typedef POINT PT;`.
+    let mut b = point_builder();
+    b.file_header.arch = Architecture::X64;
+    let name = b.add_string("PT");
+    let point_ty = TypeIndex::new(
+        TypeSort::DESIGNATED,
+        IfcBuilder::push_entry(&mut b.parts.type_designated, point_decl),
+    );
+    let alias = DeclIndex::new(
+        DeclSort::ALIAS,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_alias,
+            DeclAlias {
+                name,
+                aliasee: point_ty,
+                ..DeclAlias::new_zeroed()
+            },
+        ),
+    );
+    b.file_header.global_scope = b.add_scope(&[point_decl, alias]);
+    let old = Ifc::load(b.to_bytes()).unwrap();
+
+    // The new version makes `y` unsigned and adds `#define ANSWER 42`.
+    let mut b = point_builder();
+    b.file_header.arch = Architecture::X64;
+    b.parts.decl_field[1].ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 1);
+    let name = b.add_string("ANSWER");
+    let spelling = b.add_string("42");
+    let body = FormIndex::new(
+        FormSort::NUMBER,
+        IfcBuilder::push_entry(
+            &mut b.parts.pp_num,
+            FormNumber {
+                spelling,
+                ..FormNumber::new_zeroed()
+            },
+        ),
+    );
+    IfcBuilder::push_entry(
+        &mut b.parts.macro_object_like,
+        MacroObjectLike {
+            name,
+            body,
+            ..MacroObjectLike::new_zeroed()
+        },
+    );
+    let new = Ifc::load(b.to_bytes()).unwrap();
+
+    assert!(diff(&old, &old).unwrap().is_empty());
+    let changes: Vec<String> = diff(&old, &new)
+        .unwrap()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "changed field POINT::y: int at offset 4 -> unsigned int at offset 4",
+            "removed typedef PT: POINT",
+            "added macro ANSWER: 42",
+        ]
+    );
+
+    let changes = diff(&new, &old).unwrap();
+    assert_eq!(changes[0].kind, ChangeKind::Changed);
+    assert_eq!(changes[0].entity, EntityKind::Field);
+    assert_eq!(changes[0].old.as_deref(), Some("unsigned int at offset 4"));
+    assert_eq!(changes[2].kind, ChangeKind::Removed);
+    assert_eq!(changes[2].new, None);
+}
+
+/// Builds `f(1 + 2, 3)`, where `f` is a reference to the first field of `POINT`.
+#[test]
+fn expression_tree() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let one = b.add_integer_literal(int_ty, 1);
+    let two = b.add_integer_literal(int_ty, 2);
+    let three = b.add_integer_literal(int_ty, 3);
+    let sum = ExprIndex::new(
+        ExprSort::DYAD,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_dyad,
+            ExprDyad {
+                ty: int_ty,
+                arguments: [one, two],
+                assoc: DyadicOperator::PLUS,
+                ..ExprDyad::new_zeroed()
+            },
+        ),
+    );
+    let callee = ExprIndex::new(
+        ExprSort::NAMED_DECL,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_named_decl,
+            ExprNamedDecl {
+                resolution: DeclIndex::new(DeclSort::FIELD, 0),
+                ..ExprNamedDecl::new_zeroed()
+            },
+        ),
+    );
+    let start = IfcBuilder::push_entry(&mut b.parts.heap_expr, sum);
+    IfcBuilder::push_entry(&mut b.parts.heap_expr, three);
+    let arguments = ExprIndex::new(
+        ExprSort::TUPLE,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_tuple,
+            ExprTuple {
+                start,
+                cardinality: 2,
+                ..ExprTuple::new_zeroed()
+            },
+        ),
+    );
+    let call = ExprIndex::new(
+        ExprSort::CALL,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_call,
+            ExprCall {
+                ty: int_ty,
+                operation: callee,
+                arguments,
+                ..ExprCall::new_zeroed()
+            },
+        ),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+
+    let call = match ifc.expr(call).unwrap() {
+        Expr::Call(call) => call,
+        e => panic!("expected a call, got {:?}", e),
+    };
+    match ifc.expr(call.operation).unwrap() {
+        Expr::NamedDecl(d) => assert_eq!(d.resolution, DeclIndex::new(DeclSort::FIELD, 0)),
+        e => panic!("expected a decl, got {:?}", e),
+    }
+    let args: Vec<ExprIndex> = ifc.iter_expr_tuple(call.arguments).unwrap().collect();
+    assert_eq!(args, [sum, three]);
+    match ifc.expr(args[0]).unwrap() {
+        Expr::Dyad(dyad) => {
+            assert_eq!(dyad.assoc, DyadicOperator::PLUS);
+            assert_eq!(ifc.get_literal_expr_u32(dyad.arguments[1]).unwrap(), 2);
+        }
+        e => panic!("expected a dyad, got {:?}", e),
+    }
+    assert!(matches!(ifc.expr(ExprIndex(0)).unwrap(), Expr::Other(_)));
+    assert!(ifc.expr(ExprIndex::new(ExprSort::MONAD, 0)).is_err());
+    let single: Vec<ExprIndex> = ifc.iter_expr_tuple(three).unwrap().collect();
+    assert_eq!(single, [three]);
+
+    let mut b = point_builder();
+    b.parts.expr_tuple.push(ExprTuple {
+        cardinality: 1,
+        ..ExprTuple::new_zeroed()
+    });
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let diags: Vec<String> = ifc.validate().iter().map(|d| d.to_string()).collect();
+    assert_eq!(diags.len(), 1);
+    assert!(diags[0].starts_with("expr.tuple[0].start"), "{:?}", diags);
+    assert!(ifc
+        .iter_expr_tuple(ExprIndex::new(ExprSort::TUPLE, 0))
+        .is_err());
+}
+
+#[test]
+fn eval_const() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let uchar_ty =
+        b.add_fundamental_type(TypeBasis::CHAR, TypePrecision::DEFAULT, TypeSign::UNSIGNED);
+    let dyad = |b: &mut IfcBuilder, assoc, left, right| {
+        let i = IfcBuilder::push_entry(
+            &mut b.parts.expr_dyad,
+            ExprDyad {
+                ty: int_ty,
+                arguments: [left, right],
+                assoc,
+                ..ExprDyad::new_zeroed()
+            },
+        );
+        ExprIndex::new(ExprSort::DYAD, i)
+    };
+    let named = |b: &mut IfcBuilder, resolution| {
+        let i = IfcBuilder::push_entry(
+            &mut b.parts.expr_named_decl,
+            ExprNamedDecl {
+                resolution,
+                ..ExprNamedDecl::new_zeroed()
+            },
+        );
+        ExprIndex::new(ExprSort::NAMED_DECL, i)
+    };
+
+    // enum { A = 1 << 3, B = A | 2 };
+    let one = b.add_integer_literal(int_ty, 1);
+    let two = b.add_integer_literal(int_ty, 2);
+    let three = b.add_integer_literal(int_ty, 3);
+    let a_init = dyad(&mut b, DyadicOperator::LSHIFT, one, three);
+    let a = IfcBuilder::push_entry(
+        &mut b.parts.decl_enumerator,
+        DeclEnumerator {
+            ty: int_ty,
+            initializer: a_init,
+            ..DeclEnumerator::new_zeroed()
+        },
+    );
+    let a_ref = named(&mut b, DeclIndex::new(DeclSort::ENUMERATOR, a));
+    let b_init = dyad(&mut b, DyadicOperator::BITOR, a_ref, two);
+
+    // sizeof(int) * 2 > 7 ? -1 : 0, cast to unsigned char
+    let size = ExprIndex::new(
+        ExprSort::SIZEOF_TYPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_sizeof_type,
+            ExprSizeofType {
+                operand: int_ty,
+                ..ExprSizeofType::new_zeroed()
+            },
+        ),
+    );
+    let doubled = dyad(&mut b, DyadicOperator::MULT, size, two);
+    let seven = b.add_integer_literal(int_ty, 7);
+    let test = dyad(&mut b, DyadicOperator::GREATER, doubled, seven);
+    let minus_one = ExprIndex::new(
+        ExprSort::MONAD,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_monad,
+            ExprMonad {
+                ty: int_ty,
+                argument: one,
+                assoc: MonadicOperator::NEGATE,
+                ..ExprMonad::new_zeroed()
+            },
+        ),
+    );
+    let zero = b.add_integer_literal(int_ty, 0);
+    let choice = ExprIndex::new(
+        ExprSort::TRIAD,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_triad,
+            ExprTriad {
+                ty: int_ty,
+                arguments: [test, minus_one, zero],
+                assoc: TriadicOperator::CHOICE,
+                ..ExprTriad::new_zeroed()
+            },
+        ),
+    );
+    let cast = ExprIndex::new(
+        ExprSort::CAST,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_cast,
+            ExprCast {
+                source: choice,
+                target: uchar_ty,
+                operator: DyadicOperator::STATIC_CAST,
+                ..ExprCast::new_zeroed()
+            },
+        ),
+    );
+
+    // 1 / 0, and a variable whose initializer refers to itself
+    let div_zero = dyad(&mut b, DyadicOperator::SLASH, one, zero);
+    let var = DeclIndex::new(DeclSort::VARIABLE, b.parts.decl_var.len() as u32);
+    let var_ref = named(&mut b, var);
+    b.parts.decl_var.push(DeclVar {
+        ty: int_ty,
+        initializer: var_ref,
+        ..DeclVar::new_zeroed()
+    });
+
+    // -(1 << 127), without a type to truncate the intermediate value
+    let shift = b.add_integer_literal(int_ty, 127);
+    let min = ExprIndex::new(
+        ExprSort::DYAD,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_dyad,
+            ExprDyad {
+                arguments: [one, shift],
+                assoc: DyadicOperator::LSHIFT,
+                ..ExprDyad::new_zeroed()
+            },
+        ),
+    );
+    let negated_min = ExprIndex::new(
+        ExprSort::MONAD,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_monad,
+            ExprMonad {
+                argument: min,
+                assoc: MonadicOperator::NEGATE,
+                ..ExprMonad::new_zeroed()
+            },
+        ),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    assert_eq!(ifc.eval_const(a_ref).unwrap(), ConstValue::Int(8));
+    assert_eq!(ifc.eval_const(b_init).unwrap().to_u64(), Some(10));
+    assert_eq!(ifc.eval_const(test).unwrap().to_i64(), Some(1));
+    assert_eq!(ifc.eval_const(choice).unwrap().to_i64(), Some(-1));
+    assert_eq!(ifc.eval_const(cast).unwrap(), ConstValue::Int(255));
+    assert!(ifc.eval_const(div_zero).is_err());
+    assert!(ifc.eval_const(var_ref).is_err());
+    assert!(ifc.eval_const(ExprIndex(0)).is_err());
+    assert_eq!(ifc.eval_const(min).unwrap(), ConstValue::Int(i128::MIN));
+    assert!(ifc.eval_const(negated_min).is_err());
+}
+
+#[test]
+fn names() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+
+    let plus = b.add_string("+");
+    let plus = NameIndex::new(
+        NameSort::OPERATOR,
+        IfcBuilder::push_entry(
+            &mut b.parts.name_operator,
+            NameOperator {
+                encoded: plus,
+                operator: Operator::new(OperatorSort::DYADIC, DyadicOperator::PLUS.0),
+                __padding: 0,
+            },
+        ),
+    );
+    let conversion = NameIndex::new(
+        NameSort::CONVERSION,
+        IfcBuilder::push_entry(
+            &mut b.parts.name_conversion,
+            NameConversion {
+                target: int_ty,
+                encoded: 0,
+            },
+        ),
+    );
+    let km = b.add_string("\"\"_km");
+    let km = NameIndex::new(
+        NameSort::LITERAL,
+        IfcBuilder::push_entry(&mut b.parts.name_literal, km),
+    );
+
+    let vector = b.add_identifier("vector");
+    let int_arg = ExprIndex::new(
+        ExprSort::TYPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_type,
+            ExprType {
+                denotation: int_ty,
+                ..ExprType::new_zeroed()
+            },
+        ),
+    );
+    let vector_int = NameIndex::new(
+        NameSort::SPECIALIZATION,
+        IfcBuilder::push_entry(
+            &mut b.parts.name_specialization,
+            NameSpecialization {
+                primary: vector,
+                arguments: int_arg,
+            },
+        ),
+    );
+    let array = b.add_identifier("array");
+    let three = b.add_integer_literal(int_ty, 3);
+    let start = IfcBuilder::push_entry(&mut b.parts.heap_expr, int_arg);
+    IfcBuilder::push_entry(&mut b.parts.heap_expr, three);
+    let args = ExprIndex::new(
+        ExprSort::TUPLE,
+        IfcBuilder::push_entry(
+            &mut b.parts.expr_tuple,
+            ExprTuple {
+                start,
+                cardinality: 2,
+                ..ExprTuple::new_zeroed()
+            },
+        ),
+    );
+    let array_int_3 = NameIndex::new(
+        NameSort::SPECIALIZATION,
+        IfcBuilder::push_entry(
+            &mut b.parts.name_specialization,
+            NameSpecialization {
+                primary: array,
+                arguments: args,
+            },
+        ),
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    assert!(matches!(ifc.name(plus).unwrap(), Name::Operator(op)
+        if op.operator.sort() == OperatorSort::DYADIC));
+    assert_eq!(ifc.get_name_string(plus).unwrap(), "operator+");
+    assert_eq!(ifc.get_name_string(conversion).unwrap(), "operator int");
+    assert_eq!(ifc.get_name_string(km).unwrap(), "operator\"\"_km");
+    assert_eq!(ifc.get_name_string(vector_int).unwrap(), "vector<int>");
+    assert_eq!(ifc.get_name_string(array_int_3).unwrap(), "array<int, 3>");
+
+    // An assumed template name must not refer to another assumed template name.
+    let mut b = point_builder();
+    b.parts
+        .name_template
+        .push(NameIndex::new(NameSort::TEMPLATE, 0));
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let diags: Vec<String> = ifc.validate().iter().map(|d| d.to_string()).collect();
+    assert_eq!(diags.len(), 1);
+    assert!(diags[0].starts_with("name.template[0].name"), "{:?}", diags);
+    assert!(matches!(
+        ifc.get_name_string(NameIndex::new(NameSort::TEMPLATE, 0)),
+        Err(IfcError::BadName { .. })
+    ));
+    assert!(matches!(
+        ifc.get_name_string(NameIndex::new(NameSort::OPERATOR, 0)),
+        Err(IfcError::BadEntryIndex { .. })
+    ));
+}
+
+/// Puts `POINT` in `geo::(anonymous namespace)`, and adds `enum Color { Red }` to it.
+#[test]
+fn qualified_names() {
+    let mut b = point_builder();
+    let namespace_ty = b.add_fundamental_type(
+        TypeBasis::NAMESPACE,
+        TypePrecision::DEFAULT,
+        TypeSign::PLAIN,
+    );
+    let geo_name = b.add_identifier("geo");
+    let geo = DeclIndex::new(
+        DeclSort::SCOPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_scope,
+            DeclScope {
+                name: geo_name,
+                ty: namespace_ty,
+                ..DeclScope::new_zeroed()
+            },
+        ),
+    );
+    let anon_name = b.add_identifier("");
+    let anon = DeclIndex::new(
+        DeclSort::SCOPE,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_scope,
+            DeclScope {
+                name: anon_name,
+                ty: namespace_ty,
+                home_scope: geo,
+                ..DeclScope::new_zeroed()
+            },
+        ),
+    );
+    b.parts.decl_scope[0].home_scope = anon;
+
+    let red_name = b.add_string("Red");
+    let red = IfcBuilder::push_entry(
+        &mut b.parts.decl_enumerator,
+        DeclEnumerator {
+            name: red_name,
+            ..DeclEnumerator::new_zeroed()
+        },
+    );
+    let color_name = b.add_string("Color");
+    IfcBuilder::push_entry(
+        &mut b.parts.decl_enum,
+        DeclEnum {
+            name: color_name,
+            home_scope: DeclIndex::new(DeclSort::SCOPE, 0),
+            initializer: Sequence {
+                start: red,
+                cardinality: 1,
+            },
+            ..DeclEnum::new_zeroed()
+        },
+    );
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let x = ifc
+        .qualified_name(DeclIndex::new(DeclSort::FIELD, 0))
+        .unwrap();
+    assert_eq!(x.0, ["geo", "(anonymous namespace)", "POINT", "x"]);
+    assert_eq!(x.to_string(), "geo::(anonymous namespace)::POINT::x");
+    let red = ifc
+        .qualified_name(DeclIndex::new(DeclSort::ENUMERATOR, red))
+        .unwrap();
+    assert_eq!(
+        red.to_string(),
+        "geo::(anonymous namespace)::POINT::Color::Red"
+    );
+    assert_eq!(ifc.home_scope(geo).unwrap(), None);
+
+    // A scope that contains itself
+    let mut b = point_builder();
+    b.parts.decl_scope[0].home_scope = DeclIndex::new(DeclSort::SCOPE, 0);
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc
+        .qualified_name(DeclIndex::new(DeclSort::FIELD, 0))
+        .is_err());
+}
+
+/// Wraps `POINT` in `namespace cookie`, and adds two overloads of `cookie::area` and
+/// `enum Color { Red }` at global scope.
+#[test]
+fn symbol_index() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let point = DeclIndex::new(DeclSort::SCOPE, 0);
+    let namespace_ty = b.add_fundamental_type(
+        TypeBasis::NAMESPACE,
+        TypePrecision::DEFAULT,
+        TypeSign::PLAIN,
+    );
+    let enum_ty = b.add_fundamental_type(TypeBasis::ENUM, TypePrecision::DEFAULT, TypeSign::PLAIN);
+    let cookie = DeclIndex::new(DeclSort::SCOPE, b.parts.decl_scope.len() as u32);
+    b.parts.decl_scope[0].home_scope = cookie;
+
+    let area_name = b.add_identifier("area");
+    let mut members = vec![point];
+    for _ in 0..2 {
+        let i = IfcBuilder::push_entry(
+            &mut b.parts.decl_function,
+            DeclFunc {
+                name: area_name,
+                type_: int_ty,
+                home_scope: cookie,
+                ..DeclFunc::new_zeroed()
+            },
+        );
+        members.push(DeclIndex::new(DeclSort::FUNCTION, i));
+    }
+    let cookie_scope = b.add_scope(&members);
+    let cookie_name = b.add_identifier("cookie");
+    b.parts.decl_scope.push(DeclScope {
+        name: cookie_name,
+        ty: namespace_ty,
+        initializer: cookie_scope,
+        ..DeclScope::new_zeroed()
+    });
+
+    let red_name = b.add_string("Red");
+    let red = IfcBuilder::push_entry(
+        &mut b.parts.decl_enumerator,
+        DeclEnumerator {
+            name: red_name,
+            ..DeclEnumerator::new_zeroed()
+        },
+    );
+    let color_name = b.add_string("Color");
+    let color = DeclIndex::new(
+        DeclSort::ENUMERATION,
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_enum,
+            DeclEnum {
+                name: color_name,
+                ty: enum_ty,
+                initializer: Sequence {
+                    start: red,
+                    cardinality: 1,
+                },
+                ..DeclEnum::new_zeroed()
+            },
+        ),
+    );
+    b.file_header.global_scope = b.add_scope(&[cookie, color]);
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let index = ifc.build_index().unwrap();
+    assert_eq!(index.lookup("cookie"), [cookie]);
+    assert_eq!(index.lookup("cookie::POINT"), [point]);
+    assert_eq!(
+        index.lookup("cookie::POINT::x"),
+        [DeclIndex::new(DeclSort::FIELD, 0)]
+    );
+    assert_eq!(index.lookup("cookie::area").len(), 2);
+    assert_eq!(index.lookup("POINT"), []);
+    assert_eq!(index.lookup_unqualified("POINT"), [point]);
+    let red = DeclIndex::new(DeclSort::ENUMERATOR, red);
+    assert_eq!(index.lookup("Color::Red"), [red]);
+    assert_eq!(index.lookup("Red"), [red]);
+    assert_eq!(index.lookup_unqualified("Red"), [red]);
+    for (name, decls) in index.iter() {
+        for &decl in decls {
+            if decl.tag() != DeclSort::ENUMERATOR {
+                assert_eq!(ifc.qualified_name(decl).unwrap().to_string(), name);
+            }
+        }
+    }
+}
+
+/// Records the order in which `DeclVisitor` methods are called.
+#[derive(Default)]
+struct RecordingVisitor {
+    events: Vec<String>,
+    max_depth: Option<u32>,
+}
+
+impl RecordingVisitor {
+    fn record(&mut self, ifc: &Ifc, what: &str, decl: DeclIndex) {
+        // `walk` uses this on malformed files, so don't insist on a name.
+        let name = ifc.qualified_name(decl).map(|name| name.to_string());
+        self.events
+            .push(format!("{} {}", what, name.unwrap_or_default()));
+    }
+}
+
+impl DeclVisitor for RecordingVisitor {
+    fn max_depth(&self) -> u32 {
+        self.max_depth.unwrap_or(DEFAULT_MAX_VISIT_DEPTH)
+    }
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclScope) -> Result<bool> {
+        self.record(ifc, "namespace", decl);
+        Ok(true)
+    }
+    fn leave_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclScope) -> Result<()> {
+        self.record(ifc, "leave", decl);
+        Ok(())
+    }
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclScope) -> Result<bool> {
+        self.record(ifc, "class", decl);
+        Ok(true)
+    }
+    fn leave_class(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclScope) -> Result<()> {
+        self.record(ifc, "leave", decl);
+        Ok(())
+    }
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclEnum) -> Result<bool> {
+        self.record(ifc, "enum", decl);
+        Ok(false)
+    }
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclField) -> Result<()> {
+        self.record(ifc, "field", decl);
+        Ok(())
+    }
+    fn visit_bitfield(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclBitfield) -> Result<()> {
+        self.record(ifc, "bitfield", decl);
+        Ok(())
+    }
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, _: &DeclFunc) -> Result<()> {
+        self.record(ifc, "function", decl);
+        Ok(())
+    }
+}
+
+/// Counts the members of the scopes that it visits, and stops at depth 1 without failing.
+#[derive(Default)]
+struct MemberCounter {
+    members: usize,
+    too_deep: Vec<ScopeIndex>,
+}
+
+impl DeclVisitor for MemberCounter {
+    fn max_depth(&self) -> u32 {
+        1
+    }
+    fn depth_exceeded(&mut self, _: &Ifc, scope: ScopeIndex) -> Result<()> {
+        self.too_deep.push(scope);
+        Ok(())
+    }
+    fn visit_member(&mut self, _: &Ifc, _: DeclIndex) -> Result<()> {
+        self.members += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn visitor() {
+    let mut b = point_builder();
+    let point = DeclIndex::new(DeclSort::SCOPE, 0);
+    let namespace_ty = b.add_fundamental_type(
+        TypeBasis::NAMESPACE,
+        TypePrecision::DEFAULT,
+        TypeSign::PLAIN,
+    );
+    let cookie = DeclIndex::new(DeclSort::SCOPE, 1);
+    let alias = DeclIndex::new(DeclSort::SCOPE, 2);
+    b.parts.decl_scope[0].home_scope = cookie;
+
+    // `alias` is a class whose members are the members of `cookie`, which contains `alias`.
+    let cookie_scope = b.add_scope(&[point, alias]);
+    let cookie_name = b.add_identifier("cookie");
+    b.parts.decl_scope.push(DeclScope {
+        name: cookie_name,
+        ty: namespace_ty,
+        initializer: cookie_scope,
+        ..DeclScope::new_zeroed()
+    });
+    let alias_name = b.add_identifier("alias");
+    b.parts.decl_scope.push(DeclScope {
+        name: alias_name,
+        ty: b.parts.decl_scope[0].ty,
+        home_scope: cookie,
+        initializer: cookie_scope,
+        ..DeclScope::new_zeroed()
+    });
+    b.file_header.global_scope = b.add_scope(&[cookie]);
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let mut visitor = RecordingVisitor::default();
+    ifc.visit(&mut visitor).unwrap();
+    assert_eq!(
+        visitor.events,
+        [
+            "namespace cookie",
+            "class cookie::POINT",
+            "field cookie::POINT::x",
+            "field cookie::POINT::y",
+            "bitfield cookie::POINT::flags",
+            "leave cookie::POINT",
+            "class cookie::alias",
+            "leave cookie::alias",
+            "leave cookie",
+        ]
+    );
+
+    // The global scope is depth 0, `cookie` is depth 1 and `POINT` is depth 2.
+    let mut visitor = RecordingVisitor {
+        max_depth: Some(1),
+        ..RecordingVisitor::default()
+    };
+    assert!(ifc.visit(&mut visitor).is_err());
+    let mut visitor = RecordingVisitor {
+        max_depth: Some(2),
+        ..RecordingVisitor::default()
+    };
+    assert!(ifc.visit(&mut visitor).is_ok());
+
+    // `cookie` and its two members are visited, but not the members of `POINT`.
+    let mut counter = MemberCounter::default();
+    ifc.visit(&mut counter).unwrap();
+    assert_eq!(counter.members, 3);
+    assert_eq!(counter.too_deep, [b.parts.decl_scope[0].initializer]);
+}
+
+#[test]
+fn source_locations() {
+    let mut b = point_builder();
+    let generated = b.add_source_location(NameIndex(0), 0, 0);
+    let file = b.add_source_file("point.h");
+    b.parts.decl_scope[0].locus = b.add_source_location(file, 42, 8);
+    b.parts.decl_field[0].locus = generated;
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let point = ifc.decl_scope().entry(0).unwrap();
+    assert_eq!(ifc.resolve_locus(&point.locus), Some(("point.h", 42, 8)));
+    let x = ifc.decl_field().entry(0).unwrap();
+    assert_eq!(ifc.resolve_locus(&x.locus), None);
+    let bogus = SourceLocation {
+        line: 100,
+        column: 0,
+    };
+    assert_eq!(ifc.resolve_locus(&bogus), None);
+
+    // `src.line` entries must refer to source files.
+    let mut b = point_builder();
+    let name = b.add_identifier("point.h");
+    b.add_source_location(name, 1, 1);
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let diags = ifc.validate();
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].partition, "src.line");
+}
+
+fn word(b: &mut IfcBuilder, sort: WordSort, text: &str) -> Word {
+    Word {
+        index: b.add_string(text),
+        sort,
+        ..Word::new_zeroed()
+    }
+}
+
+#[test]
+fn attributes() {
+    let mut b = point_builder();
+    let point = DeclIndex::new(DeclSort::SCOPE, 0);
+    let uint_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 1);
+
+    let basic = |b: &mut IfcBuilder, sort: WordSort, text: &str| {
+        let w = word(b, sort, text);
+        AttrIndex::new(
+            AttrSort::BASIC,
+            IfcBuilder::push_entry(&mut b.parts.attr_basic, w),
+        )
+    };
+    let nodiscard = basic(&mut b, WordSort::IDENTIFIER, "nodiscard");
+    let deprecated = basic(&mut b, WordSort::IDENTIFIER, "deprecated");
+    let message = basic(&mut b, WordSort::LITERAL, "\"old\"");
+
+    let called = AttrCalled {
+        function: deprecated,
+        arguments: message,
+    };
+    let called = AttrIndex::new(
+        AttrSort::CALLED,
+        IfcBuilder::push_entry(&mut b.parts.attr_called, called),
+    );
+
+    let scoped = AttrScoped {
+        scope: word(&mut b, WordSort::IDENTIFIER, "gnu"),
+        member: word(&mut b, WordSort::IDENTIFIER, "packed"),
+    };
+    let scoped = AttrIndex::new(
+        AttrSort::SCOPED,
+        IfcBuilder::push_entry(&mut b.parts.attr_scoped, scoped),
+    );
+
+    let eight = b.add_integer_literal(uint_ty, 8);
+    let elaborated = AttrIndex::new(
+        AttrSort::ELABORATED,
+        IfcBuilder::push_entry(&mut b.parts.attr_elaborated, eight),
+    );
+
+    b.parts.heap_attr.extend_from_slice(&[nodiscard, scoped]);
+    let tuple = IfcBuilder::push_entry(
+        &mut b.parts.attr_tuple,
+        AttrTuple {
+            start: 0,
+            cardinality: 2,
+        },
+    );
+    let tuple = AttrIndex::new(AttrSort::TUPLE, tuple);
+
+    // MSVC puts the attributes that were written in the source in `.msvc.trait.decl-attrs`.
+    // The partitions are sorted by declaration.
+    let y = DeclIndex::new(DeclSort::FIELD, 1);
+    let mut decl_attrs = [(point, tuple), (y, nodiscard), (y, scoped)];
+    decl_attrs.sort_by_key(|(decl, _)| decl.0);
+    for (decl, attribute) in decl_attrs {
+        IfcBuilder::push_entry(
+            &mut b.parts.msvc_trait_decl_attrs,
+            AttributeTrait { decl, attribute },
+        );
+    }
+    for attribute in [called, elaborated] {
+        IfcBuilder::push_entry(
+            &mut b.parts.trait_attribute,
+            AttributeTrait {
+                decl: point,
+                attribute,
+            },
+        );
+    }
+    let message = b.add_string("old");
+    IfcBuilder::push_entry(
+        &mut b.parts.trait_deprecated,
+        DeprecatedTrait {
+            decl: point,
+            message,
+        },
+    );
+
+    // An attribute that expands itself.
+    let cyclic = AttrIndex::new(AttrSort::EXPANDED, 0);
+    b.parts.attr_expanded.push(cyclic);
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    let attrs: Vec<String> = ifc
+        .decl_attributes(point)
+        .map(|attr| ifc.get_attr_string(attr).unwrap())
+        .collect();
+    assert_eq!(
+        attrs,
+        ["nodiscard, gnu::packed", "deprecated(\"old\")", "8"]
+    );
+    assert_eq!(ifc.deprecation(point).unwrap(), Some("old"));
+    let x = DeclIndex::new(DeclSort::FIELD, 0);
+    assert_eq!(ifc.decl_attributes(x).count(), 0);
+    assert_eq!(ifc.decl_attributes(y).count(), 2);
+    assert_eq!(ifc.deprecation(x).unwrap(), None);
+    assert!(matches!(ifc.attr(tuple).unwrap(), Attr::Tuple(t) if t.len() == 2));
+    assert!(ifc.get_attr_string(cyclic).is_err());
+}
+
+#[test]
+fn function_parameters() {
+    let mut b = point_builder();
+    let int_ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 0);
+    let point = DeclIndex::new(DeclSort::SCOPE, 0);
+    let one = b.add_integer_literal(int_ty, 1);
+
+    // template<typename T> int scale(int factor, int = 1);
+    let param = |b: &mut IfcBuilder, name: &str, sort, position, initializer| {
+        let name = b.add_string(name);
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_parameter,
+            DeclParameter {
+                name,
+                ty: int_ty,
+                initializer,
+                sort,
+                position,
+                ..DeclParameter::new_zeroed()
+            },
+        )
+    };
+    let t = param(&mut b, "T", ParameterSort::TYPE, 1, ExprIndex(0));
+    let factor = param(&mut b, "factor", ParameterSort::OBJECT, 1, ExprIndex(0));
+    param(&mut b, "", ParameterSort::OBJECT, 2, one);
+
+    let template_params = IfcBuilder::push_entry(
+        &mut b.parts.chart_unilevel,
+        ChartUnilevel {
+            start: t,
+            cardinality: 1,
+            constraint: ExprIndex(0),
+        },
+    );
+    let func_params = IfcBuilder::push_entry(
+        &mut b.parts.chart_unilevel,
+        ChartUnilevel {
+            start: factor,
+            cardinality: 2,
+            constraint: ExprIndex(0),
+        },
+    );
+    let start = IfcBuilder::push_entry(
+        &mut b.parts.heap_chart,
+        ChartIndex::new(ChartSort::UNILEVEL, template_params),
+    );
+    IfcBuilder::push_entry(
+        &mut b.parts.heap_chart,
+        ChartIndex::new(ChartSort::UNILEVEL, func_params),
+    );
+    let multi = IfcBuilder::push_entry(
+        &mut b.parts.chart_multilevel,
+        ChartMultilevel {
+            start,
+            cardinality: 2,
+        },
+    );
+
+    let scale_name = b.add_identifier("scale");
+    for chart in [
+        ChartIndex::new(ChartSort::UNILEVEL, func_params),
+        ChartIndex::new(ChartSort::MULTILEVEL, multi),
+    ] {
+        IfcBuilder::push_entry(
+            &mut b.parts.decl_function,
+            DeclFunc {
+                name: scale_name,
+                type_: int_ty,
+                home_scope: point,
+                chart,
+                ..DeclFunc::new_zeroed()
+            },
+        );
+    }
+
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.validate().is_empty());
+    for func in ifc.decl_function().entries.iter() {
+        let params = ifc.function_parameters(func).unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name, "factor");
+        assert_eq!(params[0].ty, int_ty);
+        assert_eq!(params[0].default_argument, None);
+        assert_eq!(params[0].position, 1);
+        assert_eq!(params[1].name, "");
+        assert_eq!(params[1].default_argument, Some(one));
+        assert_eq!(params[1].position, 2);
+    }
+    let multi = ifc.decl_function().entry(1).unwrap().chart;
+    let levels = ifc.chart_levels(multi).unwrap();
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0].len(), 1);
+    assert_eq!(levels[1].len(), 2);
+
+    // A multilevel chart cannot contain another one.
+    let mut b = point_builder();
+    b.parts
+        .heap_chart
+        .push(ChartIndex::new(ChartSort::MULTILEVEL, 0));
+    b.parts.chart_multilevel.push(ChartMultilevel {
+        start: 0,
+        cardinality: 1,
+    });
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc
+        .chart_levels(ChartIndex::new(ChartSort::MULTILEVEL, 0))
+        .is_err());
+}
+
+#[test]
+fn raw_partition() {
+    let mut b = IfcBuilder::new();
+    b.add_raw_partition("src.line", 8, vec![0; 24]).unwrap();
+    assert!(b.add_raw_partition("src.line", 8, vec![0; 12]).is_err());
+    assert!(b.add_raw_partition("src.line", 0, Vec::new()).is_err());
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    let entry = &ifc.parts()["src.line"];
+    assert_eq!(entry.count, 3);
+    assert_eq!(entry.size, 8);
+}
+
+#[test]
+fn checksum() {
+    let mut data = point_builder().to_bytes();
+    let options = LoadOptions {
+        verify_checksum: true,
+        ..LoadOptions::default()
+    };
+
+    let ifc = Ifc::load_with_options(data.clone(), &options).unwrap();
+    ifc.verify_checksum().unwrap();
+    assert_eq!(ifc.file_header().checksum, Ifc::compute_checksum(&data));
+
+    // Corrupt a byte in the string table. Loading still works if we don't ask for verification.
+    let pos = data.windows(7).position(|w| w == b"point.h").unwrap();
+    data[pos] = b'P';
+    assert!(Ifc::load(data.clone()).is_ok());
+
+    let err = match Ifc::load_with_options(data, &options) {
+        Ok(_) => panic!("expected checksum verification to fail"),
+        Err(e) => e,
+    };
+    match err {
+        IfcError::ChecksumMismatch { expected, actual } => assert_ne!(expected, actual),
+        _ => panic!("expected ChecksumMismatch, got: {:?}", err),
+    }
+}
+
+#[test]
+fn load_errors() {
+    assert!(matches!(
+        Ifc::load(b"nope".to_vec()),
+        Err(IfcError::BadSignature)
+    ));
+    assert!(matches!(
+        Ifc::load(IFC_FILE_SIGNATURE.to_vec()),
+        Err(IfcError::TruncatedHeader)
+    ));
+
+    let ifc = load_point();
+    match ifc.decl_field().entry(10) {
+        Err(IfcError::BadEntryIndex {
+            partition,
+            index,
+            len,
+        }) => {
+            assert_eq!(partition, "decl.field");
+            assert_eq!(index, 10);
+            assert_eq!(len, 2);
+        }
+        other => panic!("expected BadEntryIndex, got: {:?}", other),
+    }
+    assert!(matches!(
+        ifc.get_string(0x10000),
+        Err(IfcError::BadString { offset: 0x10000 })
+    ));
+}
+
+#[test]
+fn borrowed_partitions() {
+    let data = point_builder().to_bytes();
+    let ifc = Ifc::from_bytes(&data).unwrap();
+    assert!(ifc.parts()["decl.field"].borrowed);
+    assert_eq!(ifc.decl_field().entries.len(), 2);
+    assert_eq!(
+        ifc.decl_field().entries.as_ptr() as usize,
+        data.as_ptr() as usize + ifc.parts()["decl.field"].part_range.start
+    );
+
+    // Shift the file so that the partitions are misaligned. They are copied instead.
+    let mut unaligned = vec![0u8; data.len() + 1];
+    unaligned[1..].copy_from_slice(&data);
+    let ifc = Ifc::from_bytes(&unaligned[1..]).unwrap();
+    assert!(!ifc.parts()["decl.field"].borrowed);
+    let point = ifc.decl_scope().entry(0).unwrap();
+    assert_eq!(ifc.get_name_string(point.name).unwrap(), "POINT");
+}
+
+#[test]
+fn extended_records_are_copied() {
+    // Records that are larger than expected (e.g. from a newer compiler) are truncated.
+    let mut b = IfcBuilder::new();
+    let mut data = Vec::new();
+    for start in [5u32, 7] {
+        data.extend_from_slice(
+            ScopeDescriptor {
+                start,
+                cardinality: 1,
+            }
+            .as_bytes(),
+        );
+        data.extend_from_slice(&[0xcc; 4]);
+    }
+    b.add_raw_partition("scope.desc", size_of::<ScopeDescriptor>() + 4, data)
+        .unwrap();
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+
+    assert!(!ifc.parts()["scope.desc"].borrowed);
+    assert_eq!(ifc.get_scope_descriptor(2).unwrap().start, 7);
+}
+
+#[test]
+fn lazy_load() {
+    // Extended records can't be read in place, so they are converted on first access.
+    let mut b = point_builder();
+    let desc: Vec<u8> = b
+        .parts
+        .scope_desc
+        .drain(..)
+        .flat_map(|d| {
+            let mut record = d.as_bytes().to_vec();
+            record.extend_from_slice(&[0xcc; 4]);
+            record
+        })
+        .collect();
+    b.add_raw_partition("scope.desc", size_of::<ScopeDescriptor>() + 4, desc)
+        .unwrap();
+
+    let options = LoadOptions {
+        lazy: true,
+        ..LoadOptions::default()
+    };
+    let ifc = Ifc::load_with_options(b.to_bytes(), &options).unwrap();
+    assert!(!ifc.parts()["scope.desc"].borrowed);
+
+    let ifc = std::sync::Arc::new(ifc);
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let ifc = ifc.clone();
+            std::thread::spawn(move || ifc.iter_scope(ifc.global_scope()).unwrap().count())
+        })
+        .collect();
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 1);
+    }
+    assert_eq!(ifc.iter_scope(1).unwrap().count(), 3);
+}
+
+#[test]
+fn validate() {
+    assert!(load_point().validate().is_empty());
+
+    let mut b = point_builder();
+    b.parts.decl_field[1].ty = TypeIndex::new(TypeSort::FUNDAMENTAL, 99);
+    b.parts.decl_field[1].home_scope = DeclIndex::new(DeclSort::TEMPLATE, 0);
+    b.parts.decl_bitfield[0].name = 0x10000;
+    b.parts.scope_desc[0].cardinality = 10;
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+
+    let diags: Vec<String> = ifc
+        .validate()
+        .iter()
+        .map(|d| format!("{}.{}[{}]", d.partition, d.field, d.entry))
+        .collect();
+    assert_eq!(
+        diags,
+        [
+            "scope.desc.start[0]",
+            "decl.field.ty[1]",
+            "decl.field.home_scope[1]",
+            "decl.bitfield.name[0]",
+        ]
+    );
+}
+
+#[test]
+fn malformed_files() {
+    let seed = point_builder().to_bytes();
+    let header = |data: &mut Vec<u8>, f: &dyn Fn(&mut FileHeader)| {
+        let mut h = FileHeader::read_from_prefix(&data[4..]).unwrap();
+        f(&mut h);
+        data[4..4 + size_of::<FileHeader>()].copy_from_slice(h.as_bytes());
+    };
+
+    // Table of contents past the end of the file.
+    let mut data = seed.clone();
+    header(&mut data, &|h| h.toc = u32::MAX);
+    assert!(matches!(
+        Ifc::load(data),
+        Err(IfcError::PartitionOutOfBounds { .. })
+    ));
+
+    // Partition with records of size zero.
+    let mut data = seed.clone();
+    let toc = FileHeader::read_from_prefix(&data[4..]).unwrap().toc as usize;
+    data[toc + 12..toc + 16].copy_from_slice(&0u32.to_le_bytes());
+    assert!(matches!(
+        Ifc::load(data),
+        Err(IfcError::BadEntitySize { .. })
+    ));
+
+    // No global scope.
+    let mut data = seed.clone();
+    header(&mut data, &|h| h.global_scope = 0);
+    let ifc = Ifc::load(data).unwrap();
+    assert!(matches!(
+        ifc.iter_scope(ifc.global_scope()),
+        Err(IfcError::BadScope { scope: 0 })
+    ));
+    walk(&ifc);
+
+    // A pointer type that points to itself.
+    let mut b = point_builder();
+    let ptr = TypeIndex::new(TypeSort::POINTER, 0);
+    b.parts.type_pointer.push(ptr);
+    b.parts.decl_field[0].ty = ptr;
+    let ifc = Ifc::load(b.to_bytes()).unwrap();
+    assert!(ifc.get_type_string(ptr).is_err());
+    walk(&ifc);
+}
+
+/// Randomly corrupts a valid file and checks that loading and walking it does not panic.
+/// The `fuzz` directory contains a fuzz target that does the same thing, for longer.
+#[test]
+fn mutated_files_do_not_panic() {
+    let seed = seed();
+
+    // xorshift, so that failures are reproducible.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..2000 {
+        let mut data = seed.clone();
+        for _ in 0..1 + next() % 4 {
+            let pos = (next() % data.len() as u64) as usize;
+            data[pos] = next() as u8;
+        }
+        if next() % 8 == 0 {
+            data.truncate((next() % data.len() as u64) as usize);
+        }
+
+        load_and_walk(&data);
+    }
+}
+
+#[test]
+fn arity_and_variadic() {
+    // `#define F(a, b, ...)`
+    let variadic = ArityAndVariadic(0x8000_0002);
+    assert_eq!(variadic.arity(), 2);
+    assert!(variadic.is_variadic());
+    // `#define G(a, b)`, with an arity that uses the high bits.
+    let fixed = ArityAndVariadic(0x0800_0002);
+    assert_eq!(fixed.arity(), 0x0800_0002);
+    assert!(!fixed.is_variadic());
+}
@@ -0,0 +1,262 @@
+//! Comparing types by their structure, within and across IFC files
+
+use super::*;
+
+/// Identifies an interned `TypeKey`. Two ids from the same `TypeKeyInterner` are equal if and
+/// only if their keys are equal, so ids are cheap to hash and compare.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct TypeKeyId(u32);
+
+/// A canonical form of a type, which does not depend on the `TypeIndex` values of any
+/// particular IFC. Two types have equal keys if they are spelled the same way after aliases
+/// are expanded, even if they come from different IFC files, so keys can be hashed to find
+/// the same type in several units.
+///
+/// Keys are interned by a `TypeKeyInterner`, and refer to the types that they are built from
+/// by `TypeKeyId`. Types from several IFCs can only be compared if their keys were interned by
+/// the same `TypeKeyInterner`.
+///
+/// Classes and enumerations are identified by their qualified names, so two unnamed classes
+/// that the compiler gave the same name are the same. Types that depend on template
+/// parameters are not decomposed; they are kept as their C++ spelling (see `Other`).
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum TypeKey {
+    /// `int` and `signed int` are the same type, so their keys are both `TypeSign::PLAIN`.
+    Fundamental {
+        basis: TypeBasis,
+        precision: TypePrecision,
+        sign: TypeSign,
+    },
+    /// A class, enumeration or other declaration, by its qualified name, e.g. `ns::_GUID`.
+    Designated(String),
+    Pointer(TypeKeyId),
+    LvalueReference(TypeKeyId),
+    RvalueReference(TypeKeyId),
+    PointerToMember {
+        class: TypeKeyId,
+        member: TypeKeyId,
+    },
+    /// An array. The extent is `None` for an array of unknown bound, as in `int a[]`.
+    Array {
+        element: TypeKeyId,
+        extent: Option<u64>,
+    },
+    /// A qualified type. The qualifiers are never empty, and the type is never itself
+    /// qualified: `const` applied to `volatile T` gives one key for `const volatile T`.
+    Qualified {
+        ty: TypeKeyId,
+        qualifiers: Qualifiers,
+    },
+    Unaligned(TypeKeyId),
+    /// A function or, if `class` is set, a non-static member function. `params` is a `Tuple`.
+    Function {
+        target: TypeKeyId,
+        params: TypeKeyId,
+        class: Option<TypeKeyId>,
+        convention: CallingConvention,
+        eh_spec: NoexceptSort,
+        traits: FunctionTypeTraits,
+    },
+    Tuple(Vec<TypeKeyId>),
+    /// Any other type, such as a template parameter, by its C++ spelling.
+    Other(String),
+}
+
+/// Stores each distinct `TypeKey` once, and gives it a `TypeKeyId`.
+#[derive(Default)]
+pub struct TypeKeyInterner {
+    keys: Vec<TypeKey>,
+    ids: HashMap<TypeKey, TypeKeyId>,
+}
+
+impl TypeKeyInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of `key`, adding it if it has not been seen before.
+    pub fn intern(&mut self, key: TypeKey) -> TypeKeyId {
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+        let id = TypeKeyId(self.keys.len() as u32);
+        self.keys.push(key.clone());
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// Returns the id of `key`, if it has been interned.
+    pub fn find(&self, key: &TypeKey) -> Option<TypeKeyId> {
+        self.ids.get(key).copied()
+    }
+
+    /// The key that `id` was given to.
+    pub fn get(&self, id: TypeKeyId) -> &TypeKey {
+        &self.keys[id.0 as usize]
+    }
+
+    /// The number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// Computes the canonical key of a type, for comparing types within and across IFCs.
+    pub fn type_key(&self, ty: TypeIndex, keys: &mut TypeKeyInterner) -> Result<TypeKeyId> {
+        self.type_key_at_depth(ty, keys, 0)
+    }
+
+    pub(crate) fn type_key_at_depth(
+        &self,
+        ty: TypeIndex,
+        keys: &mut TypeKeyInterner,
+        depth: u32,
+    ) -> Result<TypeKeyId> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("type {:?} is nested too deeply (is it cyclic?)", ty);
+        }
+
+        let i = ty.index();
+        let key = match ty.tag() {
+            TypeSort::FUNDAMENTAL => {
+                let fundamental = self.type_fundamental().entry(i)?;
+                let sign = match (fundamental.basis, fundamental.sign) {
+                    (TypeBasis::INT, TypeSign::SIGNED) => TypeSign::PLAIN,
+                    (_, sign) => sign,
+                };
+                TypeKey::Fundamental {
+                    basis: fundamental.basis,
+                    precision: fundamental.precision,
+                    sign,
+                }
+            }
+
+            TypeSort::DESIGNATED => {
+                let decl = *self.type_designated().entry(i)?;
+                match decl.tag() {
+                    DeclSort::ALIAS => {
+                        let alias = self.decl_alias().entry(decl.index())?;
+                        return self.type_key_at_depth(alias.aliasee, keys, depth + 1);
+                    }
+                    // The name of the entity is in another unit. Use a `Workspace` to find it.
+                    DeclSort::REFERENCE => {
+                        bail!("type {:?} refers to a declaration in another unit", ty)
+                    }
+                    _ => TypeKey::Designated(
+                        self.qualified_name_at_depth(decl, depth + 1)?.to_string(),
+                    ),
+                }
+            }
+
+            TypeSort::POINTER => TypeKey::Pointer(self.type_key_at_depth(
+                *self.type_pointer().entry(i)?,
+                keys,
+                depth + 1,
+            )?),
+            TypeSort::LVALUE_REFERENCE => TypeKey::LvalueReference(self.type_key_at_depth(
+                *self.type_lvalue_reference().entry(i)?,
+                keys,
+                depth + 1,
+            )?),
+            TypeSort::RVALUE_REFERENCE => TypeKey::RvalueReference(self.type_key_at_depth(
+                *self.type_rvalue_reference().entry(i)?,
+                keys,
+                depth + 1,
+            )?),
+            TypeSort::UNALIGNED => TypeKey::Unaligned(self.type_key_at_depth(
+                *self.type_unaligned().entry(i)?,
+                keys,
+                depth + 1,
+            )?),
+
+            TypeSort::POINTER_TO_MEMBER => {
+                let ptm = self.type_pointer_to_member().entry(i)?;
+                TypeKey::PointerToMember {
+                    class: self.type_key_at_depth(ptm.scope, keys, depth + 1)?,
+                    member: self.type_key_at_depth(ptm.member, keys, depth + 1)?,
+                }
+            }
+
+            TypeSort::ARRAY => {
+                let array = self.type_array().entry(i)?;
+                let extent = if array.extent.tag() == ExprSort::EMPTY || array.extent.0 == 0 {
+                    None
+                } else {
+                    match self.eval_const_at_depth(array.extent, depth + 1)?.to_u64() {
+                        Some(extent) => Some(extent),
+                        None => bail!("array {:?} has an extent that is not an integer", ty),
+                    }
+                };
+                TypeKey::Array {
+                    element: self.type_key_at_depth(array.element, keys, depth + 1)?,
+                    extent,
+                }
+            }
+
+            TypeSort::QUALIFIED => {
+                let qualified = self.type_qualified().entry(i)?;
+                let id = self.type_key_at_depth(qualified.unqualified_type, keys, depth + 1)?;
+                let (id, qualifiers) = match *keys.get(id) {
+                    TypeKey::Qualified { ty, qualifiers } => {
+                        (ty, qualifiers | qualified.qualifiers)
+                    }
+                    _ => (id, qualified.qualifiers),
+                };
+                if qualifiers.is_empty() {
+                    return Ok(id);
+                }
+                TypeKey::Qualified { ty: id, qualifiers }
+            }
+
+            TypeSort::FUNCTION => {
+                let func = self.type_function().entry(i)?;
+                TypeKey::Function {
+                    target: self.type_key_at_depth(func.target, keys, depth + 1)?,
+                    params: self.type_keys(func.source, keys, depth + 1)?,
+                    class: None,
+                    convention: func.convention,
+                    eh_spec: func.eh_spec.sort,
+                    traits: func.traits,
+                }
+            }
+
+            TypeSort::METHOD => {
+                let method = self.type_method().entry(i)?;
+                TypeKey::Function {
+                    target: self.type_key_at_depth(method.target, keys, depth + 1)?,
+                    params: self.type_keys(method.source, keys, depth + 1)?,
+                    class: Some(self.type_key_at_depth(method.scope, keys, depth + 1)?),
+                    convention: method.convention,
+                    eh_spec: method.eh_spec.sort,
+                    traits: method.traits,
+                }
+            }
+
+            TypeSort::TUPLE => return self.type_keys(ty, keys, depth + 1),
+
+            // The C++ spelling does not contain any indexes, so it is the same in every IFC.
+            _ => TypeKey::Other(self.get_type_string_at_depth(ty, depth + 1)?),
+        };
+        Ok(keys.intern(key))
+    }
+
+    /// The key of a tuple type. A null type has no elements, and any other type is a tuple
+    /// of one.
+    fn type_keys(
+        &self,
+        ty: TypeIndex,
+        keys: &mut TypeKeyInterner,
+        depth: u32,
+    ) -> Result<TypeKeyId> {
+        let mut elements = Vec::new();
+        for element in self.iter_type_tuple(ty)? {
+            elements.push(self.type_key_at_depth(element, keys, depth)?);
+        }
+        Ok(keys.intern(TypeKey::Tuple(elements)))
+    }
+}
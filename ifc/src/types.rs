@@ -26,7 +26,7 @@ pub enum TypeSort {
     ARRAY = 0x0C,
     DESIGNATED = 0x02,
     TYPENAME = 0x0D,
-    DEDUCED = 0x03,
+    DEDUCED = 0x03, // `type.tor`, called `TypeSort::Tor` in the spec
     QUALIFIED = 0x0E,
     SYNTACTIC = 0x04,
     BASE = 0x0F,
@@ -200,19 +200,21 @@ bitflags! {
     }
 }
 
-impl Ifc {
+impl<'data> Ifc<'data> {
     pub fn is_void(&self, t: TypeIndex) -> bool {
         if t.tag() != TypeSort::FUNDAMENTAL {
             return false;
         }
 
-        let tf = self.type_fundamental().entry(t.index()).unwrap();
-        tf.basis == TypeBasis::VOID
+        match self.type_fundamental().entry(t.index()) {
+            Ok(tf) => tf.basis == TypeBasis::VOID,
+            Err(_) => false,
+        }
     }
 
     pub fn as_fundamental_type(&self, t: TypeIndex) -> Option<&FundamentalType> {
         if t.tag() == TypeSort::FUNDAMENTAL {
-            Some(self.type_fundamental().entry(t.index()).unwrap())
+            self.type_fundamental().entry(t.index()).ok()
         } else {
             None
         }
@@ -226,3 +228,82 @@ pub struct TypeArray {
     pub element: TypeIndex,
     pub extent: ExprIndex,
 }
+
+// 9.1.4
+// type.tor
+/// The type of a constructor or destructor. `TypeSort::DEDUCED` is called `TypeSort::Tor` in
+/// the spec.
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct TorType {
+    pub source: TypeIndex,
+    pub eh_spec: NoexceptSpecification,
+    pub convention: CallingConvention,
+    pub padding: [u8; 3],
+}
+
+// 9.1.6
+// type.expansion
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct ExpansionType {
+    pub pack: TypeIndex,
+    pub mode: ExpansionMode,
+    pub padding: [u8; 3],
+}
+
+#[c_enum(storage = "u8")]
+pub enum ExpansionMode {
+    FULL,
+    PARTIAL,
+}
+
+// 9.1.8
+// type.pointer-to-member
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct PointerToMemberType {
+    /// The enclosing class type
+    pub scope: TypeIndex,
+    /// The type of the member
+    pub member: TypeIndex,
+}
+
+// 9.1.12
+// type.nonstatic-member-function
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct MethodType {
+    /// Return type of the method type
+    pub target: TypeIndex,
+    /// Parameter type list, with the same encoding as `FunctionType::source`.
+    pub source: TypeIndex,
+    /// The enclosing class type
+    pub scope: TypeIndex,
+    pub eh_spec: NoexceptSpecification,
+    pub convention: CallingConvention,
+    pub traits: FunctionTypeTraits,
+    pub padding: [u8; 2],
+}
+
+// 9.1.18
+// type.placeholder
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct PlaceholderType {
+    pub constraint: ExprIndex,
+    /// Either `TypeBasis::AUTO` or `TypeBasis::DECLTYPE_AUTO`.
+    pub basis: TypeBasis,
+    pub padding: [u8; 3],
+    /// The deduced type, if not null.
+    pub elaboration: TypeIndex,
+}
+
+// 9.1.20
+// type.forall
+#[repr(C)]
+#[derive(AsBytes, FromBytes, Clone, Debug)]
+pub struct ForallType {
+    pub chart: ChartIndex,
+    pub subject: TypeIndex,
+}
@@ -0,0 +1,932 @@
+//! Validating the contents of an IFC
+//!
+//! The accessors on `Ifc` assume that the indices stored in the file are well-formed. Tools
+//! that read IFCs from untrusted sources (or that want to fail early rather than in the middle
+//! of code generation) can call `Ifc::validate` first.
+
+use super::*;
+
+/// A problem found by `Ifc::validate`.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// The partition that contains the bad value, e.g. `decl.field`, or `header` for the file
+    /// header.
+    pub partition: &'static str,
+    /// The index of the record within `partition`.
+    pub entry: usize,
+    /// The field of the record that contains the bad value, e.g. `home_scope`.
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            fmt,
+            "{}[{}].{}: {}",
+            self.partition, self.entry, self.field, self.message
+        )
+    }
+}
+
+// These tables map the tag of an abstract reference to the partition that its index points
+// into. They are indexed by the numeric value of the tag, and they cover every sort defined
+// by the spec, including sorts that this crate does not decode yet. `None` means that the sort
+// has no partition, so the index is not checked.
+
+const DECL_PARTITIONS: [Option<&str>; 32] = [
+    Some("decl.vendor-extension"),
+    Some("decl.enumerator"),
+    Some("decl.variable"),
+    Some("decl.parameter"),
+    Some("decl.field"),
+    Some("decl.bitfield"),
+    Some("decl.scope"),
+    Some("decl.enum"),
+    Some("decl.alias"),
+    Some("decl.temploid"),
+    Some("decl.template"),
+    Some("decl.partial-specialization"),
+    Some("decl.specialization"),
+    None,
+    Some("decl.concept"),
+    Some("decl.function"),
+    Some("decl.method"),
+    Some("decl.constructor"),
+    Some("decl.inherited-constructor"),
+    Some("decl.destructor"),
+    Some("decl.reference"),
+    Some("decl.using-declaration"),
+    Some("decl.using-directive"),
+    Some("decl.friend"),
+    Some("decl.expansion"),
+    Some("decl.deduction-guide"),
+    Some("decl.barren"),
+    Some("decl.tuple"),
+    Some("decl.syntax-tree"),
+    Some("decl.intrinsic"),
+    Some("decl.property"),
+    Some("decl.segment"),
+];
+
+const TYPE_PARTITIONS: [Option<&str>; 22] = [
+    Some("type.vendor-extension"),
+    Some("type.fundamental"),
+    Some("type.designated"),
+    Some("type.tor"),
+    Some("type.syntactic"),
+    Some("type.expansion"),
+    Some("type.pointer"),
+    Some("type.pointer-to-member"),
+    Some("type.lvalue-reference"),
+    Some("type.rvalue-reference"),
+    Some("type.function"),
+    Some("type.nonstatic-member-function"),
+    Some("type.array"),
+    Some("type.typename"),
+    Some("type.qualified"),
+    Some("type.base"),
+    Some("type.decltype"),
+    Some("type.placeholder"),
+    Some("type.tuple"),
+    Some("type.forall"),
+    Some("type.unaligned"),
+    Some("type.syntax-tree"),
+];
+
+const EXPR_PARTITIONS: [Option<&str>; 61] = [
+    Some("expr.vendor-extension"),
+    Some("expr.empty"),
+    Some("expr.literal"),
+    Some("expr.lambda"),
+    Some("expr.type"),
+    Some("expr.decl"),
+    Some("expr.unresolved"),
+    Some("expr.template-id"),
+    Some("expr.unqualified-id"),
+    Some("expr.simple-identifier"),
+    Some("expr.pointer"),
+    Some("expr.qualified-name"),
+    Some("expr.path"),
+    Some("expr.read"),
+    Some("expr.monad"),
+    Some("expr.dyad"),
+    Some("expr.triad"),
+    Some("expr.strings"),
+    Some("expr.temporary"),
+    Some("expr.call"),
+    Some("expr.member-initializer"),
+    Some("expr.member-access"),
+    Some("expr.inheritance-path"),
+    Some("expr.initializer-list"),
+    Some("expr.cast"),
+    Some("expr.condition"),
+    Some("expr.expression-list"),
+    Some("expr.sizeof-type"),
+    Some("expr.alignof"),
+    None, // New
+    None, // Delete
+    Some("expr.typeid"),
+    Some("expr.destructor-call"),
+    Some("expr.syntax-tree"),
+    Some("expr.function-string"),
+    Some("expr.compound-string"),
+    Some("expr.string-sequence"),
+    Some("expr.initializer"),
+    Some("expr.requires"),
+    Some("expr.unary-fold"),
+    Some("expr.binary-fold"),
+    Some("expr.hierarchy-conversion"),
+    Some("expr.product-type-value"),
+    Some("expr.sum-type-value"),
+    Some("expr.class-subobject-value"),
+    Some("expr.array-value"),
+    Some("expr.dynamic-dispatch"),
+    Some("expr.virtual-function-conversion"),
+    Some("expr.placeholder"),
+    Some("expr.expansion"),
+    Some("expr.generic"),
+    Some("expr.tuple"),
+    Some("expr.nullptr"),
+    Some("expr.this"),
+    Some("expr.template-reference"),
+    Some("expr.push-state"),
+    Some("expr.type-trait"),
+    Some("expr.designated-init"),
+    Some("expr.packed-template-arguments"),
+    Some("expr.tokens"),
+    Some("expr.assign-initializer"),
+];
+
+// Identifiers are stored in the string table, so they are checked separately.
+const NAME_PARTITIONS: [Option<&str>; 8] = [
+    None,
+    Some("name.operator"),
+    Some("name.conversion"),
+    Some("name.literal"),
+    Some("name.template"),
+    Some("name.specialization"),
+    Some("name.source-file"),
+    Some("name.guide"),
+];
+
+const ATTR_PARTITIONS: [Option<&str>; 9] = [
+    None,
+    Some("attr.basic"),
+    Some("attr.scoped"),
+    Some("attr.labeled"),
+    Some("attr.called"),
+    Some("attr.expanded"),
+    Some("attr.factored"),
+    Some("attr.elaborated"),
+    Some("attr.tuple"),
+];
+
+const CHART_PARTITIONS: [Option<&str>; 3] =
+    [None, Some("chart.unilevel"), Some("chart.multilevel")];
+
+const FORM_PARTITIONS: [Option<&str>; 15] = [
+    Some("pp.ident"),
+    Some("pp.num"),
+    Some("pp.char"),
+    Some("pp.string"),
+    Some("pp.op"),
+    Some("pp.key"),
+    Some("pp.space"),
+    Some("pp.param"),
+    Some("pp.to-string"),
+    Some("pp.catenate"),
+    Some("pp.pragma"),
+    Some("pp.header"),
+    Some("pp.paren"),
+    Some("pp.tuple"),
+    Some("pp.junk"),
+];
+
+struct Validator<'a, 'data> {
+    ifc: &'a Ifc<'data>,
+    diags: Vec<Diagnostic>,
+    partition: &'static str,
+    entry: usize,
+}
+
+impl<'a, 'data> Validator<'a, 'data> {
+    fn report(&mut self, field: &'static str, message: String) {
+        self.diags.push(Diagnostic {
+            partition: self.partition,
+            entry: self.entry,
+            field,
+            message,
+        });
+    }
+
+    /// Calls `f` for each record in `part`. Diagnostics reported by `f` refer to that record.
+    fn records<T>(&mut self, part: Part<'a, T>, mut f: impl FnMut(&mut Self, &T)) {
+        self.partition = part.part_name;
+        for (i, record) in part.entries.iter().enumerate() {
+            self.entry = i;
+            f(self, record);
+        }
+    }
+
+    /// Checks that `range` is a valid range of entries in the partition named `partition`.
+    fn range(&mut self, field: &'static str, partition: &str, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        match self.ifc.parts_map.get(partition) {
+            Some(part) if range.end <= part.count as u64 => {}
+            Some(part) => self.report(
+                field,
+                format!(
+                    "entries {:?} are out of range for partition '{}' (len {})",
+                    range, partition, part.count
+                ),
+            ),
+            None => self.report(
+                field,
+                format!(
+                    "refers to partition '{}', which is not present in the file",
+                    partition
+                ),
+            ),
+        }
+    }
+
+    fn sequence(&mut self, field: &'static str, partition: &str, start: Index, count: Cardinality) {
+        self.range(field, partition, start as u64..start as u64 + count as u64);
+    }
+
+    /// Checks an abstract reference, given the table that maps its tag to a partition.
+    fn tagged(
+        &mut self,
+        field: &'static str,
+        partitions: &[Option<&str>],
+        value: &dyn Debug,
+        tag: u32,
+        index: u32,
+    ) {
+        match partitions.get(tag as usize) {
+            Some(Some(partition)) => self.sequence(field, partition, index, 1),
+            Some(None) => {}
+            None => self.report(field, format!("invalid sort in {:?}", value)),
+        }
+    }
+
+    fn decl(&mut self, field: &'static str, decl: DeclIndex) {
+        if decl.0 != 0 {
+            self.tagged(field, &DECL_PARTITIONS, &decl, decl.tag().0, decl.index());
+        }
+    }
+
+    fn ty(&mut self, field: &'static str, ty: TypeIndex) {
+        if !ty.is_null() {
+            self.tagged(field, &TYPE_PARTITIONS, &ty, ty.tag().0, ty.index());
+        }
+    }
+
+    fn expr(&mut self, field: &'static str, expr: ExprIndex) {
+        if expr.0 != 0 {
+            self.tagged(field, &EXPR_PARTITIONS, &expr, expr.tag().0, expr.index());
+        }
+    }
+
+    fn form(&mut self, field: &'static str, form: FormIndex) {
+        self.tagged(field, &FORM_PARTITIONS, &form, form.tag().0, form.index());
+    }
+
+    fn name(&mut self, field: &'static str, name: NameIndex) {
+        if name.tag() == NameSort::IDENTIFIER {
+            self.text(field, name.index());
+        } else {
+            self.tagged(field, &NAME_PARTITIONS, &name, name.tag().0, name.index());
+        }
+    }
+
+    fn chart(&mut self, field: &'static str, chart: ChartIndex) {
+        self.tagged(
+            field,
+            &CHART_PARTITIONS,
+            &chart,
+            chart.tag().0,
+            chart.index(),
+        );
+    }
+
+    fn attr(&mut self, field: &'static str, attr: AttrIndex) {
+        self.tagged(field, &ATTR_PARTITIONS, &attr, attr.tag().0, attr.index());
+    }
+
+    fn word(&mut self, field: &'static str, word: &Word) {
+        if matches!(
+            word.sort,
+            WordSort::IDENTIFIER | WordSort::KEYWORD | WordSort::LITERAL
+        ) {
+            self.text(field, word.index);
+        }
+    }
+
+    fn lit(&mut self, field: &'static str, lit: LitIndex) {
+        match lit.tag() {
+            LiteralSort::IMMEDIATE => {}
+            LiteralSort::INTEGER => self.sequence(field, "const.i64", lit.index(), 1),
+            LiteralSort::FLOATING_POINT => self.sequence(field, "const.f64", lit.index(), 1),
+            _ => self.report(field, format!("invalid sort in {:?}", lit)),
+        }
+    }
+
+    /// Checks a 1-based index into `scope.desc`. Zero means "no scope".
+    fn scope(&mut self, field: &'static str, scope: ScopeIndex) {
+        if scope != 0 {
+            self.sequence(field, "scope.desc", scope - 1, 1);
+        }
+    }
+
+    /// Checks that `text` is the offset of a NUL-terminated UTF-8 string. Zero means "no
+    /// string".
+    fn text(&mut self, field: &'static str, text: TextOffset) {
+        if text != 0 {
+            if let Err(e) = self.ifc.get_string(text) {
+                self.report(field, e.to_string());
+            }
+        }
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// Checks that every abstract reference (`DeclIndex`, `TypeIndex`, `ExprIndex`,
+    /// `NameIndex`, `FormIndex`, `TextOffset`, etc.) in the partitions that this crate decodes
+    /// has a valid tag and points to an entry that exists, and that every sequence (such as the
+    /// members of a scope) is in range.
+    ///
+    /// Returns the problems that were found. If the result is empty, then the accessors on
+    /// `Ifc` will not fail or panic because of a bad index.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut v = Validator {
+            ifc: self,
+            diags: Vec::new(),
+            partition: "header",
+            entry: 0,
+        };
+
+        v.text("src_path", self.file_header.src_path);
+        match self.unit_sort() {
+            UnitSort::PRIMARY | UnitSort::PARTITION | UnitSort::EXPORTED_TU => {
+                v.text("unit", self.file_header.unit >> 3)
+            }
+            UnitSort::SOURCE | UnitSort::HEADER => {}
+            sort => v.report("unit", format!("unknown unit sort {:?}", sort)),
+        }
+        for part in [self.module_imported(), self.module_exported()] {
+            v.records(part, |v, r| {
+                v.text("owner", r.owner);
+                v.text("partition", r.partition);
+            });
+        }
+        if self.file_header.global_scope == 0 {
+            v.report(
+                "global_scope",
+                "file does not have a global scope".to_string(),
+            );
+        }
+        v.scope("global_scope", self.file_header.global_scope);
+
+        // Scopes
+        v.records(self.scope_desc(), |v, r| {
+            v.sequence("start", "scope.member", r.start, r.cardinality)
+        });
+        v.records(self.scope_member(), |v, &r| v.decl("decl", r));
+
+        // Declarations
+        v.records(self.decl_alias(), |v, r| {
+            v.text("name", r.name);
+            v.ty("type_", r.type_);
+            v.decl("home_scope", r.home_scope);
+            v.ty("aliasee", r.aliasee);
+        });
+        for part in [self.decl_function(), self.decl_method()] {
+            v.records(part, |v, r| {
+                v.name("name", r.name);
+                v.ty("type_", r.type_);
+                v.decl("home_scope", r.home_scope);
+                v.chart("chart", r.chart);
+            });
+        }
+        v.records(self.decl_scope(), |v, r| {
+            v.name("name", r.name);
+            v.ty("ty", r.ty);
+            v.ty("base", r.base);
+            v.scope("initializer", r.initializer);
+            v.decl("home_scope", r.home_scope);
+            v.expr("alignment", r.alignment);
+        });
+        v.records(self.decl_field(), |v, r| {
+            v.text("name", r.name);
+            v.ty("ty", r.ty);
+            v.decl("home_scope", r.home_scope);
+            v.expr("initializer", r.initializer);
+            v.expr("alignment", r.alignment);
+        });
+        v.records(self.decl_enum(), |v, r| {
+            v.text("name", r.name);
+            v.ty("ty", r.ty);
+            v.ty("base", r.base);
+            v.sequence(
+                "initializer",
+                "decl.enumerator",
+                r.initializer.start,
+                r.initializer.cardinality,
+            );
+            v.decl("home_scope", r.home_scope);
+            v.expr("alignment", r.alignment);
+        });
+        v.records(self.decl_enumerator(), |v, r| {
+            v.text("name", r.name);
+            v.ty("ty", r.ty);
+            v.expr("initializer", r.initializer);
+        });
+        v.records(self.decl_var(), |v, r| {
+            v.name("name", r.name);
+            v.ty("ty", r.ty);
+            v.decl("home_scope", r.home_scope);
+            v.expr("initializer", r.initializer);
+            v.expr("alignment", r.alignment);
+        });
+        v.records(self.decl_bitfield(), |v, r| {
+            v.text("name", r.name);
+            v.ty("ty", r.ty);
+            v.decl("home_scope", r.home_scope);
+            v.expr("width", r.width);
+            v.expr("initializer", r.initializer);
+        });
+        v.records(self.decl_parameter(), |v, r| {
+            v.text("name", r.name);
+            v.ty("ty", r.ty);
+            v.expr("constraint", r.constraint);
+            v.expr("initializer", r.initializer);
+        });
+        v.records(self.decl_temploid(), |v, r| {
+            v.decl("entity.decl", r.entity.decl);
+            v.chart("chart", r.chart);
+        });
+        v.records(self.decl_template(), |v, r| {
+            v.name("name", r.name);
+            v.decl("home_scope", r.home_scope);
+            v.chart("chart", r.chart);
+            v.decl("entity.decl", r.entity.decl);
+            v.ty("ty", r.ty);
+        });
+        v.records(self.decl_partial_specialization(), |v, r| {
+            v.name("name", r.name);
+            v.decl("home_scope", r.home_scope);
+            v.chart("chart", r.chart);
+            v.decl("entity.decl", r.entity.decl);
+        });
+        v.records(self.decl_specialization(), |v, r| v.decl("decl", r.decl));
+        v.records(self.decl_concept(), |v, r| {
+            v.text("name", r.name);
+            v.decl("home_scope", r.home_scope);
+            v.chart("chart", r.chart);
+            v.ty("ty", r.ty);
+            v.expr("constraint", r.constraint);
+        });
+        v.records(self.decl_constructor(), |v, r| {
+            v.text("name", r.name);
+            v.ty("type_", r.type_);
+            v.decl("home_scope", r.home_scope);
+            v.chart("chart", r.chart);
+        });
+        v.records(self.decl_inherited_constructor(), |v, r| {
+            v.text("name", r.name);
+            v.ty("type_", r.type_);
+            v.decl("home_scope", r.home_scope);
+            v.chart("chart", r.chart);
+            v.decl("base_ctor", r.base_ctor);
+        });
+        v.records(self.decl_destructor(), |v, r| {
+            v.text("name", r.name);
+            v.decl("home_scope", r.home_scope);
+        });
+        v.records(self.decl_reference(), |v, r| {
+            v.text("unit.owner", r.unit.owner);
+            v.text("unit.partition", r.unit.partition);
+        });
+        v.records(self.decl_using_declaration(), |v, r| {
+            v.name("name", r.name);
+            v.decl("home_scope", r.home_scope);
+            v.decl("resolution", r.resolution);
+            v.expr("parent", r.parent);
+            v.text("name2", r.name2);
+        });
+        v.records(self.decl_friend(), |v, &r| v.expr("entity", r));
+        v.records(self.decl_expansion(), |v, r| v.decl("operand", r.operand));
+        v.records(self.decl_deduction_guide(), |v, r| {
+            v.name("name", r.name);
+            v.ty("specialization", r.specialization);
+        });
+        v.records(self.decl_tuple(), |v, r| {
+            v.sequence("start", "heap.decl", r.start, r.cardinality)
+        });
+        v.records(self.decl_intrinsic(), |v, r| {
+            v.text("name", r.name);
+            v.ty("type_", r.type_);
+            v.decl("home_scope", r.home_scope);
+        });
+        v.records(self.decl_property(), |v, r| {
+            v.decl("member", r.member);
+            v.text("getter", r.getter);
+            v.text("setter", r.setter);
+        });
+        v.records(self.decl_segment(), |v, r| {
+            v.text("name", r.name);
+            v.text("id", r.id);
+        });
+        v.records(self.heap_decl(), |v, &r| v.decl("decl", r));
+
+        // Types
+        v.records(self.heap_type(), |v, &r| v.ty("type", r));
+        v.records(self.type_base(), |v, r| v.ty("ty", r.ty));
+        v.records(self.type_function(), |v, r| {
+            v.ty("target", r.target);
+            v.ty("source", r.source);
+        });
+        v.records(self.type_qualified(), |v, r| {
+            v.ty("unqualified_type", r.unqualified_type)
+        });
+        v.records(self.type_tuple(), |v, r| {
+            v.sequence("start", "heap.type", r.start, r.cardinality)
+        });
+        v.records(self.type_array(), |v, r| {
+            v.ty("element", r.element);
+            v.expr("extent", r.extent);
+        });
+        v.records(self.type_designated(), |v, &r| v.decl("decl", r));
+        for part in [
+            self.type_pointer(),
+            self.type_lvalue_reference(),
+            self.type_rvalue_reference(),
+            self.type_unaligned(),
+        ] {
+            v.records(part, |v, &r| v.ty("type", r));
+        }
+        v.records(self.type_tor(), |v, r| v.ty("source", r.source));
+        v.records(self.type_syntactic(), |v, &r| v.expr("expr", r));
+        v.records(self.type_expansion(), |v, r| v.ty("pack", r.pack));
+        v.records(self.type_pointer_to_member(), |v, r| {
+            v.ty("scope", r.scope);
+            v.ty("member", r.member);
+        });
+        v.records(self.type_method(), |v, r| {
+            v.ty("target", r.target);
+            v.ty("source", r.source);
+            v.ty("scope", r.scope);
+        });
+        v.records(self.type_typename(), |v, &r| v.expr("path", r));
+        v.records(self.type_placeholder(), |v, r| {
+            v.expr("constraint", r.constraint);
+            v.ty("elaboration", r.elaboration);
+        });
+        v.records(self.type_forall(), |v, r| {
+            v.chart("chart", r.chart);
+            v.ty("subject", r.subject);
+        });
+
+        // Charts
+        v.records(self.chart_unilevel(), |v, r| {
+            v.sequence("start", "decl.parameter", r.start, r.cardinality);
+            v.expr("constraint", r.constraint);
+        });
+        v.records(self.chart_multilevel(), |v, r| {
+            v.sequence("start", "heap.chart", r.start, r.cardinality)
+        });
+        v.records(self.heap_chart(), |v, &r| v.chart("chart", r));
+
+        // Names and strings
+        v.records(self.name_operator(), |v, r| v.text("encoded", r.encoded));
+        v.records(self.name_conversion(), |v, r| {
+            v.ty("target", r.target);
+            v.text("encoded", r.encoded);
+        });
+        v.records(self.name_literal(), |v, &r| v.text("encoded", r));
+        v.records(self.name_template(), |v, &r| {
+            if r.tag() == NameSort::TEMPLATE {
+                v.report(
+                    "name",
+                    format!("assumed template name {:?} is itself a template name", r),
+                );
+            } else {
+                v.name("name", r);
+            }
+        });
+        v.records(self.name_specialization(), |v, r| {
+            v.name("primary", r.primary);
+            v.expr("arguments", r.arguments);
+        });
+        v.records(self.name_guide(), |v, &r| v.decl("primary_template", r));
+        v.records(self.name_source_file(), |v, r| {
+            v.text("path", r.path);
+            v.text("guard", r.guard);
+        });
+        v.records(self.command_line(), |v, &r| v.text("text", r));
+        v.records(self.src_line(), |v, r| {
+            if r.file.tag() == NameSort::SOURCE_FILE {
+                v.name("file", r.file);
+            } else if r.file.0 != 0 {
+                v.report("file", format!("{:?} is not a source file name", r.file));
+            }
+        });
+
+        // Attributes
+        v.records(self.attr_basic(), |v, r| v.word("word", r));
+        v.records(self.attr_scoped(), |v, r| {
+            v.word("scope", &r.scope);
+            v.word("member", &r.member);
+        });
+        v.records(self.attr_labeled(), |v, r| {
+            v.word("label", &r.label);
+            v.attr("attribute", r.attribute);
+        });
+        v.records(self.attr_called(), |v, r| {
+            v.attr("function", r.function);
+            v.attr("arguments", r.arguments);
+        });
+        v.records(self.attr_expanded(), |v, &r| v.attr("operand", r));
+        v.records(self.attr_factored(), |v, r| {
+            v.word("factor", &r.factor);
+            v.attr("terms", r.terms);
+        });
+        v.records(self.attr_elaborated(), |v, &r| v.expr("expr", r));
+        v.records(self.attr_tuple(), |v, r| {
+            v.sequence("start", "heap.attr", r.start, r.cardinality)
+        });
+        v.records(self.heap_attr(), |v, &r| v.attr("attr", r));
+        v.records(self.trait_attribute(), |v, r| {
+            v.decl("decl", r.decl);
+            v.attr("attribute", r.attribute);
+        });
+        v.records(self.msvc_trait_decl_attrs(), |v, r| {
+            v.decl("decl", r.decl);
+            v.attr("attribute", r.attribute);
+        });
+        v.records(self.trait_deprecated(), |v, r| {
+            v.decl("decl", r.decl);
+            v.text("message", r.message);
+        });
+
+        // Expressions
+        v.records(self.heap_expr(), |v, &r| v.expr("expr", r));
+        for part in [
+            self.expr_empty(),
+            self.expr_placeholder(),
+            self.expr_nullptr(),
+            self.expr_this(),
+        ] {
+            v.records(part, |v, r| v.ty("ty", r.ty));
+        }
+        v.records(self.expr_literal(), |v, r| {
+            v.ty("ty", r.ty);
+            v.lit("value", r.value);
+        });
+        v.records(self.expr_type(), |v, r| {
+            v.ty("ty", r.ty);
+            v.ty("denotation", r.denotation);
+        });
+        v.records(self.expr_named_decl(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("resolution", r.resolution);
+        });
+        v.records(self.expr_unresolved_id(), |v, r| {
+            v.ty("ty", r.ty);
+            v.name("name", r.name);
+        });
+        v.records(self.expr_template_id(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("primary", r.primary);
+            v.expr("arguments", r.arguments);
+        });
+        v.records(self.expr_unqualified_id(), |v, r| {
+            v.ty("ty", r.ty);
+            v.name("name", r.name);
+            v.expr("resolution", r.resolution);
+        });
+        v.records(self.expr_simple_identifier(), |v, r| {
+            v.ty("ty", r.ty);
+            v.name("name", r.name);
+        });
+        v.records(self.expr_qualified_name(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("elements", r.elements);
+        });
+        v.records(self.expr_path(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("scope", r.scope);
+            v.expr("member", r.member);
+        });
+        v.records(self.expr_read(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("address", r.address);
+        });
+        v.records(self.expr_monad(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("impl_", r.impl_);
+            v.expr("argument", r.argument);
+        });
+        v.records(self.expr_dyad(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("impl_", r.impl_);
+            v.expr("arguments[0]", r.arguments[0]);
+            v.expr("arguments[1]", r.arguments[1]);
+        });
+        v.records(self.expr_triad(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("impl_", r.impl_);
+            v.expr("arguments[0]", r.arguments[0]);
+            v.expr("arguments[1]", r.arguments[1]);
+            v.expr("arguments[2]", r.arguments[2]);
+        });
+        v.records(self.expr_string(), |v, r| {
+            v.ty("ty", r.ty);
+            v.sequence("string_index", "const.str", r.string_index.index(), 1);
+        });
+        v.records(self.const_str(), |v, r| v.text("suffix", r.suffix));
+        v.records(self.expr_temporary(), |v, r| v.ty("ty", r.ty));
+        v.records(self.expr_call(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("operation", r.operation);
+            v.expr("arguments", r.arguments);
+        });
+        v.records(self.expr_member_initializer(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("member", r.member);
+            v.ty("base", r.base);
+            v.expr("initializer", r.initializer);
+        });
+        v.records(self.expr_member_access(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("offset", r.offset);
+            v.ty("enclosing", r.enclosing);
+            v.text("name", r.name);
+        });
+        v.records(self.expr_inheritance_path(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("path", r.path);
+        });
+        v.records(self.expr_initializer_list(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("elements", r.elements);
+        });
+        v.records(self.expr_cast(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("source", r.source);
+            v.ty("target", r.target);
+        });
+        v.records(self.expr_condition(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("expr", r.expr);
+        });
+        v.records(self.expr_expression_list(), |v, r| {
+            v.expr("contents", r.contents)
+        });
+        for part in [
+            self.expr_sizeof_type(),
+            self.expr_alignof(),
+            self.expr_typeid(),
+        ] {
+            v.records(part, |v, r| {
+                v.ty("ty", r.ty);
+                v.ty("operand", r.operand);
+            });
+        }
+        v.records(self.expr_destructor_call(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("object", r.object);
+        });
+        v.records(self.expr_function_string(), |v, r| {
+            v.ty("ty", r.ty);
+            v.text("macro_", r.macro_);
+        });
+        v.records(self.expr_compound_string(), |v, r| {
+            v.ty("ty", r.ty);
+            v.text("prefix", r.prefix);
+            v.expr("string", r.string);
+        });
+        v.records(self.expr_string_sequence(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("strings", r.strings);
+        });
+        v.records(self.expr_initializer(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("expr", r.expr);
+        });
+        v.records(self.expr_requires(), |v, r| v.ty("ty", r.ty));
+        v.records(self.expr_unary_fold(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("expr", r.expr);
+        });
+        v.records(self.expr_binary_fold(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("left", r.left);
+            v.expr("right", r.right);
+        });
+        v.records(self.expr_hierarchy_conversion(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("source", r.source);
+            v.ty("target", r.target);
+            v.expr("inheritance", r.inheritance);
+            v.expr("override_", r.override_);
+        });
+        v.records(self.expr_product_type_value(), |v, r| {
+            v.ty("ty", r.ty);
+            v.ty("structure", r.structure);
+            v.expr("members", r.members);
+            v.expr("base_subobjects", r.base_subobjects);
+        });
+        v.records(self.expr_sum_type_value(), |v, r| {
+            v.ty("ty", r.ty);
+            v.ty("variant", r.variant);
+            v.expr("value", r.value);
+        });
+        v.records(self.expr_array_value(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("elements", r.elements);
+            v.ty("element_type", r.element_type);
+        });
+        v.records(self.expr_dynamic_dispatch(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("pivot", r.pivot);
+        });
+        v.records(self.expr_virtual_function_conversion(), |v, r| {
+            v.ty("ty", r.ty);
+            v.decl("function", r.function);
+        });
+        v.records(self.expr_expansion(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("operand", r.operand);
+        });
+        v.records(self.expr_tuple(), |v, r| {
+            v.ty("ty", r.ty);
+            v.sequence("start", "heap.expr", r.start, r.cardinality);
+        });
+        v.records(self.expr_template_reference(), |v, r| {
+            v.ty("ty", r.ty);
+            v.name("member_name", r.member_name);
+            v.ty("scope", r.scope);
+            v.expr("arguments", r.arguments);
+        });
+        v.records(self.expr_push_state(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("ctor_call", r.ctor_call);
+            v.expr("dtor_call", r.dtor_call);
+        });
+        v.records(self.expr_type_trait(), |v, r| {
+            v.ty("ty", r.ty);
+            v.ty("arguments", r.arguments);
+        });
+        v.records(self.expr_designated_init(), |v, r| {
+            v.ty("ty", r.ty);
+            v.text("member", r.member);
+            v.expr("initializer", r.initializer);
+        });
+        v.records(self.expr_packed_template_arguments(), |v, r| {
+            v.ty("ty", r.ty);
+            v.expr("arguments", r.arguments);
+        });
+        v.records(self.expr_tokens(), |v, r| v.ty("ty", r.ty));
+        v.records(self.expr_assign_initializer(), |v, r| {
+            v.expr("initializer", r.initializer)
+        });
+
+        // Macros and preprocessing forms
+        v.records(self.heap_form(), |v, &r| v.form("form", r));
+        v.records(self.macro_object_like(), |v, r| {
+            v.text("name", r.name);
+            v.form("body", r.body);
+        });
+        v.records(self.macro_function_like(), |v, r| {
+            v.text("name", r.name);
+            v.form("parameters", r.parameters);
+            v.form("body", r.body);
+        });
+        v.records(self.pp_ident(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_char(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_string(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_num(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_op(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_keyword(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_param(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_stringize(), |v, r| v.form("operand", r.operand));
+        v.records(self.pp_catenate(), |v, r| {
+            v.form("first", r.first);
+            v.form("second", r.second);
+        });
+        v.records(self.pp_header(), |v, r| v.form("spelling", r.spelling));
+        v.records(self.pp_paren(), |v, r| v.form("operand", r.operand));
+        v.records(self.pp_junk(), |v, r| v.text("spelling", r.spelling));
+        v.records(self.pp_pragma(), |v, r| v.form("operand", r.operand));
+        v.records(self.pp_tuple(), |v, r| {
+            v.sequence("start", self.heap_form().part_name, r.start, r.cardinality)
+        });
+
+        v.diags
+    }
+}
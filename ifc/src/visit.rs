@@ -0,0 +1,220 @@
+//! Recursive traversal of scopes
+
+use super::*;
+use std::collections::HashSet;
+
+/// The default value of `DeclVisitor::max_depth`.
+pub const DEFAULT_MAX_VISIT_DEPTH: u32 = 64;
+
+/// Receives the declarations found by `Ifc::visit` and `Ifc::visit_scope`.
+///
+/// Every method has a default implementation, so a visitor only needs to implement the ones
+/// that it cares about. The `visit_namespace`, `visit_class` and `visit_enum` methods return
+/// whether to visit the members of that declaration. If they return `true`, then the members
+/// are visited, followed by the matching `leave_*` method.
+///
+/// Declarations that don't have a more specific method go to `visit_other`.
+#[allow(unused_variables)]
+pub trait DeclVisitor {
+    /// Scopes that are nested deeper than this are passed to `depth_exceeded`, instead of
+    /// being visited.
+    fn max_depth(&self) -> u32 {
+        DEFAULT_MAX_VISIT_DEPTH
+    }
+
+    /// Called instead of visiting the members of a scope that is nested too deeply. By default,
+    /// this is an error.
+    fn depth_exceeded(&mut self, ifc: &Ifc, scope: ScopeIndex) -> Result<()> {
+        bail!(
+            "scope #{} is nested more than {} levels deep",
+            scope,
+            self.max_depth()
+        )
+    }
+
+    /// Called for every member of a scope, before the more specific method for it.
+    fn visit_member(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        Ok(true)
+    }
+    fn leave_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for classes, structs and unions. Scopes that are only forward declarations have
+    /// no members, but this is still called for them.
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        Ok(true)
+    }
+    fn leave_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        Ok(true)
+    }
+    fn leave_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<()> {
+        Ok(())
+    }
+    fn visit_enumerator(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        enumerator: &DeclEnumerator,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, func: &DeclFunc) -> Result<()> {
+        Ok(())
+    }
+    fn visit_method(&mut self, ifc: &Ifc, decl: DeclIndex, method: &DeclMethod) -> Result<()> {
+        Ok(())
+    }
+    fn visit_constructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        ctor: &DeclConstructor,
+    ) -> Result<()> {
+        Ok(())
+    }
+    fn visit_destructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        dtor: &DeclDestructor,
+    ) -> Result<()> {
+        Ok(())
+    }
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, field: &DeclField) -> Result<()> {
+        Ok(())
+    }
+    fn visit_bitfield(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        bitfield: &DeclBitfield,
+    ) -> Result<()> {
+        Ok(())
+    }
+    fn visit_variable(&mut self, ifc: &Ifc, decl: DeclIndex, var: &DeclVar) -> Result<()> {
+        Ok(())
+    }
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, alias: &DeclAlias) -> Result<()> {
+        Ok(())
+    }
+    fn visit_template(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        template: &DeclTemplate,
+    ) -> Result<()> {
+        Ok(())
+    }
+    fn visit_other(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'data> Ifc<'data> {
+    /// Visits every declaration that is reachable from the global scope.
+    pub fn visit<V: DeclVisitor + ?Sized>(&self, visitor: &mut V) -> Result<()> {
+        self.visit_scope(self.global_scope(), visitor)
+    }
+
+    /// Visits the members of `scope`, and recursively the members of the namespaces, classes
+    /// and enums in it. The members of each scope are visited at most once, so a malformed file
+    /// cannot send the visitor around in circles.
+    pub fn visit_scope<V: DeclVisitor + ?Sized>(
+        &self,
+        scope: ScopeIndex,
+        visitor: &mut V,
+    ) -> Result<()> {
+        let mut visited = HashSet::new();
+        self.visit_scope_members(scope, visitor, &mut visited, 0)
+    }
+
+    fn visit_scope_members<V: DeclVisitor + ?Sized>(
+        &self,
+        scope: ScopeIndex,
+        visitor: &mut V,
+        visited: &mut HashSet<ScopeIndex>,
+        depth: u32,
+    ) -> Result<()> {
+        // Incomplete classes have no scope.
+        if scope == 0 || !visited.insert(scope) {
+            return Ok(());
+        }
+        if depth > visitor.max_depth() {
+            return visitor.depth_exceeded(self, scope);
+        }
+
+        for member in self.iter_scope(scope)? {
+            visitor.visit_member(self, member)?;
+            let i = member.index();
+            match member.tag() {
+                DeclSort::SCOPE => {
+                    let nested = self.decl_scope().entry(i)?;
+                    if self.is_type_namespace(nested.ty)? {
+                        if visitor.visit_namespace(self, member, nested)? {
+                            self.visit_scope_members(
+                                nested.initializer,
+                                visitor,
+                                visited,
+                                depth + 1,
+                            )?;
+                            visitor.leave_namespace(self, member, nested)?;
+                        }
+                    } else if visitor.visit_class(self, member, nested)? {
+                        self.visit_scope_members(nested.initializer, visitor, visited, depth + 1)?;
+                        visitor.leave_class(self, member, nested)?;
+                    }
+                }
+                DeclSort::ENUMERATION => {
+                    let en = self.decl_enum().entry(i)?;
+                    if visitor.visit_enum(self, member, en)? {
+                        for e in en.initializer.to_range() {
+                            let enumerator = self.decl_enumerator().entry(e)?;
+                            let decl = DeclIndex::new(DeclSort::ENUMERATOR, e);
+                            visitor.visit_enumerator(self, decl, enumerator)?;
+                        }
+                        visitor.leave_enum(self, member, en)?;
+                    }
+                }
+                DeclSort::FUNCTION => {
+                    visitor.visit_function(self, member, self.decl_function().entry(i)?)?
+                }
+                DeclSort::METHOD => {
+                    visitor.visit_method(self, member, self.decl_method().entry(i)?)?
+                }
+                DeclSort::CONSTRUCTOR => {
+                    visitor.visit_constructor(self, member, self.decl_constructor().entry(i)?)?
+                }
+                DeclSort::DESTRUCTOR => {
+                    visitor.visit_destructor(self, member, self.decl_destructor().entry(i)?)?
+                }
+                DeclSort::FIELD => {
+                    visitor.visit_field(self, member, self.decl_field().entry(i)?)?
+                }
+                DeclSort::BITFIELD => {
+                    visitor.visit_bitfield(self, member, self.decl_bitfield().entry(i)?)?
+                }
+                DeclSort::VARIABLE => {
+                    visitor.visit_variable(self, member, self.decl_var().entry(i)?)?
+                }
+                DeclSort::ALIAS => {
+                    visitor.visit_alias(self, member, self.decl_alias().entry(i)?)?
+                }
+                DeclSort::TEMPLATE => {
+                    visitor.visit_template(self, member, self.decl_template().entry(i)?)?
+                }
+                _ => visitor.visit_other(self, member)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+//! Resolving declarations across the units of a program
+
+use super::*;
+use std::sync::Arc;
+
+/// Identifies a unit in a `Workspace`. Units are numbered in the order in which they were
+/// added.
+pub type UnitId = usize;
+
+/// A unit in a `Workspace`.
+#[derive(Clone)]
+pub struct WorkspaceUnit {
+    /// The name that the unit was added with, such as the name of the crate that is generated
+    /// from it.
+    pub name: String,
+    pub ifc: Arc<Ifc<'static>>,
+    /// The name that other units use to import this one (see `ModuleInfo::unit_name`).
+    pub unit_name: Option<String>,
+    /// The names of the units that this one imports.
+    pub imports: Vec<String>,
+    /// The declarations of the unit, by qualified name.
+    pub index: SymbolIndex,
+}
+
+/// A set of IFC files that make up a program, such as a header unit and the header units that
+/// it depends on, which resolves a declaration in one unit to the declaration that defines the
+/// same entity in another unit.
+///
+/// Entities are matched by qualified name. Where the format records more than that, it is used:
+/// a `DeclSort::REFERENCE` names the module that owns an entity, and the units that a unit
+/// imports are searched before the rest of the workspace, in the order in which they were added.
+/// Entities that only exist in one unit (unnamed entities, anything in an anonymous namespace,
+/// and declarations with internal linkage) are never matched with another unit.
+#[derive(Clone, Default)]
+pub struct Workspace {
+    units: Vec<WorkspaceUnit>,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a unit to the workspace and indexes its declarations. It is an error to add two
+    /// units with the same name.
+    pub fn add(&mut self, name: &str, ifc: Arc<Ifc<'static>>) -> Result<UnitId> {
+        if self.find(name).is_some() {
+            bail!("the workspace already contains a unit named {}", name);
+        }
+        let info = ifc.module_info()?;
+        let unit_name = info.unit_name();
+        let imports = info.imports.iter().map(|i| i.unit_name()).collect();
+        let index = ifc.build_index()?;
+
+        let id = self.units.len();
+        self.units.push(WorkspaceUnit {
+            name: name.to_string(),
+            ifc,
+            unit_name,
+            imports,
+            index,
+        });
+        Ok(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    pub fn units(&self) -> &[WorkspaceUnit] {
+        &self.units
+    }
+
+    pub fn unit(&self, unit: UnitId) -> Result<&WorkspaceUnit> {
+        match self.units.get(unit) {
+            Some(u) => Ok(u),
+            None => bail!("the workspace has no unit {}", unit),
+        }
+    }
+
+    /// Finds a unit by the name that it was added with.
+    pub fn find(&self, name: &str) -> Option<UnitId> {
+        self.units.iter().position(|u| u.name == name)
+    }
+
+    /// Finds a unit by the name that other units import it by, e.g. `M`, `M:P` or the path
+    /// of a header unit.
+    pub fn find_unit_name(&self, unit_name: &str) -> Option<UnitId> {
+        self.units
+            .iter()
+            .position(|u| u.unit_name.as_deref() == Some(unit_name))
+    }
+
+    /// Finds the declaration that defines the entity that `decl` declares. If `decl` is itself
+    /// a definition, then this returns it. Otherwise, such as for a forward declaration of a
+    /// class or a `DeclSort::REFERENCE`, this searches the other units.
+    ///
+    /// Returns `None` if no unit in the workspace defines the entity, or if the entity is local
+    /// to its unit.
+    pub fn resolve_decl(
+        &self,
+        unit: UnitId,
+        decl: DeclIndex,
+    ) -> Result<Option<(UnitId, DeclIndex)>> {
+        let ifc = &self.unit(unit)?.ifc;
+        if decl.tag() != DeclSort::REFERENCE && is_definition(ifc, decl)? {
+            return Ok(Some((unit, decl)));
+        }
+        self.find_definition(ifc, Some(unit), decl, 0)
+    }
+
+    /// Like `resolve_decl`, but for a type. Qualifiers are ignored, so `const POINT` resolves
+    /// to the definition of `POINT`. Returns `None` if the type does not name a declaration.
+    pub fn resolve_type(&self, unit: UnitId, ty: TypeIndex) -> Result<Option<(UnitId, DeclIndex)>> {
+        match designated_decl(&self.unit(unit)?.ifc, ty)? {
+            Some(decl) => self.resolve_decl(unit, decl),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds a unit in the workspace that defines the entity that `decl` declares, where `decl`
+    /// belongs to an IFC that is not in the workspace, such as the unit that is being
+    /// translated. This finds the definition even if `ifc` also contains one.
+    pub fn resolve_foreign_decl(
+        &self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+    ) -> Result<Option<(UnitId, DeclIndex)>> {
+        self.find_definition(ifc, None, decl, 0)
+    }
+
+    /// Like `resolve_foreign_decl`, but for a type.
+    pub fn resolve_foreign_type(
+        &self,
+        ifc: &Ifc,
+        ty: TypeIndex,
+    ) -> Result<Option<(UnitId, DeclIndex)>> {
+        match designated_decl(ifc, ty)? {
+            Some(decl) => self.resolve_foreign_decl(ifc, decl),
+            None => Ok(None),
+        }
+    }
+
+    /// Searches every unit except `from_unit` for the definition of `decl`, which belongs to
+    /// `ifc`.
+    fn find_definition(
+        &self,
+        ifc: &Ifc,
+        from_unit: Option<UnitId>,
+        decl: DeclIndex,
+        depth: u32,
+    ) -> Result<Option<(UnitId, DeclIndex)>> {
+        if depth > MAX_TYPE_DEPTH {
+            bail!("declaration {:?} refers to itself (is it cyclic?)", decl);
+        }
+
+        if decl.tag() == DeclSort::REFERENCE {
+            // The spec does not say which unit's numbering `local_index` uses. We take it to be
+            // the index of the declaration in the unit that owns the entity, since that is the
+            // only unit in which it can be looked up.
+            let reference = ifc.decl_reference().entry(decl.index())?;
+            let owner = ImportedUnit {
+                owner: ifc.optional_string(reference.unit.owner)?,
+                partition: ifc.optional_string(reference.unit.partition)?,
+                exported: false,
+            };
+            let owner = match self.find_unit_name(&owner.unit_name()) {
+                Some(owner) => owner,
+                None => return Ok(None),
+            };
+            let target = reference.local_index;
+            let owner_ifc = &self.units[owner].ifc;
+            if target.tag() != DeclSort::REFERENCE && is_definition(owner_ifc, target)? {
+                return Ok(Some((owner, target)));
+            }
+            return self.find_definition(owner_ifc, Some(owner), target, depth + 1);
+        }
+
+        if is_unit_local(ifc, decl)? {
+            return Ok(None);
+        }
+        let name = ifc.qualified_name(decl)?.to_string();
+        for unit in self.search_order(ifc, from_unit)? {
+            let u = &self.units[unit];
+            for &candidate in u.index.lookup(&name) {
+                // A class may also be declared by an alias of the same name, as in
+                // `typedef struct S { ... } S;`, so only the same sort of declaration matches.
+                if candidate.tag() == decl.tag()
+                    && is_definition(&u.ifc, candidate)?
+                    && is_visible(&u.ifc, candidate)?
+                {
+                    return Ok(Some((unit, candidate)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The units to search for a definition that is needed by `ifc`: the units that it imports,
+    /// then the rest, in the order in which they were added.
+    fn search_order(&self, ifc: &Ifc, from_unit: Option<UnitId>) -> Result<Vec<UnitId>> {
+        let mut order: Vec<UnitId> = Vec::with_capacity(self.units.len());
+        let imports = match from_unit {
+            Some(unit) => self.units[unit].imports.clone(),
+            None => ifc
+                .module_info()?
+                .imports
+                .iter()
+                .map(|i| i.unit_name())
+                .collect(),
+        };
+        let imported = imports.iter().filter_map(|name| self.find_unit_name(name));
+        for unit in imported.chain(0..self.units.len()) {
+            if Some(unit) != from_unit && !order.contains(&unit) {
+                order.push(unit);
+            }
+        }
+        Ok(order)
+    }
+}
+
+/// Whether a declaration defines its entity, rather than only declaring it. Forward
+/// declarations of classes have no scope, and opaque enumerations have no enumerators.
+/// Namespaces are never merged across units, so each one counts as a definition.
+fn is_definition(ifc: &Ifc, decl: DeclIndex) -> Result<bool> {
+    Ok(match decl.tag() {
+        DeclSort::SCOPE => {
+            let scope = ifc.decl_scope().entry(decl.index())?;
+            scope.initializer != 0 || ifc.is_type_namespace(scope.ty)?
+        }
+        DeclSort::ENUMERATION => ifc.decl_enum().entry(decl.index())?.initializer.cardinality != 0,
+        _ => true,
+    })
+}
+
+/// Whether a declaration can be seen by other units. Declarations in a module interface are
+/// only visible if they are exported. Header units export everything that has external
+/// linkage.
+fn is_visible(ifc: &Ifc, decl: DeclIndex) -> Result<bool> {
+    let specifiers = match basic_specifiers(ifc, decl)? {
+        Some(specifiers) => specifiers,
+        None => return Ok(true),
+    };
+    if specifiers.contains(BasicSpecifiers::INTERNAL) {
+        return Ok(false);
+    }
+    if !matches!(ifc.unit_sort(), UnitSort::PRIMARY | UnitSort::PARTITION) {
+        return Ok(true);
+    }
+    Ok(!specifiers.contains(BasicSpecifiers::NON_EXPORTED))
+}
+
+/// Whether a declaration names an entity that no other unit can name: it, or one of its
+/// enclosing scopes, has no name of its own (this includes anonymous namespaces and the names
+/// that the compiler makes up for unnamed classes, such as `<unnamed-tag>`), or has internal
+/// linkage.
+fn is_unit_local(ifc: &Ifc, decl: DeclIndex) -> Result<bool> {
+    let mut current = Some(decl);
+    for _ in 0..MAX_TYPE_DEPTH {
+        let decl = match current {
+            Some(decl) => decl,
+            None => return Ok(false),
+        };
+        match ifc.decl_name(decl)? {
+            Some(name) if !name.is_empty() && !name.starts_with('<') => {}
+            _ => return Ok(true),
+        }
+        if let Some(specifiers) = basic_specifiers(ifc, decl)? {
+            if specifiers.contains(BasicSpecifiers::INTERNAL) {
+                return Ok(true);
+            }
+        }
+        current = ifc.home_scope(decl)?;
+    }
+    bail!(
+        "declaration {:?} is nested too deeply (is it cyclic?)",
+        decl
+    )
+}
+
+/// The linkage and export specifiers of a declaration, for the sorts that have them.
+fn basic_specifiers(ifc: &Ifc, decl: DeclIndex) -> Result<Option<BasicSpecifiers>> {
+    let i = decl.index();
+    Ok(Some(match decl.tag() {
+        DeclSort::SCOPE => ifc.decl_scope().entry(i)?.specifiers,
+        DeclSort::ENUMERATION => ifc.decl_enum().entry(i)?.specifiers,
+        DeclSort::ALIAS => ifc.decl_alias().entry(i)?.specifiers,
+        DeclSort::FUNCTION => ifc.decl_function().entry(i)?.specifiers,
+        DeclSort::VARIABLE => ifc.decl_var().entry(i)?.specifier,
+        _ => return Ok(None),
+    }))
+}
+
+/// The declaration that a type names, after removing any qualifiers.
+fn designated_decl(ifc: &Ifc, ty: TypeIndex) -> Result<Option<DeclIndex>> {
+    let mut ty = ty;
+    for _ in 0..MAX_TYPE_DEPTH {
+        match ty.tag() {
+            TypeSort::QUALIFIED => ty = ifc.type_qualified().entry(ty.index())?.unqualified_type,
+            TypeSort::DESIGNATED => return Ok(Some(*ifc.type_designated().entry(ty.index())?)),
+            _ => return Ok(None),
+        }
+    }
+    bail!("type {:?} is nested too deeply (is it cyclic?)", ty)
+}
@@ -0,0 +1,290 @@
+//! Writing IFC files
+//!
+//! `IfcBuilder` assembles the contents of an IFC (the string table and the partitions) in memory
+//! and serializes them into the on-disk format, which can then be read back with `Ifc::load`.
+//! This is mostly useful for building test fixtures without needing a C++ compiler.
+
+use super::*;
+
+/// Builds the contents of an IFC file in memory.
+///
+/// Records are added directly to the partitions in `parts`, using the same record types that
+/// the reader uses. `to_bytes` produces the file: signature, header, partitions, string table,
+/// and finally the table of contents.
+pub struct IfcBuilder {
+    /// The file header. `to_bytes` fills in the checksum, string table and table of contents
+    /// fields.
+    /// Everything else (version, architecture, unit, global scope, etc.) is up to the caller.
+    pub file_header: FileHeader,
+
+    /// The partitions that will be written to the file. Empty partitions are not written.
+    pub parts: Parts,
+
+    strings: Vec<u8>,
+    string_map: HashMap<String, TextOffset>,
+    raw_parts: Vec<RawPart>,
+}
+
+/// A partition that is not described by `Parts`, e.g. a partition that the reader does not
+/// decode yet. The contents are written verbatim.
+struct RawPart {
+    name: String,
+    entity_size: usize,
+    cardinality: usize,
+    data: Vec<u8>,
+}
+
+impl Default for IfcBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn align_to_4(data: &mut Vec<u8>) {
+    while data.len() % 4 != 0 {
+        data.push(0);
+    }
+}
+
+/// Appends `s` to a string table that starts at offset `base`, unless `string_map` already
+/// has it.
+fn append_string(
+    strings: &mut Vec<u8>,
+    string_map: &mut HashMap<String, TextOffset>,
+    base: usize,
+    s: &str,
+) -> TextOffset {
+    if let Some(&offset) = string_map.get(s) {
+        return offset;
+    }
+    let offset = (base + strings.len()) as TextOffset;
+    strings.extend_from_slice(s.as_bytes());
+    strings.push(0);
+    string_map.insert(s.to_string(), offset);
+    offset
+}
+
+impl IfcBuilder {
+    pub fn new() -> Self {
+        let mut b = Self {
+            file_header: FileHeader::default(),
+            parts: Parts::default(),
+            strings: Vec::new(),
+            string_map: HashMap::new(),
+            raw_parts: Vec::new(),
+        };
+        // TextOffset 0 is used as "no string", so reserve it for the empty string.
+        b.add_string("");
+        b
+    }
+
+    /// Appends a record to a partition and returns the index of the new record, e.g.
+    /// `IfcBuilder::push_entry(&mut b.parts.type_pointer, ty)`.
+    pub fn push_entry<T>(entries: &mut Vec<T>, value: T) -> Index {
+        let index = entries.len() as Index;
+        entries.push(value);
+        index
+    }
+
+    /// Adds a string to the string table, or finds an existing copy of it.
+    pub fn add_string(&mut self, s: &str) -> TextOffset {
+        append_string(&mut self.strings, &mut self.string_map, 0, s)
+    }
+
+    /// Adds an identifier to the string table and returns a `NameIndex` for it.
+    pub fn add_identifier(&mut self, s: &str) -> NameIndex {
+        NameIndex::new(NameSort::IDENTIFIER, self.add_string(s))
+    }
+
+    /// Sets the `src_path` field of the file header.
+    pub fn set_src_path(&mut self, path: &str) {
+        self.file_header.src_path = self.add_string(path);
+    }
+
+    /// Sets the `unit` field of the file header. `name` is the module name for
+    /// `UnitSort::PRIMARY`, `M:P` for `UnitSort::PARTITION`, and ignored for other sorts.
+    pub fn set_unit(&mut self, sort: UnitSort, name: &str) {
+        let index = match sort {
+            UnitSort::PRIMARY | UnitSort::PARTITION | UnitSort::EXPORTED_TU => {
+                self.add_string(name)
+            }
+            _ => 0,
+        };
+        self.file_header.unit = (index << 3) | sort.0 as u32;
+    }
+
+    /// Adds the names in a `ModuleReference` to the string table. The result can be added to
+    /// `module.imported` or `module.exported`. Use an empty `owner` for a header unit.
+    pub fn add_module_reference(&mut self, owner: &str, partition: &str) -> ModuleReference {
+        ModuleReference {
+            owner: self.add_string(owner),
+            partition: self.add_string(partition),
+        }
+    }
+
+    /// Adds a `name.source-file` entry and returns a `NameIndex` for it.
+    pub fn add_source_file(&mut self, path: &str) -> NameIndex {
+        let path = self.add_string(path);
+        let index = Self::push_entry(
+            &mut self.parts.name_source_file,
+            NameSourceFile { path, guard: 0 },
+        );
+        NameIndex::new(NameSort::SOURCE_FILE, index)
+    }
+
+    /// Adds a `src.line` entry and returns a `SourceLocation` that refers to it.
+    pub fn add_source_location(
+        &mut self,
+        file: NameIndex,
+        line: LineNumber,
+        column: Column,
+    ) -> SourceLocation {
+        let index = Self::push_entry(&mut self.parts.src_line, FileAndLine { file, line });
+        SourceLocation {
+            line: index,
+            column,
+        }
+    }
+
+    /// Adds a scope whose members are `members`. Returns the (1-based) `ScopeIndex` of the new
+    /// scope, which is suitable for `DeclScope::initializer` or `FileHeader::global_scope`.
+    pub fn add_scope(&mut self, members: &[DeclIndex]) -> ScopeIndex {
+        let start = self.parts.scope_member.len() as Index;
+        self.parts.scope_member.extend_from_slice(members);
+        let index = Self::push_entry(
+            &mut self.parts.scope_desc,
+            ScopeDescriptor {
+                start,
+                cardinality: members.len() as Cardinality,
+            },
+        );
+        index + 1
+    }
+
+    pub fn add_fundamental_type(
+        &mut self,
+        basis: TypeBasis,
+        precision: TypePrecision,
+        sign: TypeSign,
+    ) -> TypeIndex {
+        let index = Self::push_entry(
+            &mut self.parts.type_fundamental,
+            FundamentalType {
+                basis,
+                precision,
+                sign,
+                padding: [0],
+            },
+        );
+        TypeIndex::new(TypeSort::FUNDAMENTAL, index)
+    }
+
+    /// Adds an integer literal expression. Small values are stored as immediate values, others
+    /// are stored in `const.i64`.
+    pub fn add_integer_literal(&mut self, ty: TypeIndex, value: u64) -> ExprIndex {
+        // LitIndex has 2 tag bits, leaving 30 bits for immediate values.
+        let lit = if value < (1 << 30) {
+            LitIndex::new(LiteralSort::IMMEDIATE, value as u32)
+        } else {
+            let i = Self::push_entry(&mut self.parts.const_i64, value);
+            LitIndex::new(LiteralSort::INTEGER, i)
+        };
+        let index = Self::push_entry(
+            &mut self.parts.expr_literal,
+            ExprLiteral {
+                locus: SourceLocation { line: 0, column: 0 },
+                ty,
+                value: lit,
+            },
+        );
+        ExprIndex::new(ExprSort::LITERAL, index)
+    }
+
+    /// Adds a partition that is written verbatim. `data.len()` must be a multiple of
+    /// `entity_size`.
+    pub fn add_raw_partition(
+        &mut self,
+        name: &str,
+        entity_size: usize,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        if entity_size == 0 || data.len() % entity_size != 0 {
+            bail!(
+                "partition {} has {} bytes, which is not a multiple of the entity size {}",
+                name,
+                data.len(),
+                entity_size
+            );
+        }
+        self.raw_parts.push(RawPart {
+            name: name.to_string(),
+            entity_size,
+            cardinality: data.len() / entity_size,
+            data,
+        });
+        Ok(())
+    }
+
+    /// Serializes the IFC. The checksum in the file header is computed from the output.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // The names of the partitions that are not already in the string table are written
+        // after it, so that the builder itself is not changed.
+        let mut names: Vec<u8> = Vec::new();
+        let mut names_map: HashMap<String, TextOffset> = HashMap::new();
+
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(&IFC_FILE_SIGNATURE);
+        out.extend_from_slice(FileHeader::default().as_bytes());
+        align_to_4(&mut out);
+
+        let mut toc: Vec<PartitionSummary> = Vec::new();
+        let mut write_part = |out: &mut Vec<u8>, name: &str, data: &[u8], count, size| {
+            align_to_4(out);
+            toc.push(PartitionSummary {
+                name: match self.string_map.get(name) {
+                    Some(&offset) => offset,
+                    None => append_string(&mut names, &mut names_map, self.strings.len(), name),
+                },
+                offset: out.len() as ByteOffset,
+                cardinality: count as Cardinality,
+                entity_size: size as EntitySize,
+            });
+            out.extend_from_slice(data);
+        };
+
+        self.parts.for_each_part(|name, data, count, size| {
+            write_part(&mut out, name, data, count, size);
+        });
+        for raw in self.raw_parts.iter() {
+            write_part(
+                &mut out,
+                &raw.name,
+                &raw.data,
+                raw.cardinality,
+                raw.entity_size,
+            );
+        }
+
+        align_to_4(&mut out);
+        let string_table_bytes = out.len();
+        out.extend_from_slice(&self.strings);
+        out.extend_from_slice(&names);
+
+        align_to_4(&mut out);
+        let toc_offset = out.len();
+        for summary in toc.iter() {
+            out.extend_from_slice(summary.as_bytes());
+        }
+
+        let mut header = self.file_header.clone();
+        header.string_table_bytes = string_table_bytes as ByteOffset;
+        header.string_table_size = (self.strings.len() + names.len()) as Cardinality;
+        header.toc = toc_offset as ByteOffset;
+        header.partition_count = toc.len() as Cardinality;
+        out[4..4 + size_of::<FileHeader>()].copy_from_slice(header.as_bytes());
+        let checksum = Ifc::compute_checksum(&out);
+        out[4..4 + size_of::<Sha256>()].copy_from_slice(checksum.as_bytes());
+
+        out
+    }
+}
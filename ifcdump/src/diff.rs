@@ -0,0 +1,61 @@
+//! `ifcdump diff old.ifc new.ifc`: reports ABI changes between two versions of a unit.
+
+use super::*;
+use std::path::Path;
+
+#[derive(StructOpt)]
+#[structopt(name = "ifcdump diff", bin_name = "ifcdump diff")]
+pub struct DiffOptions {
+    /// The old version of the IFC file.
+    pub old: String,
+
+    /// The new version of the IFC file.
+    pub new: String,
+
+    /// Write one change per line, as tab-separated fields: the change (`added`, `removed` or
+    /// `changed`), the kind of entity, its name, and its old and new descriptions.
+    #[structopt(long = "tsv")]
+    pub tsv: bool,
+}
+
+/// Prints the changes. Like `diff`, this exits with status 0 if the files have the same ABI, 1
+/// if they differ, and 2 if they could not be compared.
+pub fn run_diff(options: &DiffOptions) -> ! {
+    match diff_files(options) {
+        Ok(false) => std::process::exit(0),
+        Ok(true) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {:?}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns whether there were any changes.
+fn diff_files(options: &DiffOptions) -> Result<bool> {
+    let old = Ifc::from_file(Path::new(&options.old))
+        .with_context(|| format!("failed to read {}", options.old))?;
+    let new = Ifc::from_file(Path::new(&options.new))
+        .with_context(|| format!("failed to read {}", options.new))?;
+
+    let changes = ifc::diff(&old, &new)?;
+    for change in changes.iter() {
+        if options.tsv {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                change.kind,
+                change.entity,
+                change.name,
+                change.old.as_deref().unwrap_or_default(),
+                change.new.as_deref().unwrap_or_default()
+            );
+        } else {
+            println!("{}", change);
+        }
+    }
+
+    if changes.is_empty() && !options.tsv {
+        println!("No changes.");
+    }
+    Ok(!changes.is_empty())
+}
@@ -4,7 +4,7 @@
 #![forbid(unused_must_use)]
 #![forbid(unsafe_code)]
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use core::mem::size_of;
 use core::ops::Range;
 use ifc::*;
@@ -15,12 +15,18 @@ use std::collections::HashMap;
 use structopt::StructOpt;
 use zerocopy::{AsBytes, FromBytes, LayoutVerified};
 
+mod diff;
 mod options;
 mod parts;
 mod pp;
 mod summary;
 
 fn main() -> Result<()> {
+    // `ifcdump diff` compares two files, so it has its own options.
+    if std::env::args().nth(1).as_deref() == Some("diff") {
+        diff::run_diff(&diff::DiffOptions::from_iter(std::env::args().skip(1)));
+    }
+
     let mut options = options::Options::from_args();
 
     // If the user didn't specify anything, then show the summary by default.
@@ -76,7 +82,22 @@ fn main() -> Result<()> {
 
     let f = std::fs::read(&options.ifc)?;
 
-    let ifc = Ifc::load(f)?;
+    let load_options = LoadOptions {
+        verify_checksum: options.verify_checksum,
+        // Most dump options only look at a few partitions.
+        lazy: true,
+    };
+    let ifc = Ifc::load_with_options(f, &load_options)?;
+
+    if options.validate {
+        let diags = ifc.validate();
+        for diag in diags.iter() {
+            println!("{}", diag);
+        }
+        if !diags.is_empty() {
+            bail!("IFC file failed validation ({} problems)", diags.len());
+        }
+    }
 
     if options.parts {
         parts::dump_parts(&ifc)?;
@@ -156,163 +177,262 @@ impl Indent {
 
 fn dump_scope(
     ifc: &Ifc,
-    parent_scope: ScopeIndex,
+    scope: ScopeIndex,
     options: &Options,
     max_depth: u32,
     indent: Indent,
 ) -> Result<()> {
-    if parent_scope == 0 {
+    if scope == 0 {
         println!("Invalid scope (zero)");
         return Ok(());
     }
 
-    println!(
-        "{}// Scope #{}{}",
+    let mut dumper = ScopeDumper {
+        options,
+        max_depth,
         indent,
-        parent_scope,
-        if parent_scope == ifc.file_header().global_scope {
-            " - Global scope"
-        } else {
-            ""
+    };
+    dumper.begin_scope(ifc, scope);
+    ifc.visit_scope(scope, &mut dumper)
+}
+
+/// Formats a source location as `foo.h:42`, or shows the raw location if it cannot be resolved.
+fn format_locus(ifc: &Ifc, locus: &SourceLocation) -> String {
+    match ifc.resolve_locus(locus) {
+        Some((path, line, _)) => format!("{}:{}", path, line),
+        None => format!("{:?}", locus),
+    }
+}
+
+struct ScopeDumper<'a> {
+    options: &'a Options,
+    max_depth: u32,
+    indent: Indent,
+}
+
+impl<'a> ScopeDumper<'a> {
+    fn begin_scope(&self, ifc: &Ifc, scope: ScopeIndex) {
+        println!(
+            "{}// Scope #{}{}",
+            self.indent,
+            scope,
+            if scope == ifc.file_header().global_scope {
+                " - Global scope"
+            } else {
+                ""
+            }
+        );
+    }
+
+    /// Formats a declaration the way MSVC would write it, e.g. `unsigned __int64 x`.
+    fn declaration(&self, ifc: &Ifc, ty: TypeIndex, name: &str) -> Result<String> {
+        let options = CxxPrintOptions {
+            msvc_spellings: true,
+            calling_conventions: true,
+            ..CxxPrintOptions::default()
+        };
+        CxxTypePrinter::new(ifc, options).declaration(ty, name)
+    }
+
+    /// Shows the attributes of a declaration, e.g. `[[nodiscard]]`.
+    fn dump_attributes(&self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        for attr in ifc.decl_attributes(decl) {
+            println!("{}[[{}]]", self.indent, ifc.get_attr_string(attr)?);
+        }
+        if let Some(message) = ifc.deprecation(decl)? {
+            println!("{}// deprecated: {}", self.indent, message);
+        }
+        Ok(())
+    }
+
+    fn dump_function(
+        &self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        ty: TypeIndex,
+        locus: &SourceLocation,
+    ) -> Result<()> {
+        if self.options.functions {
+            self.dump_attributes(ifc, decl)?;
+            let func_name = ifc.qualified_name(decl)?.to_string();
+            println!(
+                "{}function: {} // {}",
+                self.indent,
+                self.declaration(ifc, ty, &func_name)?,
+                format_locus(ifc, locus)
+            );
         }
-    );
+        Ok(())
+    }
+}
 
-    // `scope.descriptor` gives us the start and length of the region in `scope.members` where
-    // the members for this scope can be found.
-    let scope_descriptor = ifc.scope_desc().entry(parent_scope - 1)?;
+impl<'a> DeclVisitor for ScopeDumper<'a> {
+    fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
 
-    if max_depth == 0 {
+    fn depth_exceeded(&mut self, ifc: &Ifc, scope: ScopeIndex) -> Result<()> {
         println!("Max depth exceeded!");
-        return Ok(());
+        Ok(())
     }
-    let max_depth = max_depth - 1;
 
-    trace!("{}scope descriptor = {:?}", indent, scope_descriptor);
+    fn visit_member(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        println!("{}scope member: decl_index = {:?}", self.indent, decl);
+        Ok(())
+    }
 
-    let scope_members = ifc.scope_member();
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        // We always recurse into namespaces.
+        self.indent = self.indent.nested();
+        self.begin_scope(ifc, scope.initializer);
+        Ok(true)
+    }
 
-    for member_index in
-        scope_descriptor.start..scope_descriptor.start + scope_descriptor.cardinality
-    {
-        let member_decl_index: DeclIndex = *scope_members.entry(member_index)?;
+    fn leave_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<()> {
+        self.indent = Indent(self.indent.0 - 1);
+        Ok(())
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        if !self.options.structs {
+            return Ok(false);
+        }
+        self.dump_attributes(ifc, decl)?;
+        let name = ifc.qualified_name(decl)?;
+        println!("{}struct {} {{", self.indent, name);
+        self.indent = self.indent.nested();
         println!(
-            "{}scope member: decl_index = {:?}",
-            indent, member_decl_index
+            "{}// source location: {}",
+            self.indent,
+            format_locus(ifc, &scope.locus)
         );
+        if scope.initializer != 0 {
+            self.begin_scope(ifc, scope.initializer);
+        }
+        Ok(true)
+    }
 
-        match member_decl_index.tag() {
-            DeclSort::ALIAS => {
-                if options.typedefs {
-                    let decl_alias = ifc.decl_alias().entry(member_decl_index.index())?;
-                    let alias_name = ifc.get_string(decl_alias.name)?;
-                    println!("{}alias: {}", indent, alias_name);
-                    println!("{}{:#?}", indent, decl_alias);
-                }
-            }
+    fn leave_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<()> {
+        self.indent = Indent(self.indent.0 - 1);
+        println!("{}}} // struct {}", self.indent, ifc.qualified_name(decl)?);
+        println!();
+        Ok(())
+    }
 
-            DeclSort::FUNCTION | DeclSort::METHOD => {
-                if options.functions {
-                    let func_decl = if member_decl_index.tag() == DeclSort::FUNCTION {
-                        ifc.decl_function().entry(member_decl_index.index())?
-                    } else {
-                        ifc.decl_method().entry(member_decl_index.index())?
-                    };
-                    let func_name = ifc.get_name_string(func_decl.name)?;
-                    let type_str = ifc.get_type_string(func_decl.type_)?;
-                    println!("{}function: {} : {}", indent, func_name, type_str);
-                }
-            }
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, alias: &DeclAlias) -> Result<()> {
+        if self.options.typedefs {
+            let alias_name = ifc.get_string(alias.name)?;
+            println!("{}alias: {}", self.indent, alias_name);
+            println!("{}{:#?}", self.indent, alias);
+        }
+        Ok(())
+    }
 
-            DeclSort::SCOPE => {
-                let nested_scope = ifc.decl_scope().entry(member_decl_index.index())?;
-                let nested_scope_name = ifc.get_name_string(nested_scope.name)?;
-
-                // What kind of scope is it?
-                if ifc.is_type_namespace(nested_scope.ty)? {
-                    // It's a namespace. We always recurse into namespaces.
-                    dump_scope(
-                        ifc,
-                        nested_scope.initializer,
-                        options,
-                        max_depth - 1,
-                        indent.nested(),
-                    )?;
-                } else {
-                    if options.structs {
-                        // It's a nested struct/class.
-                        println!("{}struct {} {{", indent, nested_scope_name);
-                        println!("{}// source location: {:?}", indent.nested(), nested_scope.locus);
-                        dump_scope(
-                            ifc,
-                            nested_scope.initializer,
-                            options,
-                            max_depth - 1,
-                            indent.nested(),
-                        )?;
-                        println!("{}}} // struct {}", indent, nested_scope_name);
-                        println!();
-                    }
-                }
-            }
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, func: &DeclFunc) -> Result<()> {
+        self.dump_function(ifc, decl, func.type_, &func.locus)
+    }
 
-            DeclSort::FIELD => {
-                // If we got here, then we are inside a struct/class scope, and we always want
-                // to show the fields.
-                let field = ifc.decl_field().entry(member_decl_index.index())?;
-                let field_name = ifc.get_string(field.name)?;
-                let field_type_string = ifc.get_type_string(field.ty)?;
-                println!("{}{:-20} {};", indent, field_type_string, field_name);
-            }
+    fn visit_method(&mut self, ifc: &Ifc, decl: DeclIndex, method: &DeclMethod) -> Result<()> {
+        self.dump_function(ifc, decl, method.type_, &method.locus)
+    }
 
-            DeclSort::BITFIELD => {
-                let bitfield = ifc.decl_bitfield().entry(member_decl_index.index())?;
-                let bitfield_name = ifc.get_string(bitfield.name)?;
-                let bitfield_type_string = ifc.get_type_string(bitfield.ty)?;
-                let bitfield_width = ifc.get_literal_expr_u32(bitfield.width)?;
-                println!(
-                    "{}{:-20} {} : {};",
-                    indent, bitfield_type_string, bitfield_name, bitfield_width
-                );
-            }
+    fn visit_constructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        ctor: &DeclConstructor,
+    ) -> Result<()> {
+        if self.options.functions {
+            let ctor_name = ifc.get_string(ctor.name)?;
+            println!(
+                "{}constructor: {}",
+                self.indent,
+                self.declaration(ifc, ctor.type_, ctor_name)?
+            );
+        }
+        Ok(())
+    }
 
-            DeclSort::ENUMERATION => {
-                if options.enums {
-                    let en = ifc.decl_enum().entry(member_decl_index.index())?;
-                    let en_name = ifc.get_string(en.name)?;
-                    println!("{}enum: {}", indent, en_name);
-
-                    for var_index in en.initializer.to_range() {
-                        let var = ifc.decl_enumerator().entry(var_index)?;
-                        let var_name = ifc.get_string(var.name)?;
-                        println!("{}{}", indent, var_name);
-                    }
-                }
-            }
+    fn visit_destructor(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        dtor: &DeclDestructor,
+    ) -> Result<()> {
+        if self.options.functions {
+            let dtor_name = ifc.get_string(dtor.name)?;
+            println!("{}destructor: {}", self.indent, dtor_name);
+        }
+        Ok(())
+    }
 
-            DeclSort::VARIABLE => {
-                // TODO
-            }
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, field: &DeclField) -> Result<()> {
+        // If we got here, then we are inside a struct/class scope, and we always want
+        // to show the fields.
+        self.dump_attributes(ifc, decl)?;
+        let field_name = ifc.get_string(field.name)?;
+        println!(
+            "{}{};",
+            self.indent,
+            self.declaration(ifc, field.ty, field_name)?
+        );
+        Ok(())
+    }
 
-            DeclSort::TEMPLATE => {
-                // TODO
-            }
+    fn visit_bitfield(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        bitfield: &DeclBitfield,
+    ) -> Result<()> {
+        let bitfield_name = ifc.get_string(bitfield.name)?;
+        let bitfield_width = ifc.get_literal_expr_u32(bitfield.width)?;
+        println!(
+            "{}{} : {};",
+            self.indent,
+            self.declaration(ifc, bitfield.ty, bitfield_name)?,
+            bitfield_width
+        );
+        Ok(())
+    }
 
-            DeclSort::EXPLICIT_SPECIALIZATION => {
-                // TODO
-            }
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        if self.options.enums {
+            self.dump_attributes(ifc, decl)?;
+            let en_name = ifc.get_string(en.name)?;
+            println!(
+                "{}enum: {} // {}",
+                self.indent,
+                en_name,
+                format_locus(ifc, &en.locus)
+            );
+        }
+        Ok(self.options.enums)
+    }
 
-            DeclSort::INTRINSIC => {
-                // ignore for now
-            }
+    fn visit_enumerator(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        enumerator: &DeclEnumerator,
+    ) -> Result<()> {
+        let name = ifc.get_string(enumerator.name)?;
+        println!("{}{}", self.indent, name);
+        Ok(())
+    }
 
+    fn visit_other(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        match decl.tag() {
+            DeclSort::EXPLICIT_SPECIALIZATION | DeclSort::INTRINSIC => {
+                // TODO
+            }
             _ => {
                 nyi!();
-                println!("{}unknown decl: {:?}", indent, member_decl_index);
+                println!("{}unknown decl: {:?}", self.indent, decl);
             }
         }
+        Ok(())
     }
-
-    Ok(())
 }
 
 // IFC files contain `command_line` partition, but this is undocumented.
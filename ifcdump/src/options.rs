@@ -54,6 +54,14 @@ pub struct Options {
     #[structopt(long = "wcase")]
     pub wcase: bool,
 
+    /// Verify the checksum in the file header before dumping anything.
+    #[structopt(long = "verify-checksum")]
+    pub verify_checksum: bool,
+
+    /// Check that all of the indices in the file are valid, and report any problems.
+    #[structopt(long = "validate")]
+    pub validate: bool,
+
     #[structopt(long = "summary")]
     pub summary: bool,
 
@@ -1,10 +1,29 @@
 use super::*;
 
 pub fn dump_summary(ifc: &Ifc) -> Result<()> {
-    let unit = ifc.file_header().unit;
-    println!("Unit = {} 0x{:x}", unit, unit);
+    let info = ifc.module_info()?;
+    match info.unit_name() {
+        Some(name) => println!("Unit = {:?} {}", info.sort, name),
+        None => println!("Unit = {:?}", info.sort),
+    }
+    for import in info.imports.iter() {
+        println!(
+            "{} {}{}",
+            if import.exported {
+                "Exports"
+            } else {
+                "Imports"
+            },
+            import.unit_name(),
+            if import.is_header_unit() {
+                " (header unit)"
+            } else {
+                ""
+            }
+        );
+    }
     let mut totals = Totals::default();
-    count_totals_in_scope(ifc, &mut totals, ifc.global_scope())?;
+    ifc.visit(&mut totals)?;
 
     totals.object_macros = ifc.macro_object_like().entries.len() as u64;
     totals.function_macros = ifc.macro_function_like().entries.len() as u64;
@@ -17,55 +36,78 @@ fn show_totals(totals: &Totals) {
     println!("{:#?}", totals);
 }
 
-// scope is zero-based
-fn count_totals_in_scope(ifc: &Ifc, totals: &mut Totals, scope: ScopeIndex) -> Result<()> {
-    let scope_descriptor = ifc.scope_desc().entry(scope - 1)?;
-    let scope_members = ifc.scope_member();
-
-    for member_index in
-        scope_descriptor.start..scope_descriptor.start + scope_descriptor.cardinality
-    {
-        let member_decl_index = scope_members.entry(member_index)?;
-
-        match member_decl_index.tag() {
-            DeclSort::ALIAS => totals.typedefs += 1,
-            DeclSort::FUNCTION => totals.functions += 1,
-            DeclSort::METHOD => totals.methods += 1,
-            DeclSort::ENUMERATION => totals.enums += 1,
-            DeclSort::FIELD => totals.fields += 1,
-            DeclSort::VARIABLE => totals.variables += 1,
-            DeclSort::TEMPLATE => totals.templates += 1,
-            DeclSort::INTRINSIC => totals.intrinsics += 1,
-            DeclSort::BITFIELD => totals.bitfields += 1,
-            DeclSort::EXPLICIT_SPECIALIZATION => totals.explicit_specialization += 1,
-
-            DeclSort::SCOPE => {
-                let nested_scope = ifc.decl_scope().entry(member_decl_index.index())?;
-                let nested_scope_name = ifc.get_string(nested_scope.name.index())?;
-
-                // What kind of scope is it?
-                if ifc.is_type_namespace(nested_scope.ty)? {
-                    totals.namespaces += 1;
-                    if nested_scope.initializer != 0 {
-                        count_totals_in_scope(ifc, totals, nested_scope.initializer)?;
-                    }
-                } else {
-                    // It's a struct/class.
-                    totals.structs += 1;
-                    if nested_scope.initializer != 0 {
-                        count_totals_in_scope(ifc, totals, nested_scope.initializer)?;
-                    }
-                }
-            }
+impl DeclVisitor for Totals {
+    fn visit_namespace(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        self.namespaces += 1;
+        Ok(true)
+    }
+
+    fn visit_class(&mut self, ifc: &Ifc, decl: DeclIndex, scope: &DeclScope) -> Result<bool> {
+        self.structs += 1;
+        Ok(true)
+    }
+
+    fn visit_enum(&mut self, ifc: &Ifc, decl: DeclIndex, en: &DeclEnum) -> Result<bool> {
+        self.enums += 1;
+        Ok(false)
+    }
+
+    fn visit_alias(&mut self, ifc: &Ifc, decl: DeclIndex, alias: &DeclAlias) -> Result<()> {
+        self.typedefs += 1;
+        Ok(())
+    }
+
+    fn visit_function(&mut self, ifc: &Ifc, decl: DeclIndex, func: &DeclFunc) -> Result<()> {
+        self.functions += 1;
+        Ok(())
+    }
 
+    fn visit_method(&mut self, ifc: &Ifc, decl: DeclIndex, method: &DeclMethod) -> Result<()> {
+        self.methods += 1;
+        Ok(())
+    }
+
+    fn visit_field(&mut self, ifc: &Ifc, decl: DeclIndex, field: &DeclField) -> Result<()> {
+        self.fields += 1;
+        Ok(())
+    }
+
+    fn visit_bitfield(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        bitfield: &DeclBitfield,
+    ) -> Result<()> {
+        self.bitfields += 1;
+        Ok(())
+    }
+
+    fn visit_variable(&mut self, ifc: &Ifc, decl: DeclIndex, var: &DeclVar) -> Result<()> {
+        self.variables += 1;
+        Ok(())
+    }
+
+    fn visit_template(
+        &mut self,
+        ifc: &Ifc,
+        decl: DeclIndex,
+        template: &DeclTemplate,
+    ) -> Result<()> {
+        self.templates += 1;
+        Ok(())
+    }
+
+    fn visit_other(&mut self, ifc: &Ifc, decl: DeclIndex) -> Result<()> {
+        match decl.tag() {
+            DeclSort::INTRINSIC => self.intrinsics += 1,
+            DeclSort::EXPLICIT_SPECIALIZATION => self.explicit_specialization += 1,
             _ => {
-                println!("unknown: {:?}", member_decl_index);
-                totals.unknown += 1;
+                println!("unknown: {:?}", decl);
+                self.unknown += 1;
             }
         }
+        Ok(())
     }
-
-    Ok(())
 }
 
 #[derive(Default, Debug, Clone)]